@@ -7,6 +7,7 @@
 //! - [`NodeConfig`] — Configuration for individual pipeline nodes
 //! - [`EdgeConfig`] — Connections between nodes with routing behavior
 //! - [`NodeType`] and [`EdgeType`] — Available node and edge types
+//! - [`RateLimits`] — Concurrency and token budgets per model and provider
 //! - [`PresetRegistry`] — Load pipeline presets from JSON files
 //!
 //! # Loading from JSON
@@ -40,6 +41,9 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 use std::str::FromStr;
+use std::time::Duration;
+
+use fissio_core::ErrorKind;
 
 use serde::{Deserialize, Serialize};
 
@@ -107,6 +111,10 @@ impl ConfigError {
 /// | `Evaluator` | Quality scoring |
 /// | `Synthesizer` | Synthesizes inputs |
 /// | `Coordinator` | Distributes to workers |
+/// | `Custom` | Runs an executor registered with the engine (`config.executor`) |
+/// | `Approval` | Pauses the run until a human approves, edits, or rejects its input |
+/// | `Pipeline` | Runs another pipeline (`config.pipeline`) as a nested run |
+/// | `Map` | Runs a node or pipeline once per item of a list in its input |
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum NodeType {
@@ -128,6 +136,14 @@ pub enum NodeType {
     Synthesizer,
     /// Evaluates quality of outputs.
     Evaluator,
+    /// Runs a user-registered executor named by `config.executor`.
+    Custom,
+    /// Suspends the run until a human decides on its input.
+    Approval,
+    /// Runs the pipeline named by `config.pipeline` as a nested run.
+    Pipeline,
+    /// Runs the node or pipeline named in its config once per item of its input.
+    Map,
 }
 
 impl FromStr for NodeType {
@@ -144,6 +160,10 @@ impl FromStr for NodeType {
             "worker" => Ok(Self::Worker),
             "synthesizer" => Ok(Self::Synthesizer),
             "evaluator" => Ok(Self::Evaluator),
+            "custom" => Ok(Self::Custom),
+            "approval" => Ok(Self::Approval),
+            "pipeline" => Ok(Self::Pipeline),
+            "map" => Ok(Self::Map),
             _ => Err(()),
         }
     }
@@ -161,6 +181,10 @@ impl std::fmt::Display for NodeType {
             Self::Worker => "worker",
             Self::Synthesizer => "synthesizer",
             Self::Evaluator => "evaluator",
+            Self::Custom => "custom",
+            Self::Approval => "approval",
+            Self::Pipeline => "pipeline",
+            Self::Map => "map",
         };
         write!(f, "{}", s)
     }
@@ -169,7 +193,12 @@ impl std::fmt::Display for NodeType {
 impl NodeType {
     /// Returns `true` if this node type makes an LLM call.
     pub fn requires_llm(&self) -> bool {
-        matches!(self, NodeType::Llm | NodeType::Worker)
+        matches!(self, NodeType::Llm | NodeType::Worker | NodeType::Evaluator)
+    }
+
+    /// Returns `true` if this node type merges the outputs of several upstream nodes.
+    pub fn is_aggregator(&self) -> bool {
+        matches!(self, NodeType::Aggregator | NodeType::Synthesizer)
     }
 
     /// Returns `true` if this node type performs routing decisions.
@@ -190,6 +219,10 @@ impl NodeType {
             NodeType::Synthesizer => "Synthesizing",
             NodeType::Worker => "Worker executing",
             NodeType::Evaluator => "Evaluating",
+            NodeType::Custom => "Running custom executor",
+            NodeType::Approval => "Awaiting approval",
+            NodeType::Pipeline => "Running sub-pipeline",
+            NodeType::Map => "Mapping over items",
         }
     }
 }
@@ -202,6 +235,7 @@ impl NodeType {
 /// | `Parallel` | Concurrent execution of targets |
 /// | `Conditional` | Router chooses which path to follow |
 /// | `Dynamic` | Orchestrator dynamically selects targets |
+/// | `Feedback` | Loops back for another iteration until the source passes |
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum EdgeType {
@@ -214,6 +248,11 @@ pub enum EdgeType {
     Conditional,
     /// Concurrent execution of all targets.
     Parallel,
+    /// Loops back to re-run the target subgraph with the source's critique.
+    ///
+    /// Followed only when the source node does not pass, up to
+    /// [`EdgeConfig::max_iterations`] times.
+    Feedback,
 }
 
 impl FromStr for EdgeType {
@@ -224,6 +263,7 @@ impl FromStr for EdgeType {
             "parallel" => Ok(Self::Parallel),
             "dynamic" => Ok(Self::Dynamic),
             "conditional" => Ok(Self::Conditional),
+            "feedback" => Ok(Self::Feedback),
             _ => Ok(Self::Direct),
         }
     }
@@ -236,6 +276,7 @@ impl std::fmt::Display for EdgeType {
             Self::Dynamic => "dynamic",
             Self::Conditional => "conditional",
             Self::Parallel => "parallel",
+            Self::Feedback => "feedback",
         };
         write!(f, "{}", s)
     }
@@ -264,12 +305,104 @@ pub struct NodeConfig {
     /// Observability configuration for this node (enabled by default).
     #[serde(default = "default_observe")]
     pub observe: Option<fissio_monitor::ObserveConfig>,
+    /// Retry policy for failed executions (a single attempt per model when unset).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+    /// Model IDs to try in order once the primary model has exhausted its attempts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_models: Vec<String>,
+    /// Time limit for each attempt, in milliseconds (unbounded when unset).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// JSON Schema the node's output must match (`llm` and `worker` nodes).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<serde_json::Value>,
 }
 
 fn default_observe() -> Option<fissio_monitor::ObserveConfig> {
     Some(fissio_monitor::ObserveConfig::new())
 }
 
+/// How a node retries failed executions.
+///
+/// Each model in the node's chain (its model, then `fallback_models`) gets up
+/// to `max_attempts` tries. The delay before retry `n` is
+/// `backoff_ms * backoff_multiplier^(n-1)`, capped at `max_backoff_ms`.
+/// Errors whose kind isn't listed in `retry_on` fail the node immediately.
+///
+/// ```json
+/// { "max_attempts": 3, "backoff_ms": 500, "retry_on": ["llm", "external_api"] }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Attempts per model, including the first.
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Delay before the first retry, in milliseconds.
+    #[serde(default = "default_backoff_ms")]
+    pub backoff_ms: u64,
+    /// Factor applied to the delay after each retry.
+    #[serde(default = "default_backoff_multiplier")]
+    pub backoff_multiplier: f64,
+    /// Upper bound on the delay, in milliseconds.
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// Error kinds that are retried.
+    #[serde(default = "default_retry_on")]
+    pub retry_on: Vec<ErrorKind>,
+}
+
+fn default_max_attempts() -> u32 {
+    3
+}
+
+fn default_backoff_ms() -> u64 {
+    500
+}
+
+fn default_backoff_multiplier() -> f64 {
+    2.0
+}
+
+fn default_max_backoff_ms() -> u64 {
+    30_000
+}
+
+fn default_retry_on() -> Vec<ErrorKind> {
+    vec![ErrorKind::Llm, ErrorKind::ExternalApi]
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            backoff_ms: default_backoff_ms(),
+            backoff_multiplier: default_backoff_multiplier(),
+            max_backoff_ms: default_max_backoff_ms(),
+            retry_on: default_retry_on(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy with one attempt per model, used for nodes without a `retry` setting.
+    pub fn single_attempt() -> Self {
+        Self { max_attempts: 1, ..Self::default() }
+    }
+
+    /// Returns `true` if errors of this kind should be retried.
+    pub fn retries(&self, kind: ErrorKind) -> bool {
+        self.retry_on.contains(&kind)
+    }
+
+    /// Returns the delay before retry `n` (1-based) on the same model.
+    pub fn backoff(&self, n: u32) -> Duration {
+        let factor = self.backoff_multiplier.max(1.0).powi(n.saturating_sub(1) as i32);
+        let ms = (self.backoff_ms as f64 * factor).min(self.max_backoff_ms as f64);
+        Duration::from_millis(ms as u64)
+    }
+}
+
 /// Configuration for an edge connecting nodes.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EdgeConfig {
@@ -280,6 +413,131 @@ pub struct EdgeConfig {
     /// How this edge should be traversed.
     #[serde(default)]
     pub edge_type: EdgeType,
+    /// Maximum loop iterations for feedback edges (defaults to
+    /// [`DEFAULT_MAX_FEEDBACK_ITERATIONS`] when unset).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_iterations: Option<u32>,
+    /// How failing targets of a parallel or dynamic edge affect the run.
+    #[serde(default, skip_serializing_if = "FailurePolicy::is_fail_fast")]
+    pub on_failure: FailurePolicy,
+    /// Condition the engine checks before following this edge (always followed when unset).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<Condition>,
+    /// Lets the targets run without waiting for this edge's sources; their
+    /// outputs are included only if they finished first.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
+}
+
+/// How a parallel or dynamic edge handles branches that fail.
+///
+/// Failed branches that are tolerated don't continue down their own edges;
+/// aggregators downstream see them as labelled failures with the error.
+///
+/// ```json
+/// { "from": "planner", "to": ["web", "papers", "news"], "edge_type": "parallel", "on_failure": { "quorum": 2 } }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    /// The first failure fails the run and cancels the other branches (default).
+    #[default]
+    FailFast,
+    /// Continue with whichever branches succeeded; fails only if all of them failed.
+    Continue,
+    /// Continue if at least this many branches succeeded.
+    Quorum(usize),
+}
+
+impl FailurePolicy {
+    /// Returns `true` for the default fail-fast policy.
+    pub fn is_fail_fast(&self) -> bool {
+        *self == Self::FailFast
+    }
+}
+
+/// A deterministic check on an edge, evaluated by the engine without an LLM call.
+///
+/// Every rule that is set must hold. `regex`, `keywords`, and the length
+/// bounds test the text the edge carries (the source node's output, or the
+/// user input for edges from `input`). `path` names a field of an upstream
+/// structured output as `<node_id>.<field>...`; the comparisons after it
+/// test that field, and with none of them set the field must be present and
+/// not `null` or `false`.
+///
+/// On a router's edges, conditions are rules checked against the router's
+/// input before its LLM classification, which runs only if none match.
+///
+/// ```json
+/// { "from": "triage", "to": "billing", "when": { "keywords": ["refund", "invoice"] } }
+/// { "from": "extract", "to": "escalate", "when": { "path": "extract.priority", "min": 4 } }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Condition {
+    /// Regular expression the text must match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regex: Option<String>,
+    /// Words or phrases, at least one of which the text must contain (case-insensitive).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    /// Minimum length of the text, in characters.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<usize>,
+    /// Maximum length of the text, in characters.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+    /// Field of an upstream output, as `<node_id>.<field>...`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Value the field must equal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equals: Option<serde_json::Value>,
+    /// Values, one of which the field must equal.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub one_of: Vec<serde_json::Value>,
+    /// Lower bound on a numeric field (inclusive).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    /// Upper bound on a numeric field (inclusive).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+}
+
+impl Condition {
+    /// A condition matching text that contains any of `keywords`.
+    pub fn keywords<S: Into<String>>(keywords: impl IntoIterator<Item = S>) -> Self {
+        Self { keywords: keywords.into_iter().map(Into::into).collect(), ..Self::default() }
+    }
+
+    /// A condition matching text that matches `pattern`.
+    pub fn regex(pattern: impl Into<String>) -> Self {
+        Self { regex: Some(pattern.into()), ..Self::default() }
+    }
+
+    /// A condition matching when the field at `path` equals `value`.
+    pub fn field_equals(path: impl Into<String>, value: impl Into<serde_json::Value>) -> Self {
+        Self { path: Some(path.into()), equals: Some(value.into()), ..Self::default() }
+    }
+
+    /// Returns `true` if any of the comparisons on `path` is set.
+    pub fn compares_field(&self) -> bool {
+        self.equals.is_some() || !self.one_of.is_empty() || self.min.is_some() || self.max.is_some()
+    }
+}
+
+/// Default number of times a feedback edge may be followed in one run.
+pub const DEFAULT_MAX_FEEDBACK_ITERATIONS: u32 = 3;
+
+impl EdgeConfig {
+    /// Returns `true` if this is a feedback (loop) edge.
+    pub fn is_feedback(&self) -> bool {
+        self.edge_type == EdgeType::Feedback
+    }
+
+    /// Returns the iteration cap for this edge, applying the default for feedback edges.
+    pub fn max_iterations(&self) -> u32 {
+        self.max_iterations.unwrap_or(DEFAULT_MAX_FEEDBACK_ITERATIONS)
+    }
 }
 
 /// An edge endpoint: either a single node ID or multiple node IDs.
@@ -384,6 +642,15 @@ pub struct PipelineConfig {
     pub nodes: Vec<NodeConfig>,
     /// The edges connecting nodes.
     pub edges: Vec<EdgeConfig>,
+    /// Pipeline-level values available to prompt templates as `{{vars.<name>}}`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub variables: HashMap<String, String>,
+    /// Time limit for the whole run, including a streamed response, in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Maximum number of nodes that may execute at once within a run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<usize>,
 }
 
 impl PipelineConfig {
@@ -411,6 +678,60 @@ impl PipelineConfig {
     }
 }
 
+// ============================================================================
+// Rate Limits
+// ============================================================================
+
+/// Limits on the LLM calls made through one model or provider.
+///
+/// ```json
+/// { "max_concurrency": 2, "tokens_per_minute": 200000 }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// Maximum number of node attempts using it at once.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<usize>,
+    /// Input plus output tokens allowed over any trailing minute.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens_per_minute: Option<u64>,
+}
+
+/// Rate limits keyed by model ID and by provider.
+///
+/// A provider is the model's `api_base` when set (e.g. a local Ollama host),
+/// otherwise `"openai"` or `"anthropic"` as detected from the model name.
+/// A call counts against both its model's and its provider's limits.
+///
+/// ```json
+/// {
+///   "models": { "openai-gpt5": { "max_concurrency": 4 } },
+///   "providers": {
+///     "anthropic": { "tokens_per_minute": 400000 },
+///     "http://localhost:11434/v1": { "max_concurrency": 1 }
+///   }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimits {
+    /// Limits keyed by model ID.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub models: HashMap<String, RateLimit>,
+    /// Limits keyed by provider.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub providers: HashMap<String, RateLimit>,
+}
+
+impl RateLimits {
+    /// Loads rate limits from a JSON file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .map_err(|e| ConfigError::io(path.display().to_string(), e))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
 // ============================================================================
 // Builder API
 // ============================================================================
@@ -425,6 +746,9 @@ pub struct PipelineBuilder {
     description: String,
     nodes: Vec<NodeConfig>,
     edges: Vec<EdgeConfig>,
+    variables: HashMap<String, String>,
+    timeout_ms: Option<u64>,
+    max_concurrency: Option<usize>,
 }
 
 impl PipelineBuilder {
@@ -436,6 +760,9 @@ impl PipelineBuilder {
             description: String::new(),
             nodes: Vec::new(),
             edges: Vec::new(),
+            variables: HashMap::new(),
+            timeout_ms: None,
+            max_concurrency: None,
         }
     }
 
@@ -445,6 +772,24 @@ impl PipelineBuilder {
         self
     }
 
+    /// Sets a pipeline-level template variable.
+    pub fn variable(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.variables.insert(name.into(), value.into());
+        self
+    }
+
+    /// Sets the time limit for the whole run, in milliseconds.
+    pub fn timeout_ms(mut self, timeout_ms: u64) -> Self {
+        self.timeout_ms = Some(timeout_ms);
+        self
+    }
+
+    /// Sets the maximum number of nodes that may execute at once.
+    pub fn max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = Some(max_concurrency);
+        self
+    }
+
     /// Starts building a new node with the given ID and type.
     pub fn node(self, id: impl Into<String>, node_type: NodeType) -> NodeBuilder {
         NodeBuilder::new(self, id.into(), node_type)
@@ -456,6 +801,10 @@ impl PipelineBuilder {
             from: EdgeEndpoint::Single(from.into()),
             to: EdgeEndpoint::Single(to.into()),
             edge_type: EdgeType::Direct,
+            max_iterations: None,
+            on_failure: FailurePolicy::FailFast,
+            when: None,
+            optional: false,
         });
         self
     }
@@ -471,6 +820,10 @@ impl PipelineBuilder {
             from: EdgeEndpoint::Single(from.into()),
             to: EdgeEndpoint::Single(to.into()),
             edge_type,
+            max_iterations: None,
+            on_failure: FailurePolicy::FailFast,
+            when: None,
+            optional: false,
         });
         self
     }
@@ -481,6 +834,24 @@ impl PipelineBuilder {
             from: EdgeEndpoint::Single(from.into()),
             to: EdgeEndpoint::Multiple(to.iter().map(|s| s.to_string()).collect()),
             edge_type: EdgeType::Parallel,
+            max_iterations: None,
+            on_failure: FailurePolicy::FailFast,
+            when: None,
+            optional: false,
+        });
+        self
+    }
+
+    /// Adds a parallel edge whose failing targets are handled by `policy`.
+    pub fn parallel_edge_with_policy(mut self, from: impl Into<String>, to: &[&str], policy: FailurePolicy) -> Self {
+        self.edges.push(EdgeConfig {
+            from: EdgeEndpoint::Single(from.into()),
+            to: EdgeEndpoint::Multiple(to.iter().map(|s| s.to_string()).collect()),
+            edge_type: EdgeType::Parallel,
+            max_iterations: None,
+            on_failure: policy,
+            when: None,
+            optional: false,
         });
         self
     }
@@ -491,6 +862,58 @@ impl PipelineBuilder {
             from: EdgeEndpoint::Single(from.into()),
             to: EdgeEndpoint::Multiple(to.iter().map(|s| s.to_string()).collect()),
             edge_type: EdgeType::Conditional,
+            max_iterations: None,
+            on_failure: FailurePolicy::FailFast,
+            when: None,
+            optional: false,
+        });
+        self
+    }
+
+    /// Adds an edge that is followed only when `condition` holds.
+    pub fn edge_when(mut self, from: impl Into<String>, to: impl Into<String>, condition: Condition) -> Self {
+        self.edges.push(EdgeConfig {
+            from: EdgeEndpoint::Single(from.into()),
+            to: EdgeEndpoint::Single(to.into()),
+            edge_type: EdgeType::Direct,
+            max_iterations: None,
+            on_failure: FailurePolicy::FailFast,
+            when: Some(condition),
+            optional: false,
+        });
+        self
+    }
+
+    /// Adds an edge whose target doesn't wait for `from` to finish.
+    pub fn optional_edge(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.edges.push(EdgeConfig {
+            from: EdgeEndpoint::Single(from.into()),
+            to: EdgeEndpoint::Single(to.into()),
+            edge_type: EdgeType::Direct,
+            max_iterations: None,
+            on_failure: FailurePolicy::FailFast,
+            when: None,
+            optional: true,
+        });
+        self
+    }
+
+    /// Adds a feedback edge that loops back to `to` until `from` passes,
+    /// at most `max_iterations` times.
+    pub fn feedback_edge(
+        mut self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+        max_iterations: u32,
+    ) -> Self {
+        self.edges.push(EdgeConfig {
+            from: EdgeEndpoint::Single(from.into()),
+            to: EdgeEndpoint::Single(to.into()),
+            edge_type: EdgeType::Feedback,
+            max_iterations: Some(max_iterations),
+            on_failure: FailurePolicy::FailFast,
+            when: None,
+            optional: false,
         });
         self
     }
@@ -503,6 +926,9 @@ impl PipelineBuilder {
             description: self.description,
             nodes: self.nodes,
             edges: self.edges,
+            variables: self.variables,
+            timeout_ms: self.timeout_ms,
+            max_concurrency: self.max_concurrency,
         }
     }
 
@@ -526,6 +952,10 @@ pub struct NodeBuilder {
     tools: Vec<String>,
     config: serde_json::Value,
     observe: Option<fissio_monitor::ObserveConfig>,
+    retry: Option<RetryPolicy>,
+    fallback_models: Vec<String>,
+    timeout_ms: Option<u64>,
+    output_schema: Option<serde_json::Value>,
 }
 
 impl NodeBuilder {
@@ -539,6 +969,10 @@ impl NodeBuilder {
             tools: Vec::new(),
             config: serde_json::Value::Null,
             observe: Some(fissio_monitor::ObserveConfig::new()),
+            retry: None,
+            fallback_models: Vec::new(),
+            timeout_ms: None,
+            output_schema: None,
         }
     }
 
@@ -570,6 +1004,34 @@ impl NodeBuilder {
         self
     }
 
+    /// Sets the retry policy for this node.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Sets the models to fall back to, in order, when the primary keeps failing.
+    pub fn fallback_models<I, S>(mut self, models: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.fallback_models = models.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the time limit for each attempt of this node, in milliseconds.
+    pub fn timeout_ms(mut self, timeout_ms: u64) -> Self {
+        self.timeout_ms = Some(timeout_ms);
+        self
+    }
+
+    /// Sets the JSON Schema this node's output must match.
+    pub fn output_schema(mut self, schema: serde_json::Value) -> Self {
+        self.output_schema = Some(schema);
+        self
+    }
+
     /// Enables observability with default settings.
     pub fn observe(mut self) -> Self {
         self.observe = Some(fissio_monitor::ObserveConfig::new());
@@ -592,6 +1054,10 @@ impl NodeBuilder {
             tools: self.tools,
             config: self.config,
             observe: self.observe,
+            retry: self.retry,
+            fallback_models: self.fallback_models,
+            timeout_ms: self.timeout_ms,
+            output_schema: self.output_schema,
         };
         self.pipeline.add_node(node)
     }
@@ -612,7 +1078,7 @@ impl NodeBuilder {
 ///     println!("Found preset: {}", id);
 /// }
 /// ```
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct PresetRegistry {
     presets: HashMap<String, PipelineConfig>,
 }
@@ -645,6 +1111,13 @@ impl PresetRegistry {
         Ok(registry)
     }
 
+    /// Registers a pipeline under its ID.
+    ///
+    /// If a pipeline with the same ID already exists, it will be replaced.
+    pub fn register(&mut self, config: PipelineConfig) {
+        self.presets.insert(config.id.clone(), config);
+    }
+
     /// Gets a preset by ID.
     pub fn get(&self, id: &str) -> Option<&PipelineConfig> {
         self.presets.get(id)
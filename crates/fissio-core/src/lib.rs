@@ -3,6 +3,7 @@
 //! This crate provides the fundamental types shared across the fissio framework:
 //!
 //! - [`AgentError`] — Error type for pipeline and LLM operations
+//! - [`ErrorKind`] — Category of an [`AgentError`], used by retry policies
 //! - [`Message`] and [`MessageRole`] — Conversation message types
 //! - [`ModelConfig`] — LLM model configuration
 //! - [`ToolCall`], [`ToolResult`], [`ToolSchema`] — Tool interaction types
@@ -47,9 +48,9 @@ pub enum AgentError {
     #[error("External API error: {0}")]
     ExternalApi(String),
 
-    /// Maximum retry attempts exceeded.
-    #[error("Max retries exceeded")]
-    MaxRetriesExceeded,
+    /// Maximum retry attempts exceeded; `last` is the final attempt's error.
+    #[error("Max retries exceeded after {attempts} attempts: {last}")]
+    MaxRetriesExceeded { attempts: u32, last: Box<AgentError> },
 
     /// Unknown worker type specified.
     #[error("Unknown worker type: {0}")]
@@ -58,6 +59,58 @@ pub enum AgentError {
     /// WebSocket communication error.
     #[error("WebSocket error: {0}")]
     WebSocket(String),
+
+    /// Execution exceeded a node or pipeline timeout.
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    /// Execution was cancelled by the caller.
+    #[error("Execution cancelled")]
+    Cancelled,
+
+    /// A run checkpoint couldn't be saved, loaded, or resumed.
+    #[error("Checkpoint error: {0}")]
+    Checkpoint(String),
+
+    /// A replayed run made a call that has no recorded response.
+    #[error("Replay error: {0}")]
+    Replay(String),
+}
+
+/// Broad category of an [`AgentError`], used to decide which failures are retryable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Llm,
+    Parse,
+    Worker,
+    ExternalApi,
+    MaxRetries,
+    UnknownWorker,
+    WebSocket,
+    Timeout,
+    Cancelled,
+    Checkpoint,
+    Replay,
+}
+
+impl AgentError {
+    /// Returns the category of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            AgentError::LlmError(_) => ErrorKind::Llm,
+            AgentError::ParseError(_) => ErrorKind::Parse,
+            AgentError::WorkerFailed(_) => ErrorKind::Worker,
+            AgentError::ExternalApi(_) => ErrorKind::ExternalApi,
+            AgentError::MaxRetriesExceeded { .. } => ErrorKind::MaxRetries,
+            AgentError::UnknownWorker(_) => ErrorKind::UnknownWorker,
+            AgentError::WebSocket(_) => ErrorKind::WebSocket,
+            AgentError::Timeout(_) => ErrorKind::Timeout,
+            AgentError::Cancelled => ErrorKind::Cancelled,
+            AgentError::Checkpoint(_) => ErrorKind::Checkpoint,
+            AgentError::Replay(_) => ErrorKind::Replay,
+        }
+    }
 }
 
 impl From<serde_json::Error> for AgentError {
@@ -36,7 +36,7 @@ type Props = {
   onSave?: (config: PipelineInfo) => void;
 };
 
-const NODE_TYPES = ['llm', 'worker', 'coordinator', 'aggregator', 'orchestrator', 'synthesizer', 'router', 'gate', 'evaluator'];
+const NODE_TYPES = ['llm', 'worker', 'coordinator', 'aggregator', 'orchestrator', 'synthesizer', 'router', 'gate', 'evaluator', 'custom', 'approval', 'pipeline', 'map'];
 const EDGE_TYPES = ['direct', 'conditional', 'dynamic', 'feedback'];
 
 const NODE_COLORS: Record<string, string> = {
@@ -49,6 +49,9 @@ const NODE_COLORS: Record<string, string> = {
   aggregator: '#ec4899',
   coordinator: '#06b6d4',
   evaluator: '#eab308',
+  approval: '#ef4444',
+  pipeline: '#14b8a6',
+  map: '#a855f7',
   input: '#6b7280',
   output: '#6b7280'
 };
@@ -42,9 +42,12 @@ Available node types:
 - aggregator: Combines outputs from multiple nodes
 - orchestrator: Dynamic task decomposition and worker dispatch
 - synthesizer: Synthesizes multiple inputs into coherent output
-- router: Routes input to one of several paths based on classification
+- router: Routes input to one of several paths based on classification (config: "multi_label" to follow every matching path, "min_confidence" from 0 to 1, "fallback" target when nothing matches)
 - gate: Checkpoint that validates before proceeding
 - evaluator: Evaluates output quality, can trigger feedback loops
+- approval: Pauses the run until a human approves, edits, or rejects its input
+- pipeline: Runs another saved or preset pipeline (config.pipeline) as a single node
+- map: Runs an unconnected node (config.node) or a pipeline (config.pipeline) once per item of a JSON array or line list in its input
 
 Available edge types:
 - direct: Standard flow from one node to next
@@ -81,6 +84,9 @@ When the user says "/done" or indicates they're satisfied, output the final conf
 \`\`\`
 
 The "tools" field is optional - only include it for nodes that need external capabilities.
+An llm or worker node may set "output_schema" (a JSON Schema) to make its output structured JSON; later prompts can then reference fields as {{nodes.<id>.<field>}}, and a router with "config": { "field": "<field>" } routes on a field's value.
+An edge may set "when" to be followed only if a condition holds, checked without an LLM call: "regex", "keywords" (any, case-insensitive), "min_length"/"max_length" of the text it carries, or "path": "<node_id>.<field>" with "equals", "one_of", "min", or "max". On a router's edges these are rules tried before its LLM classification.
+A node with several incoming edges waits for all of them (branches a router or condition skipped don't count), and nodes whose inputs are ready run concurrently. Set "optional": true on an edge so its target doesn't wait for that source.
 Always include edges from "input" to the first node(s) and from final node(s) to "output".`;
   }
 
@@ -207,13 +213,25 @@ Always include edges from "input" to the first node(s) and from final node(s) to
         type: n.node_type,
         model: n.model,
         prompt: n.prompt,
-        tools: n.tools
+        tools: n.tools,
+        config: n.config,
+        retry: n.retry,
+        fallback_models: n.fallback_models,
+        timeout_ms: n.timeout_ms,
+        output_schema: n.output_schema
       })),
       edges: config.edges.map((e) => ({
         from: e.from,
         to: e.to,
-        edge_type: e.edge_type
-      }))
+        edge_type: e.edge_type,
+        max_iterations: e.max_iterations,
+        on_failure: e.on_failure,
+        when: e.when,
+        optional: e.optional
+      })),
+      variables: config.variables,
+      timeout_ms: config.timeout_ms,
+      max_concurrency: config.max_concurrency
     };
   }
 
@@ -364,7 +382,10 @@ Always include edges from "input" to the first node(s) and from final node(s) to
       description: config.description,
       nodes: config.nodes,
       edges: config.edges,
-      layout: config.layout
+      layout: config.layout,
+      variables: config.variables,
+      timeout_ms: config.timeout_ms,
+      max_concurrency: config.max_concurrency
     };
     console.log('[save] Sending save request:', config.id, config.name);
     console.log('[save] nodes with positions:', config.nodes.map(n => ({ id: n.id, x: n.x, y: n.y })));
@@ -27,14 +27,45 @@ export type NodeInfo = {
 	model: string | null;
 	prompt: string | null;
 	tools?: string[];
+	config?: Record<string, unknown>;
+	retry?: RetryPolicy;
+	fallback_models?: string[];
+	timeout_ms?: number;
+	output_schema?: Record<string, unknown>;
 	x?: number;
 	y?: number;
 };
 
+export type RetryPolicy = {
+	max_attempts?: number;
+	backoff_ms?: number;
+	backoff_multiplier?: number;
+	max_backoff_ms?: number;
+	retry_on?: string[];
+};
+
+export type FailurePolicy = 'fail_fast' | 'continue' | { quorum: number };
+
+export type EdgeCondition = {
+	regex?: string;
+	keywords?: string[];
+	min_length?: number;
+	max_length?: number;
+	path?: string;
+	equals?: unknown;
+	one_of?: unknown[];
+	min?: number;
+	max?: number;
+};
+
 export type EdgeInfo = {
 	from: string | string[];
 	to: string | string[];
 	edge_type?: string;
+	max_iterations?: number;
+	on_failure?: FailurePolicy;
+	when?: EdgeCondition;
+	optional?: boolean;
 };
 
 export type PipelineInfo = {
@@ -44,6 +75,9 @@ export type PipelineInfo = {
 	nodes: NodeInfo[];
 	edges: EdgeInfo[];
 	layout?: Record<string, { x: number; y: number }>; // positions for input/output virtual nodes
+	variables?: Record<string, string>;
+	timeout_ms?: number;
+	max_concurrency?: number;
 };
 
 export type RuntimeNodeConfig = {
@@ -52,12 +86,21 @@ export type RuntimeNodeConfig = {
 	model?: string | null;
 	prompt?: string | null;
 	tools?: string[];
+	config?: Record<string, unknown>;
+	retry?: RetryPolicy;
+	fallback_models?: string[];
+	timeout_ms?: number;
+	output_schema?: Record<string, unknown>;
 };
 
 export type RuntimeEdgeConfig = {
 	from: string | string[];
 	to: string | string[];
 	edge_type?: string;
+	max_iterations?: number;
+	on_failure?: FailurePolicy;
+	when?: EdgeCondition;
+	optional?: boolean;
 };
 
 export type RuntimePipelineConfig = {
@@ -65,6 +108,9 @@ export type RuntimePipelineConfig = {
 	name?: string;
 	nodes: RuntimeNodeConfig[];
 	edges: RuntimeEdgeConfig[];
+	variables?: Record<string, string>;
+	timeout_ms?: number;
+	max_concurrency?: number;
 };
 
 export type ChatMsg = {
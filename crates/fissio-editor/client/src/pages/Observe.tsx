@@ -16,6 +16,7 @@ interface TraceRecord {
   total_output_tokens: number;
   total_tool_calls: number;
   status: string;
+  resumed_from?: string;
 }
 
 interface SpanRecord {
@@ -31,6 +32,10 @@ interface SpanRecord {
   output_tokens: number;
   tool_call_count: number;
   iteration_count: number;
+  score?: number | null;
+  attempt?: number;
+  model?: string | null;
+  error?: string | null;
 }
 
 interface MetricsSummary {
@@ -195,6 +200,10 @@ export default function Observe() {
                     <span>{detail().trace.pipeline_name}</span>
                     <span class="trace-info-label">Status</span>
                     <span class={`status-badge ${detail().trace.status}`}>{detail().trace.status}</span>
+                    <Show when={detail().trace.resumed_from}>
+                      <span class="trace-info-label">Resumed From</span>
+                      <span>{detail().trace.resumed_from}</span>
+                    </Show>
                     <span class="trace-info-label">Tokens</span>
                     <span>{(detail().trace.total_input_tokens + detail().trace.total_output_tokens).toLocaleString()}</span>
                     <span class="trace-info-label">Latency</span>
@@ -220,12 +229,24 @@ export default function Observe() {
                             <div class="span-header">
                               <span class="span-node-id">{span.node_id}</span>
                               <span class="span-node-type">{span.node_type}</span>
+                              <Show when={(span.attempt ?? 1) > 1}>
+                                <span class="span-node-type">Attempt {span.attempt}</span>
+                              </Show>
                             </div>
                             <div class="span-metrics">
                               <span>Tokens: {span.input_tokens + span.output_tokens}</span>
                               <span>Time: {formatDuration(span.end_time - span.start_time)}</span>
                               <span>Tools: {span.tool_call_count}</span>
+                              <Show when={span.score != null}>
+                                <span>Score: {span.score!.toFixed(2)}</span>
+                              </Show>
+                              <Show when={span.model}>
+                                <span>Model: {span.model}</span>
+                              </Show>
                             </div>
+                            <Show when={span.error}>
+                              <div class="span-io">Error: {truncate(span.error!, 80)}</div>
+                            </Show>
                             <Show when={span.input}>
                               <div class="span-io">In: {truncate(span.input, 80)}</div>
                             </Show>
@@ -0,0 +1,223 @@
+//! Aggregator and Synthesizer nodes: fan-in with selectable merge strategies.
+//!
+//! These nodes receive the outputs of every upstream node, labelled by source
+//! node ID, and combine them according to `config.strategy`:
+//!
+//! | Strategy | Output |
+//! |----------|--------|
+//! | `concat` | Labelled sections, one per source |
+//! | `json_array` | JSON array of each source's value (arrays are flattened) |
+//! | `json_merge` | JSON object deep-merged in source order |
+//! | `vote` | The most common answer (ties go to the earliest source) |
+//! | `llm` | The node's model synthesizes the labelled inputs using its prompt |
+//!
+//! The default is `llm` when the node has a prompt, otherwise `concat`.
+//!
+//! Sources whose branch failed under a tolerant failure policy are listed
+//! after the other sections as `## <id> (failed)` with their error for the
+//! `concat` and `llm` strategies; the other strategies merge only the
+//! outputs that exist.
+
+use std::collections::HashMap;
+
+use fissio_core::AgentError;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tracing::info;
+
+use crate::recording::Calls;
+use crate::{extract_json, ExecutionMetrics};
+
+/// How an aggregating node combines its inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum MergeStrategy {
+    Concat,
+    JsonArray,
+    JsonMerge,
+    Vote,
+    Llm,
+}
+
+impl MergeStrategy {
+    /// Reads the strategy from a node's `config`, defaulting on whether it has a prompt.
+    pub(crate) fn from_config(config: &Value, has_prompt: bool) -> Result<Self, AgentError> {
+        match config.get("strategy") {
+            Some(value) => serde_json::from_value(value.clone())
+                .map_err(|_| AgentError::ParseError(format!("Unknown merge strategy: {}", value))),
+            None if has_prompt => Ok(Self::Llm),
+            None => Ok(Self::Concat),
+        }
+    }
+}
+
+/// Formats inputs as labelled sections, one per source node, followed by a
+/// section for each failed source naming its error.
+pub(crate) fn format_labelled(inputs: &[(String, String)], failures: &[(String, String)]) -> String {
+    let succeeded = inputs.iter().map(|(source, content)| format!("## {}\n{}", source, content));
+    let failed = failures.iter().map(|(source, error)| format!("## {} (failed)\n{}", source, error));
+    succeeded.chain(failed).collect::<Vec<_>>().join("\n\n")
+}
+
+/// Executes an Aggregator or Synthesizer node over its labelled inputs and
+/// the errors of failed sources.
+pub(crate) async fn execute_aggregator(
+    calls: Calls<'_>,
+    prompt: Option<&str>,
+    strategy: MergeStrategy,
+    inputs: &[(String, String)],
+    failures: &[(String, String)],
+) -> Result<(String, ExecutionMetrics), AgentError> {
+    info!("║     Merging {} inputs ({:?}), {} failed", inputs.len(), strategy, failures.len());
+
+    let content = match strategy {
+        MergeStrategy::Concat => format_labelled(inputs, failures),
+        MergeStrategy::JsonArray => to_json_string(&merge_array(inputs))?,
+        MergeStrategy::JsonMerge => to_json_string(&merge_object(inputs))?,
+        MergeStrategy::Vote => vote(inputs),
+        MergeStrategy::Llm => {
+            let system_prompt = prompt.unwrap_or(
+                "Synthesize the following inputs into a single coherent response. \
+                Each section is labelled with the node that produced it.",
+            );
+            let response = calls.chat(system_prompt, &format_labelled(inputs, failures)).await?;
+            let mut metrics = ExecutionMetrics::default();
+            metrics.accumulate(&response.metrics);
+            metrics.iteration_count = 1;
+            return Ok((response.content, metrics));
+        }
+    };
+
+    Ok((content, ExecutionMetrics::default()))
+}
+
+fn to_json_string(value: &Value) -> Result<String, AgentError> {
+    serde_json::to_string_pretty(value).map_err(|e| AgentError::ParseError(e.to_string()))
+}
+
+/// Parses an input as JSON, falling back to a JSON string of the raw text.
+fn as_json(content: &str) -> Value {
+    extract_json(content).unwrap_or_else(|| Value::String(content.trim().to_string()))
+}
+
+/// Collects inputs into one array, flattening inputs that are themselves arrays.
+fn merge_array(inputs: &[(String, String)]) -> Value {
+    let mut items = Vec::new();
+    for (_, content) in inputs {
+        match as_json(content) {
+            Value::Array(values) => items.extend(values),
+            value => items.push(value),
+        }
+    }
+    Value::Array(items)
+}
+
+/// Deep-merges object inputs in order; later sources win on conflicts.
+/// Non-object inputs are stored under their source node ID.
+fn merge_object(inputs: &[(String, String)]) -> Value {
+    let mut merged = Value::Object(Map::new());
+    for (source, content) in inputs {
+        let value = match as_json(content) {
+            obj @ Value::Object(_) => obj,
+            other => Value::Object(Map::from_iter([(source.clone(), other)])),
+        };
+        deep_merge(&mut merged, value);
+    }
+    merged
+}
+
+fn deep_merge(target: &mut Value, source: Value) {
+    match (target, source) {
+        (Value::Object(target), Value::Object(source)) => {
+            for (key, value) in source {
+                match target.get_mut(&key) {
+                    Some(existing) => deep_merge(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, source) => *target = source,
+    }
+}
+
+/// Returns the most common answer, comparing case- and punctuation-insensitively.
+fn vote(inputs: &[(String, String)]) -> String {
+    let normalize = |s: &str| {
+        s.trim()
+            .trim_end_matches(|c: char| c.is_ascii_punctuation())
+            .to_lowercase()
+    };
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for (_, content) in inputs {
+        *counts.entry(normalize(content)).or_default() += 1;
+    }
+
+    // Earliest input wins ties
+    let mut winner: Option<(&str, usize)> = None;
+    for (_, content) in inputs {
+        let count = counts[&normalize(content)];
+        if winner.is_none_or(|(_, best)| count > best) {
+            winner = Some((content, count));
+        }
+    }
+
+    info!("║     Vote tally: {:?}", counts);
+    winner.map(|(c, _)| c.trim().to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs(items: &[(&str, &str)]) -> Vec<(String, String)> {
+        items.iter().map(|(s, c)| (s.to_string(), c.to_string())).collect()
+    }
+
+    #[test]
+    fn test_strategy_from_config() {
+        let config = serde_json::json!({ "strategy": "json_merge" });
+        assert_eq!(MergeStrategy::from_config(&config, true).unwrap(), MergeStrategy::JsonMerge);
+        assert_eq!(MergeStrategy::from_config(&Value::Null, true).unwrap(), MergeStrategy::Llm);
+        assert_eq!(MergeStrategy::from_config(&Value::Null, false).unwrap(), MergeStrategy::Concat);
+        assert!(MergeStrategy::from_config(&serde_json::json!({ "strategy": "zip" }), false).is_err());
+    }
+
+    #[test]
+    fn test_labelled_concat() {
+        let merged = format_labelled(&inputs(&[("a", "first"), ("b", "second")]), &[]);
+        assert_eq!(merged, "## a\nfirst\n\n## b\nsecond");
+
+        let merged = format_labelled(&inputs(&[("a", "first")]), &inputs(&[("b", "Timed out: node 'b' exceeded 10ms")]));
+        assert_eq!(merged, "## a\nfirst\n\n## b (failed)\nTimed out: node 'b' exceeded 10ms");
+    }
+
+    #[test]
+    fn test_json_merges() {
+        let array = merge_array(&inputs(&[("a", "[1, 2]"), ("b", "3"), ("c", "plain text")]));
+        assert_eq!(array, serde_json::json!([1, 2, 3, "plain text"]));
+
+        let object = merge_object(&inputs(&[
+            ("a", r#"{"user": {"name": "Ada"}, "tags": ["x"]}"#),
+            ("b", r#"{"user": {"email": "ada@example.com"}, "tags": ["y"]}"#),
+            ("c", "note"),
+        ]));
+        assert_eq!(
+            object,
+            serde_json::json!({
+                "user": {"name": "Ada", "email": "ada@example.com"},
+                "tags": ["y"],
+                "c": "note"
+            })
+        );
+    }
+
+    #[test]
+    fn test_vote() {
+        assert_eq!(vote(&inputs(&[("a", "Yes."), ("b", "no"), ("c", "yes")])), "Yes.");
+        assert_eq!(vote(&inputs(&[("a", "red"), ("b", "blue")])), "red");
+        assert_eq!(vote(&[]), "");
+    }
+}
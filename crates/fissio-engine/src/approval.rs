@@ -0,0 +1,113 @@
+//! Approval nodes: suspend a run until a human decides on its input.
+//!
+//! When a run reaches an `approval` node, its input (a drafted email, a
+//! planned tool call, ...) is saved in the run's checkpoint as a
+//! [`PendingApproval`] and the run returns
+//! [`EngineOutput::Suspended`](crate::EngineOutput::Suspended) once its other
+//! branches have finished.
+//! Suspending needs a checkpoint store, attached with
+//! [`PipelineEngine::with_checkpoints`](crate::PipelineEngine::with_checkpoints).
+//!
+//! ```json
+//! {
+//!   "id": "review",
+//!   "type": "approval",
+//!   "prompt": "Check the email before it goes out.",
+//!   "config": { "reject_target": "redraft", "message": "The email was not approved." }
+//! }
+//! ```
+//!
+//! [`PipelineEngine::decide`](crate::PipelineEngine::decide) continues the
+//! same run with a [`Decision`]:
+//!
+//! | Decision | Effect |
+//! |----------|--------|
+//! | `{"action": "approve"}` | The input passes on unchanged |
+//! | `{"action": "edit", "content": "..."}` | The edited content passes on instead |
+//! | `{"action": "reject", "reason": "..."}` | Like a gate rejection: only the edge to `reject_target` is followed, or the run halts with `message` as its output |
+//!
+//! The prompt is rendered like any other node's and shown to the reviewer.
+
+use serde::{Deserialize, Serialize};
+
+/// An approval node's input, waiting for a human decision.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingApproval {
+    /// The approval node.
+    pub node_id: String,
+    /// The content to decide on: the node's input.
+    pub content: String,
+    /// Instructions for the reviewer, from the node's `prompt`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
+    /// Unix timestamp (milliseconds) when the run suspended.
+    pub requested_at: i64,
+}
+
+/// A human decision on a [`PendingApproval`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Decision {
+    /// Pass the content on unchanged.
+    Approve,
+    /// Pass `content` on in place of the original.
+    Edit { content: String },
+    /// Reject the content, optionally explaining why.
+    Reject {
+        #[serde(default)]
+        reason: Option<String>,
+    },
+}
+
+/// Approval settings read from a node's `config`.
+#[derive(Debug, Default)]
+pub(crate) struct ApprovalConfig {
+    pub(crate) reject_target: Option<String>,
+    message: Option<String>,
+}
+
+impl ApprovalConfig {
+    pub(crate) fn from_value(config: &serde_json::Value) -> Self {
+        let text = |key: &str| config.get(key).and_then(|v| v.as_str()).map(String::from);
+        Self { reject_target: text("reject_target"), message: text("message") }
+    }
+
+    /// Returns the output of a rejected approval.
+    pub(crate) fn rejection_message(&self, node_id: &str, reason: Option<&str>) -> String {
+        if let Some(ref message) = self.message {
+            return message.clone();
+        }
+        match reason {
+            Some(reason) => format!("Rejected at {}: {}", node_id, reason),
+            None => format!("Rejected at {}", node_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decision_format() {
+        let parse = |json: serde_json::Value| serde_json::from_value::<Decision>(json).unwrap();
+        assert_eq!(parse(serde_json::json!({ "action": "approve" })), Decision::Approve);
+        assert_eq!(
+            parse(serde_json::json!({ "action": "edit", "content": "Hi Sam" })),
+            Decision::Edit { content: "Hi Sam".into() }
+        );
+        assert_eq!(parse(serde_json::json!({ "action": "reject" })), Decision::Reject { reason: None });
+        assert!(serde_json::from_value::<Decision>(serde_json::json!({ "action": "edit" })).is_err());
+    }
+
+    #[test]
+    fn test_rejection_message() {
+        let config = ApprovalConfig::from_value(&serde_json::json!({ "reject_target": "redraft" }));
+        assert_eq!(config.reject_target.as_deref(), Some("redraft"));
+        assert_eq!(config.rejection_message("review", Some("too formal")), "Rejected at review: too formal");
+        assert_eq!(config.rejection_message("review", None), "Rejected at review");
+
+        let config = ApprovalConfig::from_value(&serde_json::json!({ "message": "Not sent." }));
+        assert_eq!(config.rejection_message("review", Some("too formal")), "Not sent.");
+    }
+}
@@ -0,0 +1,224 @@
+//! Failure policies for concurrently executed branches.
+//!
+//! Parallel and dynamic edges run their targets concurrently. The edge's
+//! `on_failure` decides what a failing branch does to the run:
+//!
+//! | Policy | Behavior |
+//! |--------|----------|
+//! | `"fail_fast"` (default) | The first failure fails the run; other branches are cancelled |
+//! | `"continue"` | Keep the branches that succeeded; fail only if none did |
+//! | `{"quorum": N}` | Keep the branches that succeeded if at least N did |
+//!
+//! ```json
+//! { "from": "planner", "to": ["web", "papers", "news"], "edge_type": "parallel", "on_failure": "continue" }
+//! ```
+//!
+//! A tolerated failure is stored in the run context as the node's error. The
+//! failed node's outgoing edges aren't followed, and aggregators that list it
+//! as a source see a labelled section with its error. The policy is checked
+//! once every branch the edge dispatched has finished.
+
+use fissio_config::{ConfigError, EdgeType, FailurePolicy, PipelineConfig};
+use fissio_core::AgentError;
+use tracing::warn;
+
+/// Branch results split by outcome, each in dispatch order.
+#[derive(Debug)]
+pub(crate) struct Settled<T> {
+    pub(crate) succeeded: Vec<(String, T)>,
+    pub(crate) failed: Vec<(String, AgentError)>,
+}
+
+/// Splits branch results by outcome, failing if `policy` isn't satisfied.
+pub(crate) fn settle<T>(
+    policy: FailurePolicy,
+    results: Vec<(String, Result<T, AgentError>)>,
+) -> Result<Settled<T>, AgentError> {
+    let total = results.len();
+    let mut settled = Settled { succeeded: Vec::new(), failed: Vec::new() };
+    for (node_id, result) in results {
+        match result {
+            Ok(value) => settled.succeeded.push((node_id, value)),
+            Err(e) => settled.failed.push((node_id, e)),
+        }
+    }
+    if settled.failed.is_empty() {
+        return Ok(settled);
+    }
+
+    let succeeded = settled.succeeded.len();
+    let required = match policy {
+        FailurePolicy::FailFast => total,
+        FailurePolicy::Continue => 1,
+        FailurePolicy::Quorum(n) => n,
+    };
+    if succeeded >= required {
+        let failed: Vec<&str> = settled.failed.iter().map(|(id, _)| id.as_str()).collect();
+        warn!("║ ⚠ {} of {} branches failed {:?}, continuing ({:?})", failed.len(), total, failed, policy);
+        return Ok(settled);
+    }
+
+    let (node_id, error) = settled.failed.swap_remove(0);
+    match policy {
+        FailurePolicy::Quorum(n) => Err(AgentError::WorkerFailed(format!(
+            "quorum not met: {} of {} branches succeeded, {} required (first failure in '{}': {})",
+            succeeded, total, n, node_id, error
+        ))),
+        _ => Err(error),
+    }
+}
+
+/// Branches dispatched by edges whose policy tolerates failures, grouped by
+/// edge until every branch in the group has finished.
+#[derive(Debug, Default)]
+pub(crate) struct BranchGroups {
+    groups: Vec<BranchGroup>,
+}
+
+#[derive(Debug)]
+struct BranchGroup {
+    policy: FailurePolicy,
+    /// Nodes that haven't finished yet.
+    pending: Vec<String>,
+    results: Vec<(String, Result<(), AgentError>)>,
+}
+
+impl BranchGroups {
+    /// Adds the nodes an edge dispatched, unless its policy is fail-fast.
+    pub(crate) fn add(&mut self, policy: FailurePolicy, nodes: Vec<String>) {
+        if !policy.is_fail_fast() && !nodes.is_empty() {
+            self.groups.push(BranchGroup { policy, pending: nodes, results: Vec::new() });
+        }
+    }
+
+    /// Returns `true` if failures of `node_id` are tolerated for now: it is
+    /// an unfinished branch of a tolerant edge.
+    pub(crate) fn tolerates(&self, node_id: &str) -> bool {
+        self.groups.iter().any(|g| g.pending.iter().any(|id| id == node_id))
+    }
+
+    /// Records a node's results (one per subtask for dynamic workers) and,
+    /// once its group has finished, settles the group under its policy.
+    pub(crate) fn record(&mut self, node_id: &str, results: Vec<Result<(), AgentError>>) -> Result<(), AgentError> {
+        let Some(index) = self.groups.iter().position(|g| g.pending.iter().any(|id| id == node_id)) else {
+            return Ok(());
+        };
+        let group = &mut self.groups[index];
+        group.pending.retain(|id| id != node_id);
+        group.results.extend(results.into_iter().map(|r| (node_id.to_string(), r)));
+        if group.pending.is_empty() {
+            let group = self.groups.remove(index);
+            settle(group.policy, group.results)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks that failure policies are only set on concurrent edges and that
+/// quorums are reachable.
+pub(crate) fn check_failure_policies(config: &PipelineConfig) -> Result<(), ConfigError> {
+    for edge in config.edges.iter().filter(|e| !e.on_failure.is_fail_fast()) {
+        let invalid = |message: String| ConfigError::validation(&config.id, message);
+        let targets = edge.to.as_vec();
+
+        match (edge.edge_type, edge.on_failure) {
+            (EdgeType::Parallel | EdgeType::Dynamic, FailurePolicy::Quorum(0)) => {
+                return Err(invalid(format!("edge to {:?} has a quorum of 0", targets)));
+            }
+            (EdgeType::Parallel, FailurePolicy::Quorum(n)) if n > targets.len() => {
+                return Err(invalid(format!(
+                    "edge to {:?} requires a quorum of {} but has only {} targets",
+                    targets, n, targets.len()
+                )));
+            }
+            (EdgeType::Parallel | EdgeType::Dynamic, _) => {}
+            (edge_type, _) => {
+                return Err(invalid(format!(
+                    "edge to {:?} sets on_failure, which only applies to parallel and dynamic edges, not {}",
+                    targets, edge_type
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fissio_config::NodeType;
+
+    fn results(outcomes: &[bool]) -> Vec<(String, Result<usize, AgentError>)> {
+        outcomes
+            .iter()
+            .enumerate()
+            .map(|(i, &ok)| {
+                let result = if ok { Ok(i) } else { Err(AgentError::ExternalApi(format!("fetch {} failed", i))) };
+                (format!("b{}", i), result)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_settle() {
+        let settled = settle(FailurePolicy::FailFast, results(&[true, true])).unwrap();
+        assert_eq!(settled.succeeded.len(), 2);
+        assert!(matches!(settle(FailurePolicy::FailFast, results(&[true, false])), Err(AgentError::ExternalApi(_))));
+
+        let settled = settle(FailurePolicy::Continue, results(&[false, true, false])).unwrap();
+        assert_eq!(settled.succeeded, vec![("b1".to_string(), 1)]);
+        assert_eq!(settled.failed.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>(), ["b0", "b2"]);
+        assert!(matches!(
+            settle(FailurePolicy::Continue, results(&[false, false])),
+            Err(AgentError::ExternalApi(m)) if m == "fetch 0 failed"
+        ));
+
+        assert!(settle(FailurePolicy::Quorum(2), results(&[true, false, true])).is_ok());
+        let error = settle(FailurePolicy::Quorum(2), results(&[true, false, false])).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Worker execution failed: quorum not met: 1 of 3 branches succeeded, 2 required \
+            (first failure in 'b1': External API error: fetch 1 failed)"
+        );
+    }
+
+    #[test]
+    fn test_branch_groups() {
+        let failure = || Err(AgentError::ExternalApi("timed out".into()));
+        let mut groups = BranchGroups::default();
+        groups.add(FailurePolicy::FailFast, vec!["a".into()]);
+        assert!(!groups.tolerates("a"));
+
+        groups.add(FailurePolicy::Quorum(2), vec!["web".into(), "news".into()]);
+        assert!(groups.tolerates("web"));
+        groups.record("web", vec![Ok(()), failure()]).unwrap();
+        assert!(!groups.tolerates("web"));
+        let error = groups.record("news", vec![failure()]).unwrap_err();
+        assert!(error.to_string().contains("1 of 3 branches succeeded"), "{}", error);
+
+        groups.add(FailurePolicy::Continue, vec!["web".into(), "news".into()]);
+        groups.record("web", vec![failure()]).unwrap();
+        assert!(groups.record("news", vec![Ok(())]).is_ok());
+        assert!(!groups.tolerates("news"));
+    }
+
+    #[test]
+    fn test_check_failure_policies() {
+        let check = |edge_type: EdgeType, policy: FailurePolicy| {
+            let mut config = PipelineConfig::builder("p", "P")
+                .node("a", NodeType::Llm).done()
+                .node("b", NodeType::Llm).done()
+                .parallel_edge_with_policy("input", &["a", "b"], policy)
+                .build();
+            config.edges[0].edge_type = edge_type;
+            check_failure_policies(&config).map_err(|e| e.to_string())
+        };
+
+        assert!(check(EdgeType::Parallel, FailurePolicy::Continue).is_ok());
+        assert!(check(EdgeType::Parallel, FailurePolicy::Quorum(2)).is_ok());
+        assert!(check(EdgeType::Dynamic, FailurePolicy::Quorum(5)).is_ok());
+        assert!(check(EdgeType::Parallel, FailurePolicy::Quorum(3)).unwrap_err().contains("only 2 targets"));
+        assert!(check(EdgeType::Dynamic, FailurePolicy::Quorum(0)).unwrap_err().contains("quorum of 0"));
+        assert!(check(EdgeType::Conditional, FailurePolicy::Continue).unwrap_err().contains("not conditional"));
+    }
+}
@@ -0,0 +1,174 @@
+//! Timeouts and cooperative cancellation.
+//!
+//! A run stops early when the [`CancellationToken`] passed to
+//! [`PipelineEngine::execute_stream_with_cancel`](crate::PipelineEngine::execute_stream_with_cancel)
+//! is cancelled or the pipeline's `timeout_ms` elapses. In-flight work (LLM
+//! requests, tool calls, parallel branches) is dropped at its next await
+//! point, and a streamed response ends with the error.
+//!
+//! A node's `timeout_ms` bounds each of its attempts separately, so a
+//! timed-out attempt is retried when its retry policy lists `timeout`:
+//!
+//! ```json
+//! {
+//!   "timeout_ms": 120000,
+//!   "nodes": [{ "id": "researcher", "type": "worker", "timeout_ms": 30000, "retry": { "retry_on": ["timeout"] } }]
+//! }
+//! ```
+
+use std::future::{self, Future};
+use std::time::Duration;
+
+use fissio_core::AgentError;
+use fissio_llm::LlmStream;
+use futures::StreamExt;
+use tokio::time::Instant;
+
+pub use tokio_util::sync::CancellationToken;
+
+/// A point in time after which work fails with [`AgentError::Timeout`].
+#[derive(Debug, Clone)]
+pub(crate) struct Deadline {
+    at: Instant,
+    /// Describes what ran out of time, for the error message.
+    message: String,
+}
+
+impl Deadline {
+    /// A deadline `timeout_ms` from now for the named pipeline or node.
+    pub(crate) fn after(timeout_ms: u64, what: &str) -> Self {
+        Self {
+            at: Instant::now() + Duration::from_millis(timeout_ms),
+            message: format!("{} exceeded {}ms", what, timeout_ms),
+        }
+    }
+
+    fn error(&self) -> AgentError {
+        AgentError::Timeout(self.message.clone())
+    }
+}
+
+/// Runs `fut` to completion unless `deadline` passes first.
+pub(crate) async fn within<T>(
+    deadline: Option<&Deadline>,
+    fut: impl Future<Output = Result<T, AgentError>>,
+) -> Result<T, AgentError> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline.at, fut)
+            .await
+            .unwrap_or_else(|_| Err(deadline.error())),
+        None => fut.await,
+    }
+}
+
+/// Cancellation signal and deadline that bound one run.
+#[derive(Debug, Clone)]
+pub(crate) struct RunLimits {
+    cancel: CancellationToken,
+    deadline: Option<Deadline>,
+}
+
+impl RunLimits {
+    pub(crate) fn new(cancel: CancellationToken, deadline: Option<Deadline>) -> Self {
+        Self { cancel, deadline }
+    }
+
+    /// Returns these limits further bounded by `deadline`; the earlier one wins.
+    pub(crate) fn with_deadline(&self, deadline: Option<Deadline>) -> Self {
+        let deadline = match (self.deadline.clone(), deadline) {
+            (Some(a), Some(b)) => Some(if b.at < a.at { b } else { a }),
+            (a, b) => a.or(b),
+        };
+        Self { cancel: self.cancel.clone(), deadline }
+    }
+
+    /// Resolves with the reason once the run has to stop.
+    fn stopped(&self) -> impl Future<Output = AgentError> + Send + 'static {
+        let cancel = self.cancel.clone();
+        let deadline = self.deadline.clone();
+        async move {
+            let expired = async {
+                match deadline {
+                    Some(deadline) => {
+                        tokio::time::sleep_until(deadline.at).await;
+                        deadline.error()
+                    }
+                    None => future::pending().await,
+                }
+            };
+            tokio::select! {
+                biased;
+                _ = cancel.cancelled() => AgentError::Cancelled,
+                error = expired => error,
+            }
+        }
+    }
+
+    /// Runs `fut` to completion unless the run is stopped first, in which case
+    /// `fut` is dropped along with everything it was awaiting and the reason
+    /// is returned as the error.
+    pub(crate) async fn run<F: Future>(&self, fut: F) -> Result<F::Output, AgentError> {
+        tokio::select! {
+            biased;
+            error = self.stopped() => Err(error),
+            output = fut => Ok(output),
+        }
+    }
+
+    /// Wraps `stream` so it ends with an error if the run is stopped before it finishes.
+    pub(crate) fn bound_stream(&self, stream: LlmStream) -> LlmStream {
+        let state = Some((stream, Box::pin(self.stopped())));
+        Box::pin(futures::stream::unfold(state, |state| async move {
+            let (mut stream, mut stopped) = state?;
+            tokio::select! {
+                biased;
+                error = &mut stopped => Some((Err(error), None)),
+                item = stream.next() => item.map(|item| (item, Some((stream, stopped)))),
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fissio_llm::StreamChunk;
+
+    fn endless() -> LlmStream {
+        Box::pin(futures::stream::repeat_with(|| Ok(StreamChunk::Content("tick".into()))).then(|chunk| async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            chunk
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_within_deadline() {
+        let deadline = Deadline::after(10, "node 'slow'");
+        let result = within(Some(&deadline), future::pending::<Result<(), AgentError>>()).await;
+        assert!(matches!(result, Err(AgentError::Timeout(m)) if m == "node 'slow' exceeded 10ms"));
+
+        assert_eq!(within(Some(&deadline), async { Ok(1) }).await.unwrap(), 1);
+        assert_eq!(within(None, async { Ok(2) }).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_run_cancelled() {
+        let cancel = CancellationToken::new();
+        let limits = RunLimits::new(cancel.clone(), None);
+        cancel.cancel();
+        let result = limits.run(future::pending::<()>()).await;
+        assert!(matches!(result, Err(AgentError::Cancelled)));
+    }
+
+    #[tokio::test]
+    async fn test_bound_stream_ends_with_error() {
+        let limits = RunLimits::new(CancellationToken::new(), None)
+            .with_deadline(Some(Deadline::after(60_000, "pipeline 'p'")))
+            .with_deadline(Some(Deadline::after(30, "node 'n'")));
+        let items: Vec<_> = limits.bound_stream(endless()).collect().await;
+
+        assert!(items.len() > 1);
+        assert!(items[..items.len() - 1].iter().all(|item| item.is_ok()));
+        assert!(matches!(items.last(), Some(Err(AgentError::Timeout(m))) if m.starts_with("node 'n'")));
+    }
+}
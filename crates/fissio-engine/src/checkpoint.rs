@@ -0,0 +1,414 @@
+//! Checkpoints: resuming a failed run without repeating finished work.
+//!
+//! Attach a [`CheckpointStore`] with
+//! [`PipelineEngine::with_checkpoints`](crate::PipelineEngine::with_checkpoints)
+//! and the engine saves a [`Checkpoint`] under the run's ID when the run
+//! starts and after every node that completes. The checkpoint holds the
+//! run's context and executed set, along with each completed execution's
+//! node config, input, and output. Saves are written on a blocking thread,
+//! and those made while a write is in flight are folded into the next one;
+//! the run waits for the last write before it returns.
+//!
+//! [`PipelineEngine::resume`](crate::PipelineEngine::resume) starts a new run
+//! from another run's checkpoint. The pipeline is traversed from the start
+//! again, but a node whose config and input match an execution in the
+//! checkpoint gets that execution's output back instead of running, so
+//! traversal reaches the node that failed without repeating any calls:
+//!
+//! ```rust,ignore
+//! let store: Arc<dyn CheckpointStore> = Arc::new(TraceStore::new("traces.db")?);
+//! let engine = PipelineEngine::new(config, models, default_model, HashMap::new())
+//!     .with_checkpoints(Arc::clone(&store), "run-2");
+//! let output = engine.resume("run-1", CancellationToken::new()).await?;
+//! ```
+//!
+//! Resuming with an edited config re-runs the edited nodes, along with any
+//! node downstream whose input changes as a result.
+//!
+//! A run suspended by an approval node is continued the same way, except that
+//! it stays the same run: [`PipelineEngine::decide`](crate::PipelineEngine::decide)
+//! replays the run's own checkpoint and hands the decision to the approval node.
+//! The saved checkpoint is left as it was until the decision has been applied,
+//! so a continuation that fails before then can be decided again.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use fissio_config::{NodeConfig, PipelineConfig};
+use fissio_core::{AgentError, Message};
+use fissio_monitor::{CheckpointRecord, CheckpointStore};
+use serde::{Deserialize, Serialize};
+use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+use crate::{now_ms, Decision, NodeOutput, PendingApproval, RunContext};
+
+/// The saved state of a pipeline run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The pipeline the run executed.
+    pub config: PipelineConfig,
+    /// The user input that started the run.
+    pub input: String,
+    /// Conversation history the run was given.
+    pub history: Vec<Message>,
+    /// Node outputs keyed by node ID.
+    #[serde(default)]
+    pub context: RunContext,
+    /// IDs of the nodes that have completed, in the order they first did.
+    pub executed: Vec<String>,
+    /// Run this one resumed, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resumed_from: Option<String>,
+    /// Approval nodes the run is suspended at.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pending: Vec<PendingApproval>,
+    /// Every completed node execution, in completion order.
+    completed: Vec<Execution>,
+}
+
+/// A completed node execution.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Execution {
+    /// The node as it ran, with its prompt template rendered.
+    node: NodeConfig,
+    input: String,
+    output: NodeOutput,
+}
+
+impl Execution {
+    fn matches(&self, node: &NodeConfig, input: &str) -> bool {
+        self.node.id == node.id
+            && self.input == input
+            && serde_json::to_value(&self.node).ok() == serde_json::to_value(node).ok()
+    }
+}
+
+impl Checkpoint {
+    /// Loads the checkpoint saved by run `run_id`.
+    pub fn load(store: &dyn CheckpointStore, run_id: &str) -> Result<Option<Self>, AgentError> {
+        let record = store
+            .load_checkpoint(run_id)
+            .map_err(|e| AgentError::Checkpoint(e.to_string()))?;
+        record
+            .map(|record| serde_json::from_value(record.state))
+            .transpose()
+            .map_err(|e| AgentError::Checkpoint(format!("run '{}': {}", run_id, e)))
+    }
+}
+
+/// Where a run's checkpoints are saved.
+pub(crate) struct CheckpointSink {
+    pub(crate) store: Arc<dyn CheckpointStore>,
+    pub(crate) run_id: String,
+}
+
+/// Writes a run's checkpoint to its store on a blocking thread. Saves
+/// requested while a write is in flight are coalesced into one more write of
+/// the latest state.
+struct Writer {
+    store: Arc<dyn CheckpointStore>,
+    run_id: String,
+    checkpoint: Arc<Mutex<Checkpoint>>,
+    /// A save was requested since the last write started.
+    dirty: AtomicBool,
+    /// A thread is writing.
+    busy: AtomicBool,
+}
+
+impl Writer {
+    /// Writes until no save is left requested.
+    fn drain(&self) {
+        loop {
+            while self.dirty.swap(false, Ordering::SeqCst) {
+                self.write();
+            }
+            self.busy.store(false, Ordering::SeqCst);
+            // A save requested after the last check but before `busy` cleared is ours
+            if !self.dirty.load(Ordering::SeqCst) || self.busy.swap(true, Ordering::SeqCst) {
+                return;
+            }
+        }
+    }
+
+    /// Serializes the checkpoint under its lock, then saves it without holding it.
+    fn write(&self) {
+        let record = {
+            let checkpoint = self.checkpoint.lock().unwrap();
+            let state = match serde_json::to_value(&*checkpoint) {
+                Ok(state) => state,
+                Err(e) => {
+                    warn!("Failed to serialize checkpoint: {}", e);
+                    return;
+                }
+            };
+            CheckpointRecord {
+                run_id: self.run_id.clone(),
+                pipeline_id: checkpoint.config.id.clone(),
+                resumed_from: checkpoint.resumed_from.clone(),
+                state,
+                updated_at: now_ms(),
+            }
+        };
+        if let Err(e) = self.store.save_checkpoint(&record) {
+            warn!("Failed to save checkpoint: {}", e);
+        }
+    }
+}
+
+/// Records a run's completed executions and replays those of a resumed run.
+pub(crate) struct Journal<'a> {
+    sink: Option<&'a CheckpointSink>,
+    checkpoint: Arc<Mutex<Checkpoint>>,
+    writer: Option<Arc<Writer>>,
+    /// The latest write started on a blocking thread.
+    writing: Mutex<Option<JoinHandle<()>>>,
+    /// Executions from the resumed run that haven't been replayed yet.
+    pending: Mutex<Vec<Execution>>,
+    /// Decision for the approval the resumed run was suspended at, until it's used.
+    decision: Mutex<Option<(PendingApproval, Decision)>>,
+}
+
+impl<'a> Journal<'a> {
+    /// Starts the journal of a run, replaying `resumed` if given. Its
+    /// `resumed_from` carries over to this run's checkpoints.
+    pub(crate) fn new(
+        sink: Option<&'a CheckpointSink>,
+        config: &PipelineConfig,
+        input: &str,
+        history: &[Message],
+        resumed: Option<Checkpoint>,
+        decision: Option<(PendingApproval, Decision)>,
+    ) -> Self {
+        let (resumed_from, pending) = match resumed {
+            Some(checkpoint) => (checkpoint.resumed_from, checkpoint.completed),
+            None => (None, Vec::new()),
+        };
+        let checkpoint = Arc::new(Mutex::new(Checkpoint {
+            config: config.clone(),
+            input: input.to_string(),
+            history: history.to_vec(),
+            context: RunContext::default(),
+            executed: Vec::new(),
+            resumed_from,
+            pending: Vec::new(),
+            completed: Vec::new(),
+        }));
+        let writer = sink.map(|sink| {
+            Arc::new(Writer {
+                store: Arc::clone(&sink.store),
+                run_id: sink.run_id.clone(),
+                checkpoint: Arc::clone(&checkpoint),
+                dirty: AtomicBool::new(false),
+                busy: AtomicBool::new(false),
+            })
+        });
+        let journal = Self {
+            sink,
+            checkpoint,
+            writer,
+            writing: Mutex::new(None),
+            pending: Mutex::new(pending),
+            decision: Mutex::new(decision),
+        };
+        journal.save();
+        journal
+    }
+
+    /// Returns the output of a matching execution from the resumed run, if
+    /// any, recording it as part of this run.
+    pub(crate) fn replay(&self, node: &NodeConfig, input: &str) -> Option<NodeOutput> {
+        let execution = {
+            let mut pending = self.pending.lock().unwrap();
+            let index = pending.iter().position(|e| e.matches(node, input))?;
+            pending.remove(index)
+        };
+        let output = execution.output.clone();
+        self.push(execution, None);
+        Some(output)
+    }
+
+    /// Returns true if checkpoints are saved for this run.
+    pub(crate) fn is_recording(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    /// Takes the decision on approval node `node_id`, if one was made on `content`.
+    pub(crate) fn take_decision(&self, node_id: &str, content: &str) -> Option<Decision> {
+        let mut decision = self.decision.lock().unwrap();
+        match decision.as_ref() {
+            Some((approval, _)) if approval.node_id == node_id && approval.content == content => {
+                decision.take().map(|(_, decision)| decision)
+            }
+            _ => None,
+        }
+    }
+
+    /// Records an approval the run is suspended at and saves the checkpoint,
+    /// returning the run's ID.
+    pub(crate) fn suspend(&self, approval: PendingApproval) -> Result<&str, AgentError> {
+        let sink = self.sink.ok_or_else(|| {
+            AgentError::Checkpoint(format!("approval node '{}' needs a checkpoint store", approval.node_id))
+        })?;
+        self.checkpoint.lock().unwrap().pending.push(approval);
+        self.write();
+        Ok(&sink.run_id)
+    }
+
+    /// Returns the approvals the run is suspended at.
+    pub(crate) fn suspended(&self) -> Vec<PendingApproval> {
+        self.checkpoint.lock().unwrap().pending.clone()
+    }
+
+    /// Records a completed execution and saves the checkpoint. `context` is
+    /// the run's context as the node finished, before its own output is stored.
+    pub(crate) fn record(&self, node: &NodeConfig, input: &str, output: &NodeOutput, context: RunContext) {
+        let execution = Execution { node: node.clone(), input: input.to_string(), output: output.clone() };
+        self.push(execution, Some(context));
+    }
+
+    /// Waits for the checkpoint writes started so far to finish.
+    pub(crate) async fn flush(&self) {
+        loop {
+            let Some(writing) = self.writing.lock().unwrap().take() else {
+                return;
+            };
+            let _ = writing.await;
+        }
+    }
+
+    fn push(&self, execution: Execution, context: Option<RunContext>) {
+        {
+            let mut checkpoint = self.checkpoint.lock().unwrap();
+            if let Some(context) = context {
+                checkpoint.context = context;
+            }
+            let node_id = execution.node.id.clone();
+            checkpoint.context.store(&node_id, &execution.output);
+            if !checkpoint.executed.contains(&node_id) {
+                checkpoint.executed.push(node_id);
+            }
+            checkpoint.completed.push(execution);
+        }
+        self.save();
+    }
+
+    /// Saves the checkpoint, unless this run continues a suspended one whose
+    /// decision hasn't been applied yet: until then, the saved checkpoint
+    /// keeps the pending approval and the nodes that completed before it.
+    fn save(&self) {
+        if self.decision.lock().unwrap().is_some() {
+            return;
+        }
+        self.write();
+    }
+
+    /// Requests a write of the checkpoint. Inside a Tokio runtime it happens
+    /// on a blocking thread (see [`Journal::flush`]), otherwise right away.
+    fn write(&self) {
+        let Some(writer) = &self.writer else {
+            return;
+        };
+        writer.dirty.store(true, Ordering::SeqCst);
+        if writer.busy.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let writer = Arc::clone(writer);
+        match Handle::try_current() {
+            Ok(runtime) => *self.writing.lock().unwrap() = Some(runtime.spawn_blocking(move || writer.drain())),
+            Err(_) => writer.drain(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fissio_config::NodeType;
+    use fissio_monitor::TraceStore;
+
+    fn config(prompt: &str) -> PipelineConfig {
+        PipelineConfig::builder("p", "P").node("a", NodeType::Llm).prompt(prompt).done().build()
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let store: Arc<dyn CheckpointStore> = Arc::new(TraceStore::in_memory().unwrap());
+        let sink = CheckpointSink { store: Arc::clone(&store), run_id: "run-1".to_string() };
+        let original = config("Be brief.");
+        let node = &original.nodes[0];
+
+        let journal = Journal::new(Some(&sink), &original, "hi", &[Message::user("earlier")], None, None);
+        assert!(Checkpoint::load(store.as_ref(), "run-1").unwrap().unwrap().executed.is_empty());
+        journal.record(node, "hi", &NodeOutput::new("HELLO"), RunContext::default());
+
+        let checkpoint = Checkpoint::load(store.as_ref(), "run-1").unwrap().unwrap();
+        assert_eq!(checkpoint.executed, ["a"]);
+        assert_eq!(checkpoint.context.text("a").unwrap(), "HELLO");
+        assert_eq!(checkpoint.history.len(), 1);
+
+        // Replays only an execution with the same config and input, once
+        let resumed = Journal::new(None, &original, "hi", &[], Some(checkpoint.clone()), None);
+        assert!(resumed.replay(node, "other input").is_none());
+        assert_eq!(resumed.replay(node, "hi").unwrap().content, "HELLO");
+        assert!(resumed.replay(node, "hi").is_none());
+
+        let edited = config("Be thorough.");
+        let resumed = Journal::new(None, &edited, "hi", &[], Some(checkpoint), None);
+        assert!(resumed.replay(&edited.nodes[0], "hi").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_writes_on_blocking_threads() {
+        let store: Arc<dyn CheckpointStore> = Arc::new(TraceStore::in_memory().unwrap());
+        let sink = CheckpointSink { store: Arc::clone(&store), run_id: "run-1".to_string() };
+        let config = config("Be brief.");
+
+        let journal = Journal::new(Some(&sink), &config, "hi", &[], None, None);
+        for content in ["one", "two", "three"] {
+            journal.record(&config.nodes[0], content, &NodeOutput::new(content), RunContext::default());
+        }
+        journal.flush().await;
+
+        // Saves made while a write was in flight end with the latest state
+        let checkpoint = Checkpoint::load(store.as_ref(), "run-1").unwrap().unwrap();
+        assert_eq!(checkpoint.completed.len(), 3);
+        assert_eq!(checkpoint.context.text("a").unwrap(), "three");
+    }
+
+    #[test]
+    fn test_suspend_and_decide() {
+        let store: Arc<dyn CheckpointStore> = Arc::new(TraceStore::in_memory().unwrap());
+        let sink = CheckpointSink { store: Arc::clone(&store), run_id: "run-1".to_string() };
+        let config = config("Be brief.");
+        let approval = |content: &str| PendingApproval {
+            node_id: "review".into(),
+            content: content.into(),
+            instructions: None,
+            requested_at: 0,
+        };
+
+        let unsaved = Journal::new(None, &config, "hi", &[], None, None);
+        assert!(unsaved.suspend(approval("draft")).is_err());
+
+        let journal = Journal::new(Some(&sink), &config, "hi", &[], None, None);
+        assert_eq!(journal.suspend(approval("draft")).unwrap(), "run-1");
+        let checkpoint = Checkpoint::load(store.as_ref(), "run-1").unwrap().unwrap();
+        assert_eq!(checkpoint.pending, [approval("draft")]);
+
+        // The decision applies once, and only to the content it was made on
+        let continued = Journal::new(Some(&sink), &config, "hi", &[], Some(checkpoint), Some((approval("draft"), Decision::Approve)));
+        assert!(continued.suspended().is_empty());
+        assert!(continued.take_decision("review", "other draft").is_none());
+
+        // The saved checkpoint keeps its pending approval until the decision is applied
+        continued.record(&config.nodes[0], "hi", &NodeOutput::new("HELLO"), RunContext::default());
+        let saved = Checkpoint::load(store.as_ref(), "run-1").unwrap().unwrap();
+        assert_eq!(saved.pending, [approval("draft")]);
+        assert_eq!(continued.take_decision("review", "draft"), Some(Decision::Approve));
+        assert!(continued.take_decision("review", "draft").is_none());
+        continued.record(&config.nodes[0], "hi", &NodeOutput::new("HELLO"), RunContext::default());
+        assert!(Checkpoint::load(store.as_ref(), "run-1").unwrap().unwrap().pending.is_empty());
+    }
+}
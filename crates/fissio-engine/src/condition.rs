@@ -0,0 +1,223 @@
+//! Edge conditions: deterministic branching without an LLM call.
+//!
+//! An edge with a `when` [`Condition`] is followed only if the condition
+//! holds. Text rules (`regex`, `keywords`, `min_length`, `max_length`) test
+//! the text the edge carries; a `path` rule tests a field of an upstream
+//! node's output in the run context.
+//!
+//! ```json
+//! { "from": "input", "to": "summarize", "when": { "min_length": 2000 } }
+//! { "from": "extract", "to": "escalate", "when": { "path": "extract.priority", "min": 4 } }
+//! ```
+//!
+//! On a router's edges, conditions are rules instead: the router checks them
+//! against its own input, in edge order, and follows the edges whose rules
+//! match without classifying. Only when no rule matches does it ask its model.
+//!
+//! ```json
+//! { "from": "triage", "to": "billing", "when": { "keywords": ["refund", "invoice"] } }
+//! { "from": "triage", "to": ["billing", "tech_support", "sales"], "edge_type": "conditional" }
+//! ```
+
+use std::collections::HashMap;
+
+use fissio_config::{Condition, ConfigError, PipelineConfig};
+use regex::Regex;
+use serde_json::Value;
+
+use crate::schema::{has_field, lookup};
+use crate::RunContext;
+
+/// The regexes of a pipeline's edge conditions, compiled once before the
+/// run and keyed by pattern.
+pub(crate) type Patterns = HashMap<String, Regex>;
+
+/// Returns `true` if every rule `condition` sets holds for `text` and the
+/// outputs in `context`.
+///
+/// A regex missing from `patterns` never matches; [`compile_conditions`]
+/// compiles every valid one and rejects the others before a run.
+pub(crate) fn holds(condition: &Condition, patterns: &Patterns, text: &str, context: &RunContext) -> bool {
+    if let Some(pattern) = &condition.regex {
+        if !patterns.get(pattern).is_some_and(|regex| regex.is_match(text)) {
+            return false;
+        }
+    }
+    if !condition.keywords.is_empty() {
+        let text = text.to_lowercase();
+        if !condition.keywords.iter().any(|k| text.contains(&k.to_lowercase())) {
+            return false;
+        }
+    }
+    let length = text.chars().count();
+    if condition.min_length.is_some_and(|min| length < min) || condition.max_length.is_some_and(|max| length > max) {
+        return false;
+    }
+    match &condition.path {
+        Some(path) => field(path, context).is_some_and(|value| compare(condition, &value)),
+        None => true,
+    }
+}
+
+/// Looks up `<node_id>.<field>...` in the context. Outputs stored as text
+/// are parsed as JSON, as for template field references.
+fn field(path: &str, context: &RunContext) -> Option<Value> {
+    let (id, path) = path.split_once('.')?;
+    let path: Vec<&str> = path.split('.').collect();
+    match context.output(id)? {
+        Value::String(text) => lookup(&serde_json::from_str(text).ok()?, &path).cloned(),
+        value => lookup(value, &path).cloned(),
+    }
+}
+
+/// Applies a condition's comparisons to a field's value. With none set, the
+/// field must not be `null` or `false`.
+fn compare(condition: &Condition, value: &Value) -> bool {
+    if !condition.compares_field() {
+        return !matches!(value, Value::Null | Value::Bool(false));
+    }
+    if condition.equals.as_ref().is_some_and(|expected| !equal(value, expected)) {
+        return false;
+    }
+    if !condition.one_of.is_empty() && !condition.one_of.iter().any(|expected| equal(value, expected)) {
+        return false;
+    }
+    if condition.min.is_some() || condition.max.is_some() {
+        let Some(number) = value.as_f64() else {
+            return false;
+        };
+        if condition.min.is_some_and(|min| number < min) || condition.max.is_some_and(|max| number > max) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Compares JSON values, treating numbers as equal by value (`1` and `1.0`).
+fn equal(value: &Value, expected: &Value) -> bool {
+    match (value.as_f64(), expected.as_f64()) {
+        (Some(a), Some(b)) => a == b,
+        _ => value == expected,
+    }
+}
+
+/// Checks every edge condition and compiles their regexes: that it sets a
+/// rule, that its regex compiles, that its length bounds are ordered, that
+/// its path names a node and an output field it can have, that comparisons
+/// come with a path, and that it isn't on a feedback edge.
+pub(crate) fn compile_conditions(config: &PipelineConfig) -> Result<Patterns, ConfigError> {
+    let mut patterns = Patterns::new();
+    for edge in &config.edges {
+        let Some(condition) = &edge.when else {
+            continue;
+        };
+        let invalid = |message: String| {
+            ConfigError::validation(&config.id, format!("condition on edge to {:?} {}", edge.to.as_vec(), message))
+        };
+
+        if edge.is_feedback() {
+            return Err(invalid("is on a feedback edge, which loops on its evaluator's verdict".to_string()));
+        }
+        if *condition == Condition::default() {
+            return Err(invalid("sets no rule".to_string()));
+        }
+        if let Some(pattern) = &condition.regex {
+            let regex = Regex::new(pattern).map_err(|e| invalid(format!("has an invalid regex: {}", e)))?;
+            patterns.insert(pattern.clone(), regex);
+        }
+        if let (Some(min), Some(max)) = (condition.min_length, condition.max_length) {
+            if min > max {
+                return Err(invalid(format!("has min_length {} above max_length {}", min, max)));
+            }
+        }
+        match &condition.path {
+            Some(path) => {
+                let segments: Vec<&str> = path.split('.').collect();
+                let [id, field, ..] = segments[..] else {
+                    return Err(invalid(format!("has path '{}', which should be '<node_id>.<field>'", path)));
+                };
+                if segments.iter().any(|s| s.is_empty()) {
+                    return Err(invalid(format!("has malformed path '{}'", path)));
+                }
+                let Some(node) = config.nodes.iter().find(|n| n.id == id) else {
+                    return Err(invalid(format!("refers to unknown node '{}'", id)));
+                };
+                if !has_field(node, field) {
+                    return Err(invalid(format!("refers to node '{}', which has no output field '{}'", id, field)));
+                }
+            }
+            None if condition.compares_field() => {
+                return Err(invalid("compares a field but sets no path".to_string()));
+            }
+            None => {}
+        }
+    }
+    Ok(patterns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NodeOutput;
+    use fissio_config::NodeType;
+    use serde_json::json;
+
+    #[test]
+    fn test_holds() {
+        let mut context = RunContext::default();
+        context.store("extract", &NodeOutput::json(json!({ "priority": 4, "team": "billing", "urgent": false })));
+        context.store("notes", &NodeOutput::new(r#"{"lang": "de"}"#));
+        let check = |condition: Value, text: &str| {
+            let condition: Condition = serde_json::from_value(condition).unwrap();
+            let patterns = condition.regex.iter().map(|p| (p.clone(), Regex::new(p).unwrap())).collect();
+            holds(&condition, &patterns, text, &context)
+        };
+
+        assert!(check(json!({ "keywords": ["Refund", "invoice"] }), "I want a refund"));
+        assert!(!check(json!({ "keywords": ["refund"] }), "Where is my order?"));
+        assert!(check(json!({ "regex": r"^\d+$" }), "12345"));
+        assert!(!check(json!({ "regex": r"^\d+$", "min_length": 6 }), "12345"));
+        assert!(check(json!({ "max_length": 5 }), "héllo"));
+
+        assert!(check(json!({ "path": "extract.priority", "min": 4 }), ""));
+        assert!(check(json!({ "path": "extract.priority", "equals": 4.0 }), ""));
+        assert!(!check(json!({ "path": "extract.priority", "max": 3 }), ""));
+        assert!(check(json!({ "path": "extract.team", "one_of": ["sales", "billing"] }), ""));
+        assert!(!check(json!({ "path": "extract.team", "min": 1 }), ""));
+        assert!(check(json!({ "path": "extract.team" }), ""));
+        assert!(!check(json!({ "path": "extract.urgent" }), ""));
+        assert!(!check(json!({ "path": "extract.missing" }), ""));
+        assert!(check(json!({ "path": "notes.lang", "equals": "de" }), ""));
+        assert!(!check(json!({ "path": "absent.team" }), ""));
+    }
+
+    #[test]
+    fn test_compile_conditions() {
+        let pipeline = |when: Value| {
+            let mut config = PipelineConfig::builder("cond", "Conditions")
+                .node("extract", NodeType::Llm)
+                    .output_schema(json!({
+                        "type": "object",
+                        "properties": { "priority": { "type": "integer" } },
+                        "additionalProperties": false
+                    }))
+                    .done()
+                .node("escalate", NodeType::Llm).done()
+                .edge("input", "extract")
+                .edge("extract", "escalate")
+                .build();
+            config.edges[1].when = Some(serde_json::from_value(when).unwrap());
+            compile_conditions(&config).map_err(|e| e.to_string())
+        };
+
+        assert!(pipeline(json!({ "path": "extract.priority", "min": 4 })).is_ok());
+        assert!(pipeline(json!({ "regex": r"^\d+$" })).unwrap().contains_key(r"^\d+$"));
+        assert!(pipeline(json!({ "regex": "(" })).unwrap_err().contains("invalid regex"));
+        assert!(pipeline(json!({})).unwrap_err().contains("sets no rule"));
+        assert!(pipeline(json!({ "min_length": 10, "max_length": 5 })).unwrap_err().contains("above max_length"));
+        assert!(pipeline(json!({ "path": "extract" })).unwrap_err().contains("'<node_id>.<field>'"));
+        assert!(pipeline(json!({ "path": "triage.team" })).unwrap_err().contains("unknown node 'triage'"));
+        assert!(pipeline(json!({ "path": "extract.team" })).unwrap_err().contains("no output field 'team'"));
+        assert!(pipeline(json!({ "equals": 4 })).unwrap_err().contains("sets no path"));
+    }
+}
@@ -0,0 +1,192 @@
+//! The run context: every node's latest output, typed, with how it was produced.
+//!
+//! Each node that runs stores a [`NodeRecord`] in the run's [`RunContext`].
+//! Its value is JSON: the parsed value for structured outputs (nodes with an
+//! `output_schema`), orchestrator plans, map results, and custom nodes that
+//! set [`NodeOutput::value`]; a string for everything else. The record also
+//! keeps the node's status, verdict, and usage (model, tokens, time).
+//!
+//! Custom executors read the context through [`NodeInput`](crate::NodeInput),
+//! and [`PipelineEngine::execute_with_context`](crate::PipelineEngine::execute_with_context)
+//! returns it to callers along with the run's output:
+//!
+//! ```rust,ignore
+//! let run = engine.execute_with_context("Refund order 12", &[], CancellationToken::new()).await?;
+//! let team = run.context.field("triage", &["team"]);
+//! let tokens = run.context.get("writer").map(|r| r.usage.output_tokens);
+//! ```
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::schema::lookup;
+use crate::{NodeOutput, Verdict};
+
+/// How a node's latest execution ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeStatus {
+    /// The node produced its output.
+    Completed,
+    /// The node stopped the run: a gate or approval rejecting its input, or
+    /// an approval awaiting a decision.
+    Halted,
+    /// The node's branch failed and its edge's failure policy tolerated it;
+    /// the value is the error message.
+    Failed,
+}
+
+/// What a node's execution used.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NodeUsage {
+    /// ID of the model the successful attempt ran with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub elapsed_ms: u64,
+}
+
+/// A node's latest output and how it was produced.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeRecord {
+    /// The output: structured JSON, or a string.
+    pub value: Value,
+    pub status: NodeStatus,
+    /// Verdict from an Evaluator node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verdict: Option<Verdict>,
+    #[serde(default)]
+    pub usage: NodeUsage,
+    /// Text passed on to the next nodes in place of the value; a router
+    /// forwards its input while its classification is the value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forwarded: Option<String>,
+}
+
+impl NodeRecord {
+    /// Returns the value as text: strings as-is, other values as compact JSON.
+    pub fn text(&self) -> Cow<'_, str> {
+        match &self.value {
+            Value::String(s) => Cow::Borrowed(s),
+            other => Cow::Owned(other.to_string()),
+        }
+    }
+}
+
+/// Every node's latest output in a run, keyed by node ID.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunContext {
+    pub nodes: HashMap<String, NodeRecord>,
+}
+
+impl RunContext {
+    /// Returns the record of node `node_id`, if it has run.
+    pub fn get(&self, node_id: &str) -> Option<&NodeRecord> {
+        self.nodes.get(node_id)
+    }
+
+    /// Returns the output of node `node_id`, unless it hasn't run or failed.
+    pub fn output(&self, node_id: &str) -> Option<&Value> {
+        self.get(node_id).filter(|r| r.status != NodeStatus::Failed).map(|r| &r.value)
+    }
+
+    /// Returns the output of node `node_id` as text (see [`NodeRecord::text`]),
+    /// unless it hasn't run or failed.
+    pub fn text(&self, node_id: &str) -> Option<Cow<'_, str>> {
+        self.get(node_id).filter(|r| r.status != NodeStatus::Failed).map(NodeRecord::text)
+    }
+
+    /// Returns the text node `node_id` passes on to the nodes after it: what
+    /// it forwarded, if anything, otherwise its output as text.
+    pub fn passed_on(&self, node_id: &str) -> Option<Cow<'_, str>> {
+        let record = self.get(node_id).filter(|r| r.status != NodeStatus::Failed)?;
+        Some(record.forwarded.as_deref().map_or_else(|| record.text(), Cow::Borrowed))
+    }
+
+    /// Looks up a field of node `node_id`'s output by path; numeric segments
+    /// index arrays, e.g. `["results", "0", "url"]`.
+    pub fn field<S: AsRef<str>>(&self, node_id: &str, path: &[S]) -> Option<&Value> {
+        lookup(self.output(node_id)?, path)
+    }
+
+    /// Returns the verdict of evaluator `node_id`, if it has run.
+    pub fn verdict(&self, node_id: &str) -> Option<&Verdict> {
+        self.get(node_id)?.verdict.as_ref()
+    }
+
+    /// Returns the error node `node_id`'s branch failed with, if it did.
+    pub fn error(&self, node_id: &str) -> Option<&str> {
+        self.get(node_id).filter(|r| r.status == NodeStatus::Failed)?.value.as_str()
+    }
+
+    /// Stores a node's output, replacing its previous record.
+    pub(crate) fn store(&mut self, node_id: &str, output: &NodeOutput) {
+        let record = NodeRecord {
+            value: output.value.clone().unwrap_or_else(|| Value::String(output.content.clone())),
+            status: if output.halted { NodeStatus::Halted } else { NodeStatus::Completed },
+            verdict: output.verdict.clone(),
+            usage: output.usage.clone().unwrap_or_default(),
+            forwarded: output.value.as_ref()
+                .filter(|value| output.content != value.to_string())
+                .map(|_| output.content.clone()),
+        };
+        self.nodes.insert(node_id.to_string(), record);
+    }
+
+    /// Records the error of a node whose branch failed. Errors from several
+    /// subtasks of one worker are kept together, one per line.
+    pub(crate) fn store_failure(&mut self, node_id: &str, error: &str) {
+        let message = match self.error(node_id) {
+            Some(previous) => format!("{}\n{}", previous, error),
+            None => error.to_string(),
+        };
+        let record = NodeRecord {
+            value: Value::String(message),
+            status: NodeStatus::Failed,
+            verdict: None,
+            usage: NodeUsage::default(),
+            forwarded: None,
+        };
+        self.nodes.insert(node_id.to_string(), record);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_store_and_read() {
+        let mut context = RunContext::default();
+        context.store("writer", &NodeOutput::new("Dear customer"));
+        context.store("triage", &NodeOutput::json(json!({ "team": "billing", "tags": ["refund"] })));
+        context.store("plan", &NodeOutput::json(json!(["a", 1])));
+        let routed = NodeOutput { content: "Refund it".to_string(), value: Some(json!({ "targets": ["billing"] })), ..NodeOutput::default() };
+        context.store("router", &routed);
+        context.store_failure("web", "timed out");
+        context.store_failure("web", "rate limited");
+
+        assert_eq!(context.text("writer").unwrap(), "Dear customer");
+        assert_eq!(context.text("plan").unwrap(), r#"["a",1]"#);
+        assert_eq!(context.field("triage", &["tags", "0"]), Some(&json!("refund")));
+        assert_eq!(context.field("writer", &["team"]), None);
+        assert_eq!(context.passed_on("router").unwrap(), "Refund it");
+        assert_eq!(context.passed_on("triage").unwrap(), context.text("triage").unwrap());
+        assert_eq!(context.field("router", &["targets", "0"]), Some(&json!("billing")));
+
+        assert_eq!(context.output("web"), None);
+        assert_eq!(context.error("web"), Some("timed out\nrate limited"));
+        assert_eq!(context.error("writer"), None);
+        assert_eq!(context.get("web").unwrap().status, NodeStatus::Failed);
+
+        let saved = serde_json::to_value(&context).unwrap();
+        assert_eq!(serde_json::from_value::<RunContext>(saved).unwrap(), context);
+        let corrupt = json!({ "input": "hi", "writer": "Dear customer" });
+        assert!(serde_json::from_value::<RunContext>(corrupt).is_err());
+    }
+}
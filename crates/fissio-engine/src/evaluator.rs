@@ -0,0 +1,207 @@
+//! Evaluator nodes: rubric-based scoring with a structured pass/fail verdict.
+//!
+//! An evaluator asks its model to grade the incoming content against a rubric
+//! and passes the content through unchanged. The parsed [`Verdict`] is stored
+//! with the node's output in the run context so feedback edges, routers, and
+//! `on_pass` / `on_fail` branches can act on it.
+//!
+//! # Configuration
+//!
+//! ```json
+//! {
+//!   "id": "evaluator",
+//!   "type": "evaluator",
+//!   "config": {
+//!     "rubric": ["Answers the question directly", "Cites sources"],
+//!     "threshold": 0.7,
+//!     "on_pass": "publisher",
+//!     "on_fail": "escalate"
+//!   }
+//! }
+//! ```
+//!
+//! `rubric` may be a string or a list of criteria. When `threshold` is set,
+//! the verdict passes if `score >= threshold`; otherwise the model's own
+//! `pass` field decides.
+
+use fissio_core::AgentError;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::recording::Calls;
+use crate::{extract_json, ExecutionMetrics};
+
+/// Structured verdict produced by an evaluator node.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Verdict {
+    /// Quality score from 0.0 to 1.0.
+    pub score: f64,
+    /// Whether the content meets the rubric.
+    pub pass: bool,
+    /// Actionable feedback explaining the score.
+    #[serde(default)]
+    pub critique: String,
+}
+
+impl Verdict {
+    /// Parses a verdict from model output.
+    ///
+    /// Accepts a JSON object (optionally wrapped in prose or code fences) with
+    /// `score`, `pass`, and `critique` fields. Output that isn't JSON passes
+    /// only if its first word is `PASS`, and the whole text becomes the critique.
+    pub fn parse(output: &str) -> Self {
+        let trimmed = output.trim();
+        if let Some(obj) = extract_json(trimmed).filter(|v| v.is_object()) {
+            let score = obj.get("score").and_then(|v| v.as_f64());
+            let pass = obj.get("pass").and_then(|v| v.as_bool());
+            let critique = obj
+                .get("critique")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string();
+            let pass = pass.unwrap_or_else(|| score.is_some_and(|s| s >= DEFAULT_THRESHOLD));
+            let score = score.unwrap_or(if pass { 1.0 } else { 0.0 });
+            return Self { score, pass, critique };
+        }
+
+        let pass = trimmed
+            .split_whitespace()
+            .next()
+            .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()).eq_ignore_ascii_case("pass"))
+            .unwrap_or(false);
+        Self {
+            score: if pass { 1.0 } else { 0.0 },
+            pass,
+            critique: trimmed.to_string(),
+        }
+    }
+}
+
+/// Score at or above which a verdict without an explicit `pass` field passes.
+const DEFAULT_THRESHOLD: f64 = 0.7;
+
+/// Evaluator settings read from a node's `config`.
+#[derive(Debug, Default)]
+pub(crate) struct EvaluatorConfig {
+    rubric: Option<String>,
+    threshold: Option<f64>,
+    on_pass: Option<String>,
+    on_fail: Option<String>,
+}
+
+impl EvaluatorConfig {
+    pub(crate) fn from_value(config: &serde_json::Value) -> Self {
+        let rubric = match config.get("rubric") {
+            Some(serde_json::Value::String(s)) => Some(s.clone()),
+            Some(serde_json::Value::Array(items)) => Some(
+                items
+                    .iter()
+                    .filter_map(|v| v.as_str())
+                    .map(|c| format!("- {}", c))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            _ => None,
+        };
+        let text = |key: &str| config.get(key).and_then(|v| v.as_str()).map(String::from);
+
+        Self {
+            rubric,
+            threshold: config.get("threshold").and_then(|v| v.as_f64()),
+            on_pass: text("on_pass"),
+            on_fail: text("on_fail"),
+        }
+    }
+
+    /// Returns the configured branch target for a verdict, if any.
+    pub(crate) fn branch(&self, verdict: &Verdict) -> Option<&str> {
+        if verdict.pass {
+            self.on_pass.as_deref()
+        } else {
+            self.on_fail.as_deref()
+        }
+    }
+}
+
+/// Executes an Evaluator node: the LLM grades the input against the rubric.
+pub(crate) async fn execute_evaluator(
+    calls: Calls<'_>,
+    prompt: Option<&str>,
+    config: &EvaluatorConfig,
+    input: &str,
+) -> Result<(Verdict, ExecutionMetrics), AgentError> {
+    let mut system_prompt = prompt
+        .unwrap_or("Evaluate the following content for quality, accuracy, and completeness.")
+        .to_string();
+    if let Some(ref rubric) = config.rubric {
+        system_prompt.push_str(&format!("\n\nRubric:\n{}", rubric));
+    }
+    system_prompt.push_str(
+        "\n\nRespond with ONLY a JSON object of the form \
+        {\"score\": <number from 0.0 to 1.0>, \"pass\": <true|false>, \"critique\": \"<specific, actionable feedback>\"}.",
+    );
+
+    let response = calls.chat(&system_prompt, input).await?;
+    let mut verdict = Verdict::parse(&response.content);
+    if let Some(threshold) = config.threshold {
+        verdict.pass = verdict.score >= threshold;
+    }
+
+    if verdict.critique.is_empty() && !verdict.pass {
+        warn!("║     ⚠ Evaluator failed the input without a critique");
+    }
+    info!("║     Verdict: {} (score {:.2})", if verdict.pass { "PASS" } else { "FAIL" }, verdict.score);
+
+    let mut metrics = ExecutionMetrics::default();
+    metrics.accumulate(&response.metrics);
+    metrics.iteration_count = 1;
+
+    Ok((verdict, metrics))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verdict_parse_json() {
+        let verdict = Verdict::parse(r#"{"score": 0.9, "pass": true, "critique": "Clear and complete"}"#);
+        assert_eq!(
+            verdict,
+            Verdict { score: 0.9, pass: true, critique: "Clear and complete".to_string() }
+        );
+
+        let verdict = Verdict::parse("```json\n{\"score\": 0.4, \"critique\": \"Too short\"}\n```");
+        assert!(!verdict.pass);
+        assert_eq!(verdict.score, 0.4);
+        assert_eq!(verdict.critique, "Too short");
+
+        assert!(!Verdict::parse(r#"{"pass": false, "critique": "Too short"}"#).pass);
+    }
+
+    #[test]
+    fn test_verdict_parse_text() {
+        assert!(Verdict::parse("PASS").pass);
+        assert!(Verdict::parse("  pass: looks good").pass);
+        assert!(!Verdict::parse("FAIL - missing examples").pass);
+        assert!(!Verdict::parse("Passable, but needs work").pass);
+        assert!(!Verdict::parse("").pass);
+        assert_eq!(Verdict::parse("FAIL - missing examples").critique, "FAIL - missing examples");
+    }
+
+    #[test]
+    fn test_evaluator_config() {
+        let config = EvaluatorConfig::from_value(&serde_json::json!({
+            "rubric": ["Accurate", "Concise"],
+            "threshold": 0.8,
+            "on_fail": "escalate"
+        }));
+        assert_eq!(config.rubric.as_deref(), Some("- Accurate\n- Concise"));
+        assert_eq!(config.threshold, Some(0.8));
+
+        let failed = Verdict { score: 0.5, pass: false, critique: String::new() };
+        assert_eq!(config.branch(&failed), Some("escalate"));
+        let passed = Verdict { pass: true, ..failed };
+        assert_eq!(config.branch(&passed), None);
+    }
+}
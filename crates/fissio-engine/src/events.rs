@@ -0,0 +1,212 @@
+//! Typed execution events emitted while a pipeline runs.
+//!
+//! Attach a sender with [`PipelineEngine::with_events`](crate::PipelineEngine::with_events)
+//! to observe node lifecycle, tool calls, and routing decisions as they happen.
+
+use fissio_config::NodeType;
+use fissio_monitor::NodeMetrics;
+
+use crate::Subtask;
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+/// Sending half of an engine event channel.
+pub type EventSender = mpsc::UnboundedSender<EngineEvent>;
+
+/// Receiving half of an engine event channel.
+pub type EventReceiver = mpsc::UnboundedReceiver<EngineEvent>;
+
+/// Creates a new unbounded channel for engine events.
+pub fn event_channel() -> (EventSender, EventReceiver) {
+    mpsc::unbounded_channel()
+}
+
+/// A structured event describing pipeline progress.
+///
+/// Serializes with a `type` tag matching [`EngineEvent::name`], e.g.
+/// `{"type": "node_started", "node_id": "researcher", ...}`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EngineEvent {
+    /// A node began executing.
+    NodeStarted {
+        node_id: String,
+        node_type: NodeType,
+        step: usize,
+    },
+    /// A node completed successfully.
+    NodeFinished {
+        node_id: String,
+        metrics: NodeMetrics,
+    },
+    /// A node reused its output from the checkpoint of the run being resumed.
+    NodeRestored {
+        node_id: String,
+    },
+    /// A worker node invoked a tool.
+    ToolCallStarted {
+        node_id: String,
+        tool: String,
+        arguments: serde_json::Value,
+    },
+    /// A tool call returned (successfully or not).
+    ToolCallFinished {
+        node_id: String,
+        tool: String,
+        elapsed_ms: u64,
+        success: bool,
+    },
+    /// A feedback edge sent execution back for another iteration.
+    FeedbackIteration {
+        node_id: String,
+        targets: Vec<String>,
+        iteration: u32,
+        max_iterations: u32,
+    },
+    /// An orchestrator planned subtasks for its workers.
+    PlanCreated {
+        node_id: String,
+        subtasks: Vec<Subtask>,
+    },
+    /// A gate rejected its input; `reject_target` is `None` when the run halts.
+    GateRejected {
+        node_id: String,
+        reason: String,
+        reject_target: Option<String>,
+    },
+    /// The run reached an approval node and will suspend; decide on `content`
+    /// to continue run `run_id`.
+    ApprovalRequested {
+        run_id: String,
+        node_id: String,
+        content: String,
+        instructions: Option<String>,
+    },
+    /// A node attempt failed and will be retried, on `model` after `delay_ms`.
+    NodeRetry {
+        node_id: String,
+        attempt: u32,
+        error: String,
+        model: String,
+        delay_ms: u64,
+    },
+    /// A router node chose its targets.
+    RouterDecision {
+        node_id: String,
+        targets: Vec<String>,
+    },
+    /// Execution failed, attributed to a node unless the whole run was
+    /// cancelled or timed out.
+    Error {
+        node_id: Option<String>,
+        message: String,
+    },
+}
+
+impl EngineEvent {
+    /// Returns the event name (matches the serialized `type` tag).
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::NodeStarted { .. } => "node_started",
+            Self::NodeFinished { .. } => "node_finished",
+            Self::NodeRestored { .. } => "node_restored",
+            Self::ToolCallStarted { .. } => "tool_call_started",
+            Self::ToolCallFinished { .. } => "tool_call_finished",
+            Self::FeedbackIteration { .. } => "feedback_iteration",
+            Self::PlanCreated { .. } => "plan_created",
+            Self::GateRejected { .. } => "gate_rejected",
+            Self::ApprovalRequested { .. } => "approval_requested",
+            Self::NodeRetry { .. } => "node_retry",
+            Self::RouterDecision { .. } => "router_decision",
+            Self::Error { .. } => "error",
+        }
+    }
+
+    /// Returns the event with the node IDs it names under `prefix`, as a
+    /// nested run under the pipeline or map node `prefix` names emits it. An
+    /// error of the whole nested run is attributed to that node.
+    pub(crate) fn prefixed(self, prefix: &str) -> Self {
+        let id = |node_id: String| format!("{}{}", prefix, node_id);
+        let ids = |node_ids: Vec<String>| node_ids.into_iter().map(id).collect();
+        match self {
+            Self::NodeStarted { node_id, node_type, step } => Self::NodeStarted { node_id: id(node_id), node_type, step },
+            Self::NodeFinished { node_id, metrics } => Self::NodeFinished {
+                node_id: id(node_id),
+                metrics: NodeMetrics { node_id: id(metrics.node_id.clone()), ..metrics },
+            },
+            Self::NodeRestored { node_id } => Self::NodeRestored { node_id: id(node_id) },
+            Self::ToolCallStarted { node_id, tool, arguments } => {
+                Self::ToolCallStarted { node_id: id(node_id), tool, arguments }
+            }
+            Self::ToolCallFinished { node_id, tool, elapsed_ms, success } => {
+                Self::ToolCallFinished { node_id: id(node_id), tool, elapsed_ms, success }
+            }
+            Self::FeedbackIteration { node_id, targets, iteration, max_iterations } => {
+                Self::FeedbackIteration { node_id: id(node_id), targets: ids(targets), iteration, max_iterations }
+            }
+            Self::PlanCreated { node_id, subtasks } => Self::PlanCreated {
+                node_id: id(node_id),
+                subtasks: subtasks.into_iter().map(|s| Subtask { worker: id(s.worker), ..s }).collect(),
+            },
+            Self::GateRejected { node_id, reason, reject_target } => {
+                Self::GateRejected { node_id: id(node_id), reason, reject_target: reject_target.map(id) }
+            }
+            Self::ApprovalRequested { run_id, node_id, content, instructions } => {
+                Self::ApprovalRequested { run_id, node_id: id(node_id), content, instructions }
+            }
+            Self::NodeRetry { node_id, attempt, error, model, delay_ms } => {
+                Self::NodeRetry { node_id: id(node_id), attempt, error, model, delay_ms }
+            }
+            Self::RouterDecision { node_id, targets } => Self::RouterDecision { node_id: id(node_id), targets: ids(targets) },
+            Self::Error { node_id, message } => Self::Error {
+                node_id: Some(node_id.map_or_else(|| prefix.trim_end_matches('/').to_string(), id)),
+                message,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_serializes_with_name_tag() {
+        let event = EngineEvent::RouterDecision {
+            node_id: "router".to_string(),
+            targets: vec!["code_llm".to_string()],
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], event.name());
+        assert_eq!(json["targets"][0], "code_llm");
+
+        let event = EngineEvent::NodeStarted {
+            node_id: "llm".to_string(),
+            node_type: NodeType::Llm,
+            step: 1,
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "node_started");
+        assert_eq!(json["node_type"], "llm");
+    }
+
+    #[test]
+    fn test_prefixed_event_names_nested_nodes() {
+        let event = EngineEvent::RouterDecision {
+            node_id: "triage".to_string(),
+            targets: vec!["billing".to_string()],
+        };
+        match event.prefixed("support/") {
+            EngineEvent::RouterDecision { node_id, targets } => {
+                assert_eq!((node_id.as_str(), targets), ("support/triage", vec!["support/billing".to_string()]));
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+
+        let event = EngineEvent::Error { node_id: None, message: "Timed out".to_string() };
+        match event.prefixed("each[2]/") {
+            EngineEvent::Error { node_id, .. } => assert_eq!(node_id.as_deref(), Some("each[2]")),
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+}
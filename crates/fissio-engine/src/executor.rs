@@ -0,0 +1,103 @@
+//! Custom node types: user-defined executors registered by name.
+//!
+//! Implement [`NodeExecutor`], register it in a [`NodeRegistry`], and attach
+//! the registry with [`PipelineEngine::with_nodes`](crate::PipelineEngine::with_nodes).
+//! Nodes of type `custom` name their executor in `config.executor`; the rest
+//! of `config` is available to the executor through [`NodeContext::node`],
+//! and the outputs of earlier nodes through [`NodeContext::run`].
+//!
+//! ```rust,ignore
+//! use fissio_engine::{NodeContext, NodeExecutor, NodeOutput, NodeRegistry};
+//! use async_trait::async_trait;
+//!
+//! struct OrderLookup { db: Db }
+//!
+//! #[async_trait]
+//! impl NodeExecutor for OrderLookup {
+//!     async fn execute(&self, ctx: &NodeContext<'_>) -> Result<NodeOutput, AgentError> {
+//!         let table = ctx.node.config["table"].as_str().unwrap_or("orders");
+//!         Ok(NodeOutput::new(self.db.find(table, ctx.input).await?))
+//!     }
+//! }
+//!
+//! let mut nodes = NodeRegistry::new();
+//! nodes.register("order_lookup", OrderLookup { db });
+//! let engine = PipelineEngine::new(config, models, default_model, HashMap::new()).with_nodes(nodes);
+//! ```
+//!
+//! ```json
+//! { "id": "lookup", "type": "custom", "config": { "executor": "order_lookup", "table": "orders" } }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use fissio_config::NodeConfig;
+use fissio_core::{AgentError, ModelConfig};
+
+use crate::{NodeInput, NodeOutput};
+
+/// Everything a custom executor can see when its node runs.
+pub struct NodeContext<'a> {
+    /// The node being executed, with its prompt template rendered.
+    pub node: &'a NodeConfig,
+    /// The node's input, assembled from upstream outputs as for built-in nodes.
+    pub input: &'a str,
+    /// The user input, history, and outputs of the nodes that have run so far.
+    pub run: &'a NodeInput,
+    /// The model resolved for this node, for executors that make LLM calls.
+    pub model: &'a ModelConfig,
+}
+
+/// Trait for implementing custom node types.
+///
+/// The returned [`NodeOutput`] is handled like a built-in node's: `content`
+/// (or `value`, if set) is stored as the node's output, non-empty
+/// `next_nodes` restrict which outgoing edges are followed, and `halted`
+/// stops the run.
+#[async_trait]
+pub trait NodeExecutor: Send + Sync {
+    /// Executes the node.
+    async fn execute(&self, ctx: &NodeContext<'_>) -> Result<NodeOutput, AgentError>;
+}
+
+/// Registry of custom node executors, keyed by the name used in `config.executor`.
+#[derive(Default)]
+pub struct NodeRegistry {
+    executors: HashMap<String, Arc<dyn NodeExecutor>>,
+}
+
+impl NodeRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an executor under `name`.
+    ///
+    /// If an executor with the same name already exists, it will be replaced.
+    pub fn register<E: NodeExecutor + 'static>(&mut self, name: impl Into<String>, executor: E) {
+        self.executors.insert(name.into(), Arc::new(executor));
+    }
+
+    /// Gets an executor by name.
+    pub fn get(&self, name: &str) -> Option<Arc<dyn NodeExecutor>> {
+        self.executors.get(name).cloned()
+    }
+
+    /// Returns true if an executor with the given name is registered.
+    pub fn has(&self, name: &str) -> bool {
+        self.executors.contains_key(name)
+    }
+
+    /// Returns the names of all registered executors.
+    pub fn names(&self) -> Vec<String> {
+        self.executors.keys().cloned().collect()
+    }
+}
+
+/// Returns the executor name configured on a custom node.
+pub(crate) fn executor_name(node: &NodeConfig) -> Option<&str> {
+    node.config.get("executor").and_then(|v| v.as_str())
+}
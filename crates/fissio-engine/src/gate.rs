@@ -0,0 +1,202 @@
+//! Gate nodes: validate input and halt or divert the pipeline on rejection.
+//!
+//! Deterministic rules run first so obviously invalid input is rejected
+//! without spending tokens. An LLM check runs afterwards only if the node has
+//! a `prompt` or a `check` criterion.
+//!
+//! # Configuration
+//!
+//! ```json
+//! {
+//!   "id": "scope_gate",
+//!   "type": "gate",
+//!   "config": {
+//!     "check": "The request is about our product or an order",
+//!     "regex": "\\S",
+//!     "max_length": 4000,
+//!     "json": false,
+//!     "reject_target": "refusal",
+//!     "message": "Sorry, I can only help with questions about your orders."
+//!   }
+//! }
+//! ```
+//!
+//! On rejection the gate outputs `message` (or a default naming the failed
+//! check). With `reject_target` set, only the edge to that node is followed;
+//! otherwise the run halts and the message becomes the pipeline output.
+//! The `regex` is compiled, and `reject_target` checked against the gate's
+//! outgoing edges, before the run.
+
+use fissio_config::{ConfigError, NodeType, PipelineConfig};
+use fissio_core::AgentError;
+use regex::Regex;
+use tracing::info;
+
+use crate::condition::Patterns;
+use crate::recording::Calls;
+use crate::schedule::is_target;
+use crate::{ExecutionMetrics, Verdict};
+
+/// Gate settings read from a node's `config`.
+#[derive(Debug, Default)]
+pub(crate) struct GateConfig {
+    regex: Option<String>,
+    max_length: Option<usize>,
+    require_json: bool,
+    check: Option<String>,
+    pub(crate) reject_target: Option<String>,
+    message: Option<String>,
+}
+
+impl GateConfig {
+    pub(crate) fn from_value(config: &serde_json::Value) -> Self {
+        let text = |key: &str| config.get(key).and_then(|v| v.as_str()).map(String::from);
+        Self {
+            regex: text("regex"),
+            max_length: config.get("max_length").and_then(|v| v.as_u64()).map(|n| n as usize),
+            require_json: config.get("json").and_then(|v| v.as_bool()).unwrap_or(false),
+            check: text("check"),
+            reject_target: text("reject_target"),
+            message: text("message"),
+        }
+    }
+
+    /// Applies the deterministic rules, returning the reason for the first failure.
+    ///
+    /// The regex is looked up in `patterns`, where [`check_gate_nodes`]
+    /// compiled it; a missing one never matches.
+    pub(crate) fn check_rules(&self, patterns: &Patterns, input: &str) -> Result<(), String> {
+        if let Some(max) = self.max_length {
+            let len = input.chars().count();
+            if len > max {
+                return Err(format!("input is {} characters, limit is {}", len, max));
+            }
+        }
+        if let Some(ref pattern) = self.regex {
+            if !patterns.get(pattern).is_some_and(|regex| regex.is_match(input)) {
+                return Err(format!("input does not match pattern '{}'", pattern));
+            }
+        }
+        if self.require_json {
+            if let Err(e) = serde_json::from_str::<serde_json::Value>(input.trim()) {
+                return Err(format!("input is not valid JSON: {}", e));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the criterion for the LLM check, if one is configured.
+    pub(crate) fn criterion<'a>(&'a self, prompt: Option<&'a str>) -> Option<&'a str> {
+        prompt.or(self.check.as_deref())
+    }
+
+    /// Returns the user-visible rejection message.
+    pub(crate) fn rejection_message(&self, node_id: &str, reason: &str) -> String {
+        self.message
+            .clone()
+            .unwrap_or_else(|| format!("Request rejected by {}: {}", node_id, reason))
+    }
+}
+
+/// Checks every gate's settings, compiling its regex into `patterns`, and
+/// that its `reject_target` is one of its targets.
+pub(crate) fn check_gate_nodes(config: &PipelineConfig, patterns: &mut Patterns) -> Result<(), ConfigError> {
+    for node in config.nodes.iter().filter(|n| n.node_type == NodeType::Gate) {
+        let invalid = |message: String| ConfigError::validation(&config.id, format!("gate '{}' {}", node.id, message));
+        let gate = GateConfig::from_value(&node.config);
+        if let Some(pattern) = gate.regex {
+            let regex = Regex::new(&pattern).map_err(|e| invalid(format!("has an invalid regex: {}", e)))?;
+            patterns.insert(pattern, regex);
+        }
+        if let Some(target) = gate.reject_target {
+            if !is_target(config, &node.id, &target) {
+                return Err(invalid(format!("rejects to '{}', which isn't one of its targets", target)));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs a gate's LLM check: the model decides whether the input meets the criterion.
+pub(crate) async fn execute_gate_check(
+    calls: Calls<'_>,
+    criterion: &str,
+    input: &str,
+) -> Result<(Verdict, ExecutionMetrics), AgentError> {
+    let system_prompt = format!(
+        "You are a validation gate. Decide whether the input satisfies this requirement:\n{}\n\n\
+        Respond with ONLY a JSON object of the form \
+        {{\"pass\": <true|false>, \"critique\": \"<short reason if it fails>\"}}.",
+        criterion
+    );
+
+    let response = calls.chat(&system_prompt, input).await?;
+    let verdict = Verdict::parse(&response.content);
+    info!("║     Gate check: {}", if verdict.pass { "PASS" } else { "FAIL" });
+
+    let mut metrics = ExecutionMetrics::default();
+    metrics.accumulate(&response.metrics);
+    metrics.iteration_count = 1;
+
+    Ok((verdict, metrics))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the gate for `config` with its regex compiled.
+    fn gate(config: serde_json::Value) -> (GateConfig, Patterns) {
+        let gate = GateConfig::from_value(&config);
+        let patterns = gate.regex.iter().map(|p| (p.clone(), Regex::new(p).unwrap())).collect();
+        (gate, patterns)
+    }
+
+    #[test]
+    fn test_gate_rules() {
+        let (config, patterns) = gate(serde_json::json!({ "regex": "^order #\\d+", "max_length": 20 }));
+
+        assert!(config.check_rules(&patterns, "order #123 is late").is_ok());
+        assert!(config.check_rules(&patterns, "where is my order?").is_err());
+        assert!(config.check_rules(&patterns, "order #123 is late, please help").is_err());
+
+        let (config, patterns) = gate(serde_json::json!({ "json": true }));
+        assert!(config.check_rules(&patterns, r#"{"id": 1}"#).is_ok());
+        assert!(config.check_rules(&patterns, "not json").is_err());
+    }
+
+    #[test]
+    fn test_gate_config_messages() {
+        let config = GateConfig::from_value(&serde_json::json!({ "check": "On topic" }));
+        assert_eq!(config.criterion(None), Some("On topic"));
+        assert_eq!(config.criterion(Some("Custom")), Some("Custom"));
+        assert_eq!(config.rejection_message("gate", "too long"), "Request rejected by gate: too long");
+    }
+
+    #[test]
+    fn test_check_gate_nodes() {
+        let pipeline = |config: serde_json::Value| {
+            PipelineConfig::builder("gated", "Gated")
+                .node("gate", NodeType::Gate).config(config).done()
+                .node("answer", NodeType::Llm).done()
+                .node("refusal", NodeType::Llm).done()
+                .edge("input", "gate")
+                .edge("gate", "answer")
+                .edge("gate", "refusal")
+                .edge("answer", "output")
+                .edge("refusal", "output")
+                .build()
+        };
+
+        let mut patterns = Patterns::new();
+        let config = pipeline(serde_json::json!({ "regex": "\\S", "reject_target": "refusal" }));
+        check_gate_nodes(&config, &mut patterns).unwrap();
+        assert!(patterns.contains_key("\\S"));
+
+        let err = check_gate_nodes(&pipeline(serde_json::json!({ "regex": "(" })), &mut patterns).unwrap_err();
+        assert!(err.to_string().contains("gate 'gate' has an invalid regex"), "{}", err);
+
+        let err = check_gate_nodes(&pipeline(serde_json::json!({ "reject_target": "refusl" })), &mut patterns).unwrap_err();
+        assert!(err.to_string().contains("rejects to 'refusl', which isn't one of its targets"), "{}", err);
+    }
+}
@@ -0,0 +1,204 @@
+//! Conversation history policies: how much of the chat each node sees.
+//!
+//! LLM and router nodes include prior conversation turns in their calls
+//! according to `config.history`:
+//!
+//! | Value | History sent |
+//! |-------|--------------|
+//! | `"full"` (default) | Every prior turn |
+//! | `"none"` | Nothing; the node sees only its input |
+//! | `{"last": N}` or `N` | The last N user/assistant turns |
+//! | `"summary"` | A model-written summary, added to the system prompt |
+//!
+//! # Configuration
+//!
+//! ```json
+//! { "id": "classifier", "type": "router", "config": { "history": { "last": 2 } } }
+//! ```
+//!
+//! Summaries are generated once per model per run and shared by every node
+//! using that model.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use fissio_core::{AgentError, Message};
+use fissio_llm::LlmMetrics;
+use serde_json::Value;
+use tokio::sync::Mutex;
+use tracing::info;
+
+use crate::recording::Calls;
+
+/// How much conversation history a node receives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HistoryPolicy {
+    None,
+    Full,
+    /// The last N turns (user message plus assistant reply).
+    Last(usize),
+    Summary,
+}
+
+impl HistoryPolicy {
+    /// Reads the policy from a node's `config`, defaulting to [`HistoryPolicy::Full`].
+    pub(crate) fn from_config(node_id: &str, config: &Value) -> Result<Self, AgentError> {
+        let invalid = |value: &Value| {
+            AgentError::ParseError(format!("Invalid history policy for node '{}': {}", node_id, value))
+        };
+
+        match config.get("history") {
+            None | Some(Value::Null) => Ok(Self::Full),
+            Some(Value::String(s)) => match s.as_str() {
+                "none" => Ok(Self::None),
+                "full" => Ok(Self::Full),
+                "summary" => Ok(Self::Summary),
+                _ => Err(invalid(&Value::String(s.clone()))),
+            },
+            Some(value @ Value::Number(n)) => n.as_u64().map(|n| Self::Last(n as usize)).ok_or_else(|| invalid(value)),
+            Some(value @ Value::Object(obj)) => obj
+                .get("last")
+                .and_then(|v| v.as_u64())
+                .map(|n| Self::Last(n as usize))
+                .ok_or_else(|| invalid(value)),
+            Some(value) => Err(invalid(value)),
+        }
+    }
+
+    /// Returns the messages sent verbatim under this policy.
+    fn select<'a>(&self, history: &'a [Message]) -> &'a [Message] {
+        match self {
+            Self::Full => history,
+            Self::None | Self::Summary => &[],
+            Self::Last(turns) => {
+                let mut recent = &history[history.len().saturating_sub(turns * 2)..];
+                // Don't open mid-turn with an assistant reply
+                while recent.first().is_some_and(|m| m.role != fissio_core::MessageRole::User) {
+                    recent = &recent[1..];
+                }
+                recent
+            }
+        }
+    }
+}
+
+/// Conversation history resolved for one node call.
+#[derive(Debug, Default)]
+pub(crate) struct NodeHistory<'a> {
+    /// Prior turns to send as messages.
+    pub(crate) messages: &'a [Message],
+    /// Summary of the conversation, for the summary policy.
+    summary: Option<String>,
+    /// Usage of the summarization call, if this node made it.
+    pub(crate) metrics: LlmMetrics,
+}
+
+impl NodeHistory<'_> {
+    /// Returns the node's system prompt with the conversation summary appended, if any.
+    pub(crate) fn system_prompt<'p>(&self, base: &'p str) -> Cow<'p, str> {
+        match self.summary {
+            Some(ref summary) => Cow::Owned(format!("{}\n\nSummary of the conversation so far:\n{}", base, summary)),
+            None => Cow::Borrowed(base),
+        }
+    }
+}
+
+/// Resolves a node's history under `policy`, summarizing with `calls` if needed.
+///
+/// `summaries` caches summaries by model ID for the rest of the run.
+pub(crate) async fn resolve_history<'a>(
+    policy: HistoryPolicy,
+    history: &'a [Message],
+    calls: Calls<'_>,
+    summaries: &Mutex<HashMap<String, String>>,
+) -> Result<NodeHistory<'a>, AgentError> {
+    if policy != HistoryPolicy::Summary || history.is_empty() {
+        return Ok(NodeHistory { messages: policy.select(history), ..Default::default() });
+    }
+
+    // Held across the call so parallel branches share one summary
+    let mut summaries = summaries.lock().await;
+    let model = calls.model();
+    if let Some(summary) = summaries.get(&model.id) {
+        return Ok(NodeHistory { summary: Some(summary.clone()), ..Default::default() });
+    }
+
+    info!("║     → Summarizing {} history messages", history.len());
+    let response = calls
+        .chat(
+            "Summarize the following conversation in a few sentences. Keep facts, decisions, \
+            names, and open questions that later replies may depend on.",
+            &format_transcript(history),
+        )
+        .await?;
+
+    summaries.insert(model.id.clone(), response.content.clone());
+    Ok(NodeHistory { messages: &[], summary: Some(response.content), metrics: response.metrics })
+}
+
+/// Formats messages as a plain-text transcript.
+pub(crate) fn format_transcript(history: &[Message]) -> String {
+    history
+        .iter()
+        .map(|m| match m.role {
+            fissio_core::MessageRole::User => format!("User: {}", m.content),
+            fissio_core::MessageRole::Assistant => format!("Assistant: {}", m.content),
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conversation() -> Vec<Message> {
+        vec![
+            Message::user("Hi, I'm Ada"),
+            Message::assistant("Hello Ada!"),
+            Message::user("Where is order #12?"),
+            Message::assistant("It shipped yesterday."),
+        ]
+    }
+
+    #[test]
+    fn test_policy_from_config() {
+        let policy = |config: Value| HistoryPolicy::from_config("node", &config);
+        assert_eq!(policy(Value::Null).unwrap(), HistoryPolicy::Full);
+        assert_eq!(policy(serde_json::json!({ "history": "none" })).unwrap(), HistoryPolicy::None);
+        assert_eq!(policy(serde_json::json!({ "history": "summary" })).unwrap(), HistoryPolicy::Summary);
+        assert_eq!(policy(serde_json::json!({ "history": { "last": 2 } })).unwrap(), HistoryPolicy::Last(2));
+        assert_eq!(policy(serde_json::json!({ "history": 3 })).unwrap(), HistoryPolicy::Last(3));
+        assert!(policy(serde_json::json!({ "history": "recent" })).is_err());
+        assert!(policy(serde_json::json!({ "history": { "first": 1 } })).is_err());
+    }
+
+    #[test]
+    fn test_policy_select() {
+        let history = conversation();
+        assert_eq!(HistoryPolicy::Full.select(&history).len(), 4);
+        assert!(HistoryPolicy::None.select(&history).is_empty());
+        assert!(HistoryPolicy::Summary.select(&history).is_empty());
+
+        let last = HistoryPolicy::Last(1).select(&history);
+        assert_eq!(last.len(), 2);
+        assert_eq!(last[0].content, "Where is order #12?");
+        assert_eq!(HistoryPolicy::Last(10).select(&history).len(), 4);
+        assert!(HistoryPolicy::Last(0).select(&history).is_empty());
+
+        // A window starting on an assistant reply skips it
+        assert_eq!(HistoryPolicy::Last(1).select(&history[1..]).len(), 2);
+        assert_eq!(HistoryPolicy::Last(1).select(&history[..3]).len(), 1);
+    }
+
+    #[test]
+    fn test_summary_prompt_and_transcript() {
+        let history = NodeHistory { summary: Some("Ada asked about order #12.".into()), ..Default::default() };
+        assert_eq!(
+            history.system_prompt("Be brief."),
+            "Be brief.\n\nSummary of the conversation so far:\nAda asked about order #12."
+        );
+        assert_eq!(NodeHistory::default().system_prompt("Be brief."), "Be brief.");
+        assert!(format_transcript(&conversation()).starts_with("User: Hi, I'm Ada\n\nAssistant: Hello Ada!"));
+    }
+}
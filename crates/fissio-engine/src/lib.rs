@@ -6,6 +6,16 @@
 //! - [`ModelResolver`] — Resolves model IDs to configurations
 //! - [`EngineOutput`] — Stream or complete response from execution
 //! - [`NodeInput`] / [`NodeOutput`] — Data flowing through nodes
+//! - [`RunContext`] / [`NodeRecord`] — Every node's typed output, status, and usage, returned by
+//!   [`PipelineEngine::execute_with_context`]
+//! - [`EngineEvent`] — Structured progress events (node lifecycle, tool calls, routing)
+//! - [`Verdict`] — Score, pass/fail, and critique produced by evaluator nodes
+//! - [`Plan`] / [`Subtask`] — Work decomposition produced by orchestrator nodes
+//! - [`NodeExecutor`] / [`NodeRegistry`] — User-defined `custom` node types
+//! - [`RateLimiter`] — Concurrency and token budgets per model and provider, shared across runs
+//! - [`Checkpoint`] — Saved state of a run, used to resume it without repeating finished nodes
+//! - [`PendingApproval`] / [`Decision`] — Human review at `approval` nodes, which suspend the run
+//! - [`Replay`] — Recorded provider calls served in place of live ones
 //!
 //! # Quick Start
 //!
@@ -27,16 +37,176 @@
 //! match result {
 //!     EngineOutput::Stream(stream) => { /* consume stream */ }
 //!     EngineOutput::Complete(text) => println!("{}", text),
+//!     EngineOutput::Suspended(approvals) => { /* wait for decisions */ }
 //! }
 //! ```
 //!
 //! # Execution Model
 //!
-//! The engine traverses the pipeline DAG starting from `input` edges:
+//! The engine schedules the pipeline DAG by its dependencies, starting from
+//! `input` edges. A node runs once every edge into it has been settled (its
+//! source ran, or was skipped because a router, gate, or condition chose
+//! another path) and at least one of them was followed, so a node fed by
+//! several branches joins all of them. Nodes that become ready together run
+//! concurrently. An `optional` edge doesn't hold its target back; its source's
+//! output is included only if it finished first.
 //!
-//! 1. **Sequential** (Direct edges) — Nodes execute one after another
-//! 2. **Parallel** (Parallel edges) — Nodes execute concurrently via `tokio::join_all`
-//! 3. **Conditional** (Router nodes) — LLM classifies input to choose path
+//! 1. **Sequential** (Direct edges) — A node runs after the nodes feeding it
+//! 2. **Parallel** (Parallel edges) — Fans out to nodes that run concurrently
+//! 3. **Conditional** (Router nodes) — Rules on the router's edges, then the
+//!    LLM, choose one or more paths, falling back to a configured target when no
+//!    route is confident enough (or an evaluator's `on_pass` / `on_fail` target)
+//! 4. **Dynamic** (Orchestrator nodes) — LLM plans subtasks, dispatched in parallel
+//!    to the chosen workers
+//! 5. **Feedback** (Loop edges) — Re-runs an upstream subgraph with a critique
+//!
+//! A parallel or dynamic edge's `on_failure` policy decides whether a failing
+//! branch fails the run (`fail_fast`, the default) or the run continues with
+//! the branches that succeeded (`continue`, or `{"quorum": N}`).
+//!
+//! Any edge may set a `when` condition (a regex, keywords, length bounds, or a
+//! comparison on an upstream output field); it is followed only if the
+//! condition holds, checked in the engine without an LLM call.
+//!
+//! Aggregator and Synthesizer nodes receive every upstream output labelled by
+//! source node ID and merge them with a configurable strategy (labelled
+//! concatenation, JSON array/object merge, majority vote, or LLM synthesis).
+//!
+//! Gate nodes validate their input with deterministic rules and/or an LLM
+//! check. A rejected input either halts the run, returning the gate's
+//! rejection message as the pipeline output, or is diverted to the gate's
+//! `reject_target`.
+//!
+//! # Feedback Loops
+//!
+//! A feedback edge points from a node (typically an evaluator) back to an
+//! upstream node. After the source runs, its [`Verdict`] is checked (for
+//! non-evaluator sources, the output is parsed as one: a leading `PASS` or a
+//! JSON object with `"pass": true`). If it did not pass, every node on the
+//! forward path from the edge target to the source runs again, with the
+//! source's critique appended to the target's input. This repeats until the
+//! source passes or the edge's `max_iterations` is reached, after which
+//! traversal continues forward with the latest output. Each iteration is
+//! recorded as its own span.
+//!
+//! # Conversation History
+//!
+//! The `history` passed to [`PipelineEngine::execute_stream`] is included in
+//! the calls made by LLM, worker, and router nodes. Each node's
+//! `config.history` selects how much it sees: `"full"` (the default),
+//! `"none"`, the last N turns, or a `"summary"` generated once per model.
+//!
+//! # Prompt Templates
+//!
+//! Node prompts may reference `{{input}}`, `{{nodes.<id>}}`, `{{history}}`,
+//! and pipeline `variables` as `{{vars.<name>}}`. Templates are compiled
+//! before the run starts, so unknown references fail fast (see
+//! [`PipelineEngine::validate`]), and are rendered just before the node runs.
+//!
+//! # Structured Outputs
+//!
+//! An `llm` or `worker` node with an `output_schema` must answer with JSON
+//! matching that JSON Schema. The engine asks the provider for it, validates
+//! the response, and re-prompts with the validation errors when it doesn't
+//! match. The parsed value is stored as JSON, so templates can reference its
+//! fields as `{{nodes.<id>.<field>}}` and routers can route on a field.
+//!
+//! # Run Context
+//!
+//! Every node's latest output is kept in a [`RunContext`] as a JSON value,
+//! with its status, verdict, and usage (model, tokens, elapsed time). Custom
+//! executors read it through [`NodeContext::run`], and
+//! [`PipelineEngine::execute_with_context`] returns it with the run's output.
+//!
+//! # Retries and Fallbacks
+//!
+//! A node's `retry` policy retries failed attempts with exponential backoff,
+//! for the error kinds listed in `retry_on` (LLM and external API errors by
+//! default). When a model runs out of attempts, each of `fallback_models` is
+//! tried in turn with the same policy. Every failed attempt is recorded as a
+//! span carrying its error, and a `node_retry` event is emitted before the
+//! next one. Nodes without a policy get a single attempt.
+//!
+//! # Timeouts and Cancellation
+//!
+//! A node's `timeout_ms` bounds each of its attempts; the pipeline's
+//! `timeout_ms` bounds the whole run, including a streamed response.
+//! [`PipelineEngine::execute_stream_with_cancel`] also takes a
+//! [`CancellationToken`]; cancelling it drops in-flight work and fails the run
+//! with [`AgentError::Cancelled`].
+//!
+//! # Concurrency and Rate Limits
+//!
+//! The pipeline's `max_concurrency` caps how many nodes execute at once, so
+//! parallel and dynamic branches beyond it wait for a free slot. Limits per
+//! model and provider (concurrent attempts and tokens per minute) are enforced
+//! by a [`RateLimiter`] attached with [`PipelineEngine::with_rate_limiter`];
+//! share one limiter between engines to apply them across concurrent runs.
+//!
+//! # Checkpoints and Resume
+//!
+//! With a checkpoint store attached ([`PipelineEngine::with_checkpoints`]),
+//! the run's context and executed set are saved after every node.
+//! [`PipelineEngine::resume`] re-runs a failed run from its checkpoint: nodes
+//! that already completed with the same config and input return their saved
+//! output (emitting [`EngineEvent::NodeRestored`]) instead of executing again.
+//!
+//! # Human Approval
+//!
+//! An `approval` node suspends the run: its input is saved in the checkpoint
+//! and the run returns [`EngineOutput::Suspended`]. [`PipelineEngine::decide`]
+//! continues the same run once a human approves, edits, or rejects it.
+//!
+//! # Sub-pipelines
+//!
+//! A `pipeline` node runs another pipeline, looked up by ID in the registry
+//! attached with [`PipelineEngine::with_pipelines`], as a nested run whose
+//! spans are recorded under the node. Cycles between pipelines and deep
+//! nesting are rejected by [`PipelineEngine::validate`].
+//!
+//! # Map Nodes
+//!
+//! A `map` node splits its input into items, a JSON array or one per line,
+//! and runs an unconnected body node or a pipeline once per item, a few at a
+//! time. Its output is the results as a JSON array, in item order.
+//!
+//! # Recording and Replay
+//!
+//! With a call store attached ([`PipelineEngine::with_recording`]), every
+//! successful LLM request and tool execution is recorded with its response.
+//! A [`Replay`] of a recorded run ([`PipelineEngine::with_replay`]) serves
+//! those responses instead of calling providers and tools, except for nodes
+//! marked live, so a run can be repeated with one node's prompt edited.
+//!
+//! # LLM Providers
+//!
+//! Nodes send their LLM requests through the engine's [`LlmProvider`],
+//! [`UnifiedProvider`] unless another is attached with
+//! [`PipelineEngine::with_provider`]. A [`MockLlm`] answers from a script, so
+//! pipelines can be tested without network access.
+//!
+//! # Streaming Output
+//!
+//! When the node feeding `output` is a plain LLM call (no tools, no other
+//! downstream edges), its execution is deferred until every upstream node has
+//! completed. It is then run via [`LlmProvider::chat_stream`] and returned
+//! as [`EngineOutput::Stream`] so callers can forward tokens as they arrive.
+//!
+//! # Execution Events
+//!
+//! Attach a channel with [`PipelineEngine::with_events`] to receive an
+//! [`EngineEvent`] for every node start/finish, tool call, router decision,
+//! and error while execution is in progress:
+//!
+//! ```rust,ignore
+//! let (tx, mut rx) = fissio_engine::event_channel();
+//! let engine = engine.with_events(tx);
+//! tokio::spawn(async move {
+//!     while let Some(event) = rx.recv().await {
+//!         println!("{}: {:?}", event.name(), event);
+//!     }
+//! });
+//! ```
 //!
 //! # Agentic Tool Loops
 //!
@@ -46,44 +216,132 @@
 //! 3. Send results back to LLM
 //! 4. Repeat until LLM returns final content (max 10 iterations)
 
+mod aggregator;
+mod approval;
+mod branches;
+mod cancel;
+mod checkpoint;
+mod condition;
+mod context;
+mod evaluator;
+mod events;
+mod executor;
+mod gate;
+mod history;
+mod map;
+mod mock;
+mod orchestrator;
+mod provider;
+mod recording;
+mod router;
+mod schedule;
+mod schema;
+mod subpipeline;
+mod template;
+mod throttle;
+
+pub use approval::{Decision, PendingApproval};
+pub use cancel::CancellationToken;
+pub use checkpoint::Checkpoint;
+pub use context::{NodeRecord, NodeStatus, NodeUsage, RunContext};
+pub use evaluator::Verdict;
+pub use events::{event_channel, EngineEvent, EventReceiver, EventSender};
+pub use executor::{NodeContext, NodeExecutor, NodeRegistry};
+pub use mock::{MockLlm, MockLlmBuilder, MockRequest, MockRuleBuilder};
+pub use orchestrator::{Plan, Subtask};
+pub use provider::{LlmProvider, LlmRequest, UnifiedProvider};
+pub use recording::Replay;
+pub use throttle::RateLimiter;
+
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
-
-use fissio_config::{EdgeConfig, EdgeEndpoint, EdgeType, NodeConfig, NodeType, PipelineConfig};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use aggregator::{execute_aggregator, format_labelled, MergeStrategy};
+use approval::ApprovalConfig;
+use branches::check_failure_policies;
+use cancel::{within, Deadline, RunLimits};
+use checkpoint::{CheckpointSink, Journal};
+use condition::{compile_conditions, Patterns};
+use evaluator::{execute_evaluator, EvaluatorConfig};
+use executor::executor_name;
+use gate::{check_gate_nodes, execute_gate_check, GateConfig};
+use history::{resolve_history, HistoryPolicy, NodeHistory};
+use map::{check_map_nodes, MapBody, MapConfig};
+use orchestrator::{execute_orchestrator, WorkerInfo};
+use recording::{Calls, Recorder};
+use router::{check_router_nodes, execute_router, RouterConfig};
+use schedule::{is_target, NodeState, Schedule};
+use schema::{check_output_schemas, repair_input, route_field, route_on_field, OutputSchema, SCHEMA_ATTEMPTS};
+use subpipeline::{check_pipeline_nodes, collect_output, input_key, pipeline_ref, NestedCollector};
+use template::{compile_templates, Template, TemplateContext};
+use throttle::{check_concurrency, Permit};
+use fissio_config::{
+    ConfigError, EdgeConfig, EdgeType, NodeConfig, NodeType, PipelineConfig,
+    PresetRegistry, RetryPolicy,
+};
 use fissio_core::{AgentError, ModelConfig};
-use fissio_llm::{ChatResponse, LlmMetrics, LlmStream, ToolCall, ToolSchema, UnifiedLlmClient};
+use fissio_llm::{ChatResponse, LlmMetrics, LlmStream, StreamChunk, ToolCall, ToolSchema, UnifiedLlmClient};
 use fissio_tools::ToolRegistry;
 use async_recursion::async_recursion;
-use futures::future::join_all;
-use fissio_monitor::{MetricsCollector, NodeMetrics};
-use tokio::sync::RwLock;
+use futures::future::{join_all, try_join_all};
+use futures::stream::FuturesUnordered;
+use futures::{StreamExt, TryStreamExt};
+use fissio_monitor::{CallStore, CheckpointStore, MetricsCollector, NodeMetrics};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, RwLock, Semaphore};
 use tracing::{debug, info, warn};
 
-/// Input data passed to a node during execution.
+/// The run as a custom node sees it when it executes.
 ///
-/// Contains the user's message, conversation history, and accumulated
-/// context from previous nodes in the pipeline.
+/// Contains the user's message, conversation history, and the outputs of
+/// the nodes that have run so far.
 #[derive(Debug, Clone, Default)]
 pub struct NodeInput {
     /// The original user input that started pipeline execution.
     pub user_input: String,
     /// Conversation history for multi-turn interactions.
     pub history: Vec<fissio_core::Message>,
-    /// Key-value context accumulated from previous nodes.
-    pub context: HashMap<String, String>,
+    /// Outputs of previous nodes, with their status and usage.
+    pub context: RunContext,
 }
 
 /// Output produced by a node after execution.
 ///
 /// For most nodes, `next_nodes` is empty. Router nodes populate it
-/// with the target node IDs determined by classification.
-#[derive(Debug, Clone)]
+/// with the target node IDs determined by classification, and evaluators
+/// with their `on_pass` / `on_fail` target.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NodeOutput {
     /// The content produced by this node.
     pub content: String,
-    /// Target nodes for routing (only set by Router nodes).
+    /// Target nodes for routing (set by Router and Evaluator nodes).
     pub next_nodes: Vec<String>,
+    /// Verdict from an Evaluator node.
+    pub verdict: Option<Verdict>,
+    /// Set when a node stops the run: a Gate or Approval rejecting its input
+    /// (`content` is the rejection message), or an Approval awaiting a decision.
+    pub halted: bool,
+    /// Structured form of `content`, stored in the [`RunContext`] in its place.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<serde_json::Value>,
+    /// What the execution used; filled in by the engine.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<NodeUsage>,
+}
+
+impl NodeOutput {
+    /// Creates an output with the given content that follows every outgoing edge.
+    pub fn new(content: impl Into<String>) -> Self {
+        Self { content: content.into(), ..Self::default() }
+    }
+
+    /// Creates a structured output; its content is the value as compact JSON.
+    pub fn json(value: serde_json::Value) -> Self {
+        Self { content: value.to_string(), value: Some(value), ..Self::default() }
+    }
 }
 
 /// Accumulated metrics from node execution.
@@ -114,10 +372,101 @@ impl ExecutionMetrics {
 /// Depending on pipeline structure, execution may return a stream
 /// for real-time output or a complete response string.
 pub enum EngineOutput {
-    /// Streaming response for real-time output.
+    /// Streaming response from the final node; upstream nodes have already completed.
     Stream(LlmStream),
     /// Complete response after pipeline finishes.
     Complete(String),
+    /// The run is suspended until each approval is decided with
+    /// [`PipelineEngine::decide`].
+    Suspended(Vec<PendingApproval>),
+}
+
+/// Result of [`PipelineEngine::execute_with_context`].
+pub struct RunResult {
+    /// The pipeline's output: complete, or suspended at approval nodes.
+    pub output: EngineOutput,
+    /// Every node's latest output, with its status and usage.
+    pub context: RunContext,
+}
+
+/// A successful attempt from [`PipelineEngine::with_retries`].
+struct Attempt<T> {
+    value: T,
+    /// Model that produced the value.
+    model: Arc<ModelConfig>,
+    /// 1-based attempt number across the whole model chain.
+    number: u32,
+    start: Instant,
+    start_time_ms: i64,
+    /// The attempt's node timeout, which a streamed value stays bound by.
+    deadline: Option<Deadline>,
+    /// Rate limit slots held until the value has been consumed.
+    permit: Permit,
+}
+
+/// State of a single pipeline run shared by the nodes it executes; the
+/// traversal itself is tracked in a [`Schedule`].
+struct RunState<'a> {
+    /// The user input that started the run.
+    input: &'a str,
+    /// Node outputs keyed by node ID.
+    context: Arc<RwLock<RunContext>>,
+    /// Conversation history for multi-turn interactions.
+    history: &'a [fissio_core::Message],
+    /// Conversation summaries generated this run, keyed by model ID.
+    summaries: Mutex<HashMap<String, String>>,
+    /// Compiled prompt templates keyed by node ID.
+    templates: HashMap<String, Template>,
+    /// Compiled edge condition regexes.
+    patterns: Patterns,
+    /// Step counter shared with parallel branches for log output.
+    step: Arc<RwLock<usize>>,
+    /// Cancellation and pipeline deadline, applied to a streamed output.
+    limits: RunLimits,
+    /// Slots bounding how many nodes execute at once, from `max_concurrency`.
+    slots: Option<Semaphore>,
+    /// Completed executions, checkpointed and replayed when resuming.
+    journal: &'a Journal<'a>,
+}
+
+impl RunState<'_> {
+    /// Returns the view of this run shared with concurrently executing nodes.
+    fn env(&self) -> NodeEnv<'_> {
+        NodeEnv {
+            input: self.input,
+            context: &self.context,
+            step: &self.step,
+            history: self.history,
+            summaries: &self.summaries,
+            templates: &self.templates,
+            patterns: &self.patterns,
+            slots: self.slots.as_ref(),
+            journal: self.journal,
+        }
+    }
+}
+
+/// Read-only view of a run passed to node execution, shareable across parallel branches.
+#[derive(Clone, Copy)]
+struct NodeEnv<'a> {
+    /// The user input that started the run.
+    input: &'a str,
+    /// Node outputs keyed by node ID.
+    context: &'a RwLock<RunContext>,
+    /// Step counter for log output.
+    step: &'a RwLock<usize>,
+    /// Conversation history for multi-turn interactions.
+    history: &'a [fissio_core::Message],
+    /// Conversation summaries generated this run, keyed by model ID.
+    summaries: &'a Mutex<HashMap<String, String>>,
+    /// Compiled prompt templates keyed by node ID.
+    templates: &'a HashMap<String, Template>,
+    /// Compiled edge condition regexes.
+    patterns: &'a Patterns,
+    /// Slots bounding how many nodes execute at once.
+    slots: Option<&'a Semaphore>,
+    /// Completed executions, checkpointed and replayed when resuming.
+    journal: &'a Journal<'a>,
 }
 
 /// Resolves model IDs to their configurations.
@@ -144,6 +493,22 @@ impl ModelResolver {
             .cloned()
             .unwrap_or_else(|| Arc::clone(&self.default_model))
     }
+
+    /// Resolves a model ID followed by fallback model IDs, in order and without
+    /// duplicates. Unknown fallback IDs are skipped rather than resolved to the default.
+    pub fn resolve_chain(&self, model_id: Option<&str>, fallbacks: &[String]) -> Vec<Arc<ModelConfig>> {
+        let mut chain = vec![self.resolve(model_id)];
+        for id in fallbacks {
+            let model = self.models.get(id)
+                .or_else(|| (self.default_model.id == *id).then_some(&self.default_model));
+            match model {
+                Some(model) if !chain.iter().any(|m| m.id == model.id) => chain.push(Arc::clone(model)),
+                Some(_) => {}
+                None => warn!("Unknown fallback model '{}', skipping", id),
+            }
+        }
+        chain
+    }
 }
 
 /// Core pipeline execution engine.
@@ -159,10 +524,22 @@ impl ModelResolver {
 /// ```
 pub struct PipelineEngine {
     config: PipelineConfig,
-    resolver: ModelResolver,
+    resolver: Arc<ModelResolver>,
     node_overrides: HashMap<String, String>,
     tool_registry: Arc<ToolRegistry>,
+    node_registry: Arc<NodeRegistry>,
+    pipelines: Arc<PresetRegistry>,
+    /// IDs of the pipelines this one runs nested inside, outermost first.
+    ancestry: Vec<String>,
     collector: Option<Arc<dyn MetricsCollector>>,
+    events: Option<EventSender>,
+    /// Put before the node IDs in emitted events; set on nested runs.
+    event_prefix: String,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    checkpoints: Option<CheckpointSink>,
+    provider: Arc<dyn LlmProvider>,
+    recorder: Option<Recorder>,
+    replay: Option<Arc<Replay>>,
 }
 
 impl PipelineEngine {
@@ -175,10 +552,20 @@ impl PipelineEngine {
     ) -> Self {
         Self {
             config,
-            resolver: ModelResolver::new(models, default_model),
+            resolver: Arc::new(ModelResolver::new(models, default_model)),
             node_overrides,
             tool_registry: Arc::new(ToolRegistry::with_defaults()),
+            node_registry: Arc::new(NodeRegistry::new()),
+            pipelines: Arc::new(PresetRegistry::new()),
+            ancestry: Vec::new(),
             collector: None,
+            events: None,
+            event_prefix: String::new(),
+            rate_limiter: None,
+            checkpoints: None,
+            provider: Arc::new(UnifiedProvider),
+            recorder: None,
+            replay: None,
         }
     }
 
@@ -192,26 +579,111 @@ impl PipelineEngine {
     ) -> Self {
         Self {
             config,
-            resolver: ModelResolver::new(models, default_model),
+            resolver: Arc::new(ModelResolver::new(models, default_model)),
             node_overrides,
             tool_registry: Arc::new(tool_registry),
+            node_registry: Arc::new(NodeRegistry::new()),
+            pipelines: Arc::new(PresetRegistry::new()),
+            ancestry: Vec::new(),
             collector: None,
+            events: None,
+            event_prefix: String::new(),
+            rate_limiter: None,
+            checkpoints: None,
+            provider: Arc::new(UnifiedProvider),
+            recorder: None,
+            replay: None,
         }
     }
 
+    /// Attaches a registry of executors for `custom` nodes.
+    pub fn with_nodes(mut self, node_registry: NodeRegistry) -> Self {
+        self.node_registry = Arc::new(node_registry);
+        self
+    }
+
+    /// Attaches the pipelines that `pipeline` nodes can run, by ID.
+    pub fn with_pipelines(mut self, pipelines: Arc<PresetRegistry>) -> Self {
+        self.pipelines = pipelines;
+        self
+    }
+
     /// Attaches a metrics collector to this engine.
     pub fn with_collector(mut self, collector: Arc<dyn MetricsCollector>) -> Self {
         self.collector = Some(collector);
         self
     }
 
-    /// Gets the model to use for a node, considering overrides.
-    /// Returns Arc for cheap cloning in parallel execution.
-    fn get_node_model(&self, node: &NodeConfig) -> Arc<ModelConfig> {
+    /// Attaches an event sender that receives [`EngineEvent`]s as the pipeline runs.
+    pub fn with_events(mut self, events: EventSender) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Attaches a rate limiter; engines sharing one limiter share its limits.
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Saves a [`Checkpoint`] of the run to `store` under `run_id` after every
+    /// completed node, and lets [`resume`](Self::resume) load checkpoints from it.
+    pub fn with_checkpoints(mut self, store: Arc<dyn CheckpointStore>, run_id: impl Into<String>) -> Self {
+        self.checkpoints = Some(CheckpointSink { store, run_id: run_id.into() });
+        self
+    }
+
+    /// Sends nodes' LLM requests to `provider` instead of [`UnifiedProvider`].
+    pub fn with_provider(mut self, provider: Arc<dyn LlmProvider>) -> Self {
+        self.provider = provider;
+        self
+    }
+
+    /// Records every successful LLM request and tool execution of the run, with
+    /// its response, to `store` under `run_id`.
+    pub fn with_recording(mut self, store: Arc<dyn CallStore>, run_id: impl Into<String>) -> Self {
+        self.recorder = Some(Recorder { store, run_id: run_id.into() });
+        self
+    }
+
+    /// Serves LLM requests and tool executions from `replay` instead of
+    /// calling providers and tools.
+    pub fn with_replay(mut self, replay: Replay) -> Self {
+        self.replay = Some(Arc::new(replay));
+        self
+    }
+
+    /// Returns the calls node `node_id` makes to `model` through the provider,
+    /// recorded and replayed as configured.
+    fn calls<'a>(&'a self, node_id: &'a str, model: &'a ModelConfig) -> Calls<'a> {
+        Calls::new(node_id, model, self.provider.as_ref(), self.recorder.as_ref(), self.replay.as_deref())
+    }
+
+    /// Sends an event to the attached subscriber, if any.
+    fn emit(&self, event: EngineEvent) {
+        if let Some(ref events) = self.events {
+            let event = match self.event_prefix.as_str() {
+                "" => event,
+                prefix => event.prefixed(prefix),
+            };
+            // A closed channel only means nobody is listening anymore
+            let _ = events.send(event);
+        }
+    }
+
+    /// Returns the collector to record a node's metrics with, if observability is enabled for it.
+    fn node_collector(&self, node: &NodeConfig) -> Option<Arc<dyn MetricsCollector>> {
+        let enabled = node.observe.as_ref().is_some_and(|o| o.enabled);
+        self.collector.clone().filter(|_| enabled)
+    }
+
+    /// Gets the models to try for a node, considering overrides: its model
+    /// first, then its fallbacks. Returns Arcs for cheap cloning in parallel execution.
+    fn get_node_models(&self, node: &NodeConfig) -> Vec<Arc<ModelConfig>> {
         let model_id = self.node_overrides
             .get(&node.id)
             .or(node.model.as_ref());
-        self.resolver.resolve(model_id.map(|s| s.as_str()))
+        self.resolver.resolve_chain(model_id.map(|s| s.as_str()), &node.fallback_models)
     }
 
     /// Finds a node by ID.
@@ -219,13 +691,45 @@ impl PipelineEngine {
         self.config.nodes.iter().find(|n| n.id == id)
     }
 
-    /// Gets all edges originating from a node.
+    /// Gets all forward (non-feedback) edges originating from a node.
     fn get_outgoing_edges(&self, node_id: &str) -> Vec<&EdgeConfig> {
         self.config.edges.iter().filter(|e| {
-            e.from.as_vec().contains(&node_id)
+            !e.is_feedback() && e.from.as_vec().contains(&node_id)
         }).collect()
     }
 
+    /// Returns nodes on a forward path from `start` to `end` (inclusive),
+    /// i.e. the body of a feedback loop from `end` back to `start`.
+    ///
+    /// Returns an empty set if `end` is not reachable from `start`.
+    fn get_loop_body(&self, start: &str, end: &str) -> HashSet<String> {
+        let reachable = |from: &str, forward: bool| {
+            let mut seen = HashSet::from([from.to_string()]);
+            let mut stack = vec![from.to_string()];
+            while let Some(id) = stack.pop() {
+                for edge in self.config.edges.iter().filter(|e| !e.is_feedback()) {
+                    let (src, dst) = if forward { (&edge.from, &edge.to) } else { (&edge.to, &edge.from) };
+                    if !src.as_vec().contains(&id.as_str()) {
+                        continue;
+                    }
+                    for next in dst.as_vec() {
+                        if seen.insert(next.to_string()) {
+                            stack.push(next.to_string());
+                        }
+                    }
+                }
+            }
+            seen
+        };
+
+        let downstream = reachable(start, true);
+        if !downstream.contains(end) {
+            return HashSet::new();
+        }
+        let upstream = reachable(end, false);
+        downstream.intersection(&upstream).cloned().collect()
+    }
+
     /// Gets all target node IDs from outgoing edges (for router decisions).
     fn get_outgoing_targets(&self, node_id: &str) -> Vec<String> {
         self.get_outgoing_edges(node_id)
@@ -235,520 +739,2772 @@ impl PipelineEngine {
             .collect()
     }
 
-    /// Executes the pipeline and returns the result.
-    pub async fn execute_stream(
-        &self,
-        user_input: &str,
-        history: &[fissio_core::Message],
-    ) -> Result<EngineOutput, AgentError> {
-        info!("╔══════════════════════════════════════════════════════════════");
-        info!("║ PIPELINE: {}", self.config.name);
-        info!("║ Input: {}...", user_input.chars().take(50).collect::<String>());
-        info!("╠══════════════════════════════════════════════════════════════");
+    /// Returns `true` if a node only feeds `output`, unconditionally and
+    /// without a feedback loop, and is a plain LLM call, so its execution can
+    /// be deferred and streamed.
+    fn is_streamable(&self, node: &NodeConfig) -> bool {
+        let outgoing = self.get_outgoing_edges(&node.id);
+        matches!(node.node_type, NodeType::Llm | NodeType::Worker)
+            && node.tools.is_empty()
+            && node.output_schema.is_none()
+            && !outgoing.is_empty()
+            && outgoing.iter().all(|e| e.to.as_vec() == ["output"] && e.when.is_none())
+            && !self.config.edges.iter().any(|e| e.is_feedback() && e.from.as_vec().contains(&node.id.as_str()))
+    }
 
-        if !self.node_overrides.is_empty() {
-            info!("║ Node model overrides: {:?}", self.node_overrides);
+    /// Returns `true` if `edge` has no condition or its condition holds for
+    /// the text it carries: its sources' outputs, or the user input for
+    /// edges from `input`. Conditions on a router's edges are rules the
+    /// router applies itself, so they always hold here.
+    fn edge_holds(&self, edge: &EdgeConfig, run: &RunState<'_>, ctx: &RunContext) -> bool {
+        let Some(condition) = &edge.when else {
+            return true;
+        };
+        let sources = edge.from.as_vec();
+        if sources.iter().any(|id| self.get_node(id).is_some_and(|n| n.node_type.is_router())) {
+            return true;
         }
+        let text = if sources == ["input"] {
+            Cow::Borrowed(run.input)
+        } else {
+            Cow::Owned(sources.iter().filter_map(|id| ctx.text(id)).collect::<Vec<_>>().join("\n\n---\n\n"))
+        };
+        condition::holds(condition, &run.patterns, &text, ctx)
+    }
 
-        let context = Arc::new(RwLock::new(HashMap::<String, String>::new()));
-        context.write().await.insert("input".to_string(), user_input.to_string());
-
-        let mut executed: HashSet<String> = HashSet::new();
-        let step = Arc::new(RwLock::new(0usize));
+    /// Checks that every `custom` node names a registered executor, that
+    /// `approval` nodes have a checkpoint store to suspend to, that `pipeline`
+    /// nodes name known pipelines without cycles or excessive nesting, that `map`
+    /// nodes have valid settings and an unconnected body node, that routers
+    /// have valid settings and fall back to one of their targets, that edge
+    /// conditions are well-formed and refer to known output fields, that output
+    /// schemas compile and are set on `llm` or `worker` nodes, that prompt
+    /// templates have no syntax errors or unknown node or variable references,
+    /// and that edge failure policies are valid.
+    ///
+    /// [`execute_stream`](Self::execute_stream) runs the same checks before any
+    /// node executes.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.compile().map(|_| ())
+    }
 
-        // Find starting edges (from "input")
-        let start_edges: Vec<&EdgeConfig> = self.config.edges.iter()
-            .filter(|e| matches!(&e.from, EdgeEndpoint::Single(s) if s == "input"))
-            .collect();
+    /// Runs the checks of [`validate`](Self::validate), returning the
+    /// compiled prompt templates and edge condition regexes a run uses.
+    fn compile(&self) -> Result<(HashMap<String, Template>, Patterns), ConfigError> {
+        self.check_custom_nodes()?;
+        self.check_approvals()?;
+        check_pipeline_nodes(&self.config, &self.ancestry, &self.pipelines)?;
+        check_map_nodes(&self.config)?;
+        check_router_nodes(&self.config)?;
+        let mut patterns = compile_conditions(&self.config)?;
+        check_gate_nodes(&self.config, &mut patterns)?;
+        check_output_schemas(&self.config)?;
+        check_failure_policies(&self.config)?;
+        check_concurrency(&self.config)?;
+        let templates = compile_templates(&self.config)?;
+        Ok((templates, patterns))
+    }
 
-        for start_edge in start_edges {
-            self.process_edge(start_edge, &context, &mut executed, history, &step).await?;
+    fn check_custom_nodes(&self) -> Result<(), ConfigError> {
+        for node in self.config.nodes.iter().filter(|n| n.node_type == NodeType::Custom) {
+            match executor_name(node) {
+                Some(name) if self.node_registry.has(name) => {}
+                Some(name) => {
+                    return Err(ConfigError::validation(
+                        &self.config.id,
+                        format!("node '{}' uses unregistered executor '{}'", node.id, name),
+                    ));
+                }
+                None => {
+                    return Err(ConfigError::validation(
+                        &self.config.id,
+                        format!("custom node '{}' has no config.executor", node.id),
+                    ));
+                }
+            }
         }
+        Ok(())
+    }
 
-        // Find output
-        let ctx = context.read().await;
-        for edge in &self.config.edges {
-            if !matches!(&edge.to, EdgeEndpoint::Single(s) if s == "output") {
-                continue;
+    fn check_approvals(&self) -> Result<(), ConfigError> {
+        for node in self.config.nodes.iter().filter(|n| n.node_type == NodeType::Approval) {
+            if self.checkpoints.is_none() {
+                return Err(ConfigError::validation(
+                    &self.config.id,
+                    format!("approval node '{}' needs a checkpoint store to suspend the run", node.id),
+                ));
+            }
+            if let Some(target) = ApprovalConfig::from_value(&node.config).reject_target {
+                if !is_target(&self.config, &node.id, &target) {
+                    return Err(ConfigError::validation(
+                        &self.config.id,
+                        format!("approval node '{}' rejects to '{}', which isn't one of its targets", node.id, target),
+                    ));
+                }
             }
-
-            let from_nodes = edge.from.as_vec();
-            let output = from_nodes.iter()
-                .rev()
-                .find_map(|id| ctx.get(*id))
-                .cloned()
-                .unwrap_or_default();
-
-            info!("║ Pipeline complete");
-            info!("╚══════════════════════════════════════════════════════════════");
-            return Ok(EngineOutput::Complete(output));
         }
-
-        info!("║ Pipeline complete (no output edge found)");
-        info!("╚══════════════════════════════════════════════════════════════");
-        Ok(EngineOutput::Complete(String::new()))
+        Ok(())
     }
 
-    /// Processes an edge, executing target nodes based on edge type.
-    #[async_recursion]
-    async fn process_edge(
+    /// Executes the pipeline and returns the result.
+    ///
+    /// If the pipeline ends in a single streamable node, upstream nodes run to
+    /// completion first and the final node is returned as [`EngineOutput::Stream`].
+    pub async fn execute_stream(
         &self,
-        edge: &EdgeConfig,
-        context: &Arc<RwLock<HashMap<String, String>>>,
-        executed: &mut HashSet<String>,
+        user_input: &str,
         history: &[fissio_core::Message],
-        step: &Arc<RwLock<usize>>,
-    ) -> Result<(), AgentError> {
-        let target_ids = edge.to.as_vec();
-
-        if target_ids.len() == 1 && target_ids[0] == "output" {
-            return Ok(());
-        }
-
-        if edge.edge_type == EdgeType::Parallel {
-            return self.execute_parallel(target_ids, context, executed, history, step).await;
-        }
+    ) -> Result<EngineOutput, AgentError> {
+        self.execute_stream_with_cancel(user_input, history, CancellationToken::new()).await
+    }
 
-        self.execute_sequential(target_ids, context, executed, history, step).await
+    /// Executes the pipeline like [`execute_stream`](Self::execute_stream),
+    /// stopping early once `cancel` is cancelled.
+    ///
+    /// Cancellation and the pipeline's `timeout_ms` abort in-flight LLM
+    /// requests, tool calls, and parallel branches, failing the run with
+    /// [`AgentError::Cancelled`] or [`AgentError::Timeout`]. A returned stream
+    /// remains bound by both and ends with the error if either fires.
+    pub async fn execute_stream_with_cancel(
+        &self,
+        user_input: &str,
+        history: &[fissio_core::Message],
+        cancel: CancellationToken,
+    ) -> Result<EngineOutput, AgentError> {
+        self.execute(user_input, history, cancel, None, None, true).await.map(|(output, _)| output)
     }
 
-    /// Executes multiple nodes concurrently using `tokio::join_all`.
+    /// Executes the pipeline like [`execute_stream_with_cancel`](Self::execute_stream_with_cancel),
+    /// returning the run context along with the output.
     ///
-    /// Each node runs independently with its own model and input context.
-    /// Results are collected and stored in the shared context map.
-    /// Router node decisions are tracked to filter subsequent edge processing.
-    async fn execute_parallel(
+    /// The context holds every node's latest output as JSON, with its status,
+    /// verdict, and usage. The final node is never streamed, so the output is
+    /// [`EngineOutput::Complete`] or [`EngineOutput::Suspended`].
+    pub async fn execute_with_context(
         &self,
-        target_ids: Vec<&str>,
-        context: &Arc<RwLock<HashMap<String, String>>>,
-        executed: &mut HashSet<String>,
+        user_input: &str,
         history: &[fissio_core::Message],
-        step: &Arc<RwLock<usize>>,
-    ) -> Result<(), AgentError> {
-        info!("╠══════════════════════════════════════════════════════════════");
-        info!("║ PARALLEL EXECUTION: {:?}", target_ids);
-
-        // Gather node data
-        let mut node_data = Vec::new();
-        for id in target_ids.iter().filter(|&id| !executed.contains(*id)) {
-            let Some(node) = self.get_node(id) else { continue };
-            let input = self.get_input_for_node(id, context).await;
-            let model = self.get_node_model(node).clone();
-            let outgoing_targets = self.get_outgoing_targets(id);
-            node_data.push((node.id.clone(), node.node_type, model, node.prompt.clone(), node.tools.clone(), input, outgoing_targets, node.observe.clone()));
-        }
-
-        // Execute in parallel
-        let tool_registry = Arc::clone(&self.tool_registry);
-        let collector = self.collector.clone();
-        let futures: Vec<_> = node_data.into_iter()
-            .map(|(node_id, node_type, model, prompt, tools, input, outgoing_targets, observe)| {
-                let step = Arc::clone(step);
-                let registry = Arc::clone(&tool_registry);
-                let collector = collector.clone();
-                async move {
-                    let current_step = {
-                        let mut s = step.write().await;
-                        *s += 1;
-                        *s
-                    };
-                    let start = std::time::Instant::now();
-                    let start_time_ms = now_ms();
-                    let result = execute_node(&node_id, node_type, &model, prompt.as_deref(), &input, &tools, &registry, current_step, &outgoing_targets).await;
-                    let elapsed_ms = start.elapsed().as_millis() as u64;
-                    let end_time_ms = now_ms();
-
-                    // Record metrics and span if observe is enabled and execution succeeded
-                    let should_record = result.is_ok()
-                        && observe.as_ref().is_some_and(|o| o.enabled)
-                        && collector.is_some();
-                    if should_record {
-                        let (ref output, ref exec_metrics) = result.as_ref().unwrap();
-                        let node_metrics = NodeMetrics {
-                            node_id: node_id.clone(),
-                            input_tokens: exec_metrics.input_tokens,
-                            output_tokens: exec_metrics.output_tokens,
-                            elapsed_ms,
-                            tool_call_count: exec_metrics.tool_call_count,
-                            iteration_count: exec_metrics.iteration_count,
-                            estimated_cost_usd: None,
-                        };
-                        let coll = collector.as_ref().unwrap();
-                        coll.record(node_metrics.clone());
-                        coll.record_span(
-                            &node_id,
-                            &node_type.to_string(),
-                            start_time_ms,
-                            end_time_ms,
-                            &input,
-                            &output.content,
-                            &node_metrics,
-                        );
-                    }
+        cancel: CancellationToken,
+    ) -> Result<RunResult, AgentError> {
+        let (output, context) = self.execute(user_input, history, cancel, None, None, false).await?;
+        Ok(RunResult { output, context })
+    }
 
-                    // Map result to extract just the NodeOutput for compatibility
-                    (node_id, result.map(|(output, _)| output))
-                }
-            })
-            .collect();
+    /// Resumes run `run_id` from its checkpoint as a new run.
+    ///
+    /// The checkpoint's input and history are run through this engine's
+    /// pipeline, which may be an edited version of the original. Nodes whose
+    /// config and input match an execution in the checkpoint reuse its output
+    /// instead of running again; the rest, starting with the node that failed,
+    /// run as usual. Requires [`with_checkpoints`](Self::with_checkpoints),
+    /// which also names the new run.
+    pub async fn resume(&self, run_id: &str, cancel: CancellationToken) -> Result<EngineOutput, AgentError> {
+        let sink = self.checkpoints.as_ref()
+            .ok_or_else(|| AgentError::Checkpoint("no checkpoint store attached".to_string()))?;
+        let mut checkpoint = Checkpoint::load(sink.store.as_ref(), run_id)?
+            .ok_or_else(|| AgentError::Checkpoint(format!("no checkpoint for run '{}'", run_id)))?;
+        let (input, history) = (checkpoint.input.clone(), checkpoint.history.clone());
+        checkpoint.resumed_from = Some(run_id.to_string());
+
+        info!("Resuming run {} as {} ({} nodes completed)", run_id, sink.run_id, checkpoint.executed.len());
+        self.execute(&input, &history, cancel, Some(checkpoint), None, true).await.map(|(output, _)| output)
+    }
 
-        let results = join_all(futures).await;
+    /// Continues the run suspended at approval node `node_id` with a human's decision.
+    ///
+    /// The run is the one checkpointed under this engine's run ID (see
+    /// [`with_checkpoints`](Self::with_checkpoints)) and continues as the same
+    /// run: completed nodes are replayed as in [`resume`](Self::resume), then
+    /// the approval node applies `decision` and traversal carries on. Other
+    /// approvals the run was suspended at are requested again.
+    pub async fn decide(&self, node_id: &str, decision: Decision, cancel: CancellationToken) -> Result<EngineOutput, AgentError> {
+        let sink = self.checkpoints.as_ref()
+            .ok_or_else(|| AgentError::Checkpoint("no checkpoint store attached".to_string()))?;
+        let checkpoint = Checkpoint::load(sink.store.as_ref(), &sink.run_id)?
+            .ok_or_else(|| AgentError::Checkpoint(format!("no checkpoint for run '{}'", sink.run_id)))?;
+        let approval = checkpoint.pending.iter()
+            .find(|a| a.node_id == node_id)
+            .cloned()
+            .ok_or_else(|| {
+                AgentError::Checkpoint(format!("run '{}' is not awaiting approval at '{}'", sink.run_id, node_id))
+            })?;
+        let (input, history) = (checkpoint.input.clone(), checkpoint.history.clone());
+
+        info!("Continuing run {} with decision on [{}]: {:?}", sink.run_id, node_id, decision);
+        self.execute(&input, &history, cancel, Some(checkpoint), Some((approval, decision)), true)
+            .await
+            .map(|(output, _)| output)
+    }
 
-        // Store results and track router decisions
-        let mut router_decisions: HashMap<String, Vec<String>> = HashMap::new();
-        for (node_id, result) in results {
-            let output = result?;
-            context.write().await.insert(node_id.clone(), output.content);
-            if !output.next_nodes.is_empty() {
-                router_decisions.insert(node_id.clone(), output.next_nodes);
+    async fn execute(
+        &self,
+        user_input: &str,
+        history: &[fissio_core::Message],
+        cancel: CancellationToken,
+        resumed: Option<Checkpoint>,
+        decision: Option<(PendingApproval, Decision)>,
+        stream: bool,
+    ) -> Result<(EngineOutput, RunContext), AgentError> {
+        let deadline = self.config.timeout_ms.map(|ms| Deadline::after(ms, &format!("pipeline '{}'", self.config.id)));
+        let limits = RunLimits::new(cancel, deadline);
+        let journal = Journal::new(self.checkpoints.as_ref(), &self.config, user_input, history, resumed, decision);
+        let outcome = limits.run(self.run_pipeline(user_input, history, limits.clone(), &journal, stream)).await;
+        journal.flush().await;
+        match outcome {
+            Ok(result) => result,
+            Err(e) => {
+                warn!("║ Pipeline stopped: {}", e);
+                info!("╚══════════════════════════════════════════════════════════════");
+                self.emit(EngineEvent::Error { node_id: None, message: e.to_string() });
+                Err(e)
             }
-            executed.insert(node_id);
         }
+    }
 
-        info!("║ PARALLEL EXECUTION COMPLETE");
+    /// Runs the pipeline, streaming its final node if `stream` is set and the
+    /// pipeline allows it, and returns the output with the run context.
+    async fn run_pipeline(
+        &self,
+        user_input: &str,
+        history: &[fissio_core::Message],
+        limits: RunLimits,
+        journal: &Journal<'_>,
+        stream: bool,
+    ) -> Result<(EngineOutput, RunContext), AgentError> {
+        info!("╔══════════════════════════════════════════════════════════════");
+        info!("║ PIPELINE: {}", self.config.name);
+        info!("║ Input: {}...", user_input.chars().take(50).collect::<String>());
         info!("╠══════════════════════════════════════════════════════════════");
 
-        // Process outgoing edges
-        for node_id in target_ids {
-            let router_targets = router_decisions.get(node_id).map(|v| v.as_slice()).unwrap_or(&[]);
-            self.process_outgoing_edges(node_id, router_targets, context, executed, history, step).await?;
+        if !self.node_overrides.is_empty() {
+            info!("║ Node model overrides: {:?}", self.node_overrides);
         }
 
-        Ok(())
-    }
+        let (templates, patterns) = self.compile().map_err(|e| AgentError::ParseError(e.to_string()))?;
+
+        let run = RunState {
+            input: user_input,
+            context: Arc::new(RwLock::new(RunContext::default())),
+            history,
+            summaries: Mutex::new(HashMap::new()),
+            templates,
+            patterns,
+            step: Arc::new(RwLock::new(0usize)),
+            limits,
+            slots: self.config.max_concurrency.map(Semaphore::new),
+            journal,
+        };
+
+        let mut schedule = Schedule::new(&self.config);
+        self.run_graph(&run, &mut schedule).await?;
+
+        let suspended = run.journal.suspended();
+        if !suspended.is_empty() {
+            let nodes: Vec<&str> = suspended.iter().map(|a| a.node_id.as_str()).collect();
+            info!("║ Pipeline suspended awaiting approval at {:?}", nodes);
+            info!("╚══════════════════════════════════════════════════════════════");
+            return Ok((EngineOutput::Suspended(suspended), run.context.read().await.clone()));
+        }
 
-    /// Executes nodes one at a time in order.
-    ///
-    /// Each node receives input from previously executed nodes via the context map.
-    /// Errors in any node abort execution and propagate up.
-    /// Router decisions are applied to filter which outgoing edges to follow.
-    async fn execute_sequential(
-        &self,
-        target_ids: Vec<&str>,
-        context: &Arc<RwLock<HashMap<String, String>>>,
-        executed: &mut HashSet<String>,
-        history: &[fissio_core::Message],
-        step: &Arc<RwLock<usize>>,
-    ) -> Result<(), AgentError> {
-        for node_id in target_ids {
-            if executed.contains(node_id) || node_id == "output" {
-                continue;
+        if let Some(node_id) = &schedule.halted {
+            let context = run.context.read().await.clone();
+            let message = context.text(node_id).unwrap_or_default().into_owned();
+            info!("║ Pipeline halted by [{}]", node_id);
+            info!("╚══════════════════════════════════════════════════════════════");
+            return Ok((EngineOutput::Complete(message), context));
+        }
+
+        let has_completed_output = {
+            let ctx = run.context.read().await;
+            schedule.sources("output").iter().any(|id| ctx.output(id).is_some())
+        };
+
+        if let [node_id] = schedule.deferred.as_slice() {
+            if stream && !has_completed_output {
+                let stream = self.stream_node(node_id, &run, &schedule).await?;
+                info!("║ Pipeline upstream complete, streaming from [{}]", node_id);
+                info!("╚══════════════════════════════════════════════════════════════");
+                return Ok((EngineOutput::Stream(stream), run.context.read().await.clone()));
             }
+        }
 
-            let Some(node) = self.get_node(node_id) else { continue };
-            let input = self.get_input_for_node(node_id, context).await;
-            let outgoing_targets = self.get_outgoing_targets(node_id);
+        // Streaming isn't possible (several candidates reached, another output
+        // already produced content, or the caller wants the context), so run
+        // deferred nodes to completion
+        for node_id in std::mem::take(&mut schedule.deferred) {
+            let Some(node) = self.get_node(&node_id) else { continue };
+            let input = self.get_input_for_node(&node_id, &run, &schedule).await;
+            let results = self.run_tasks(node, &[input], true, run.env()).await;
+            self.complete(node, results, &run, &mut schedule).await?;
+        }
 
-            let current_step = {
-                let mut s = step.write().await;
-                *s += 1;
-                *s
-            };
+        // The output is that of the last source, in edge order, whose edge to it was followed
+        let context = run.context.read().await.clone();
+        let output = schedule.sources("output").iter()
+            .rev()
+            .find_map(|id| context.text(id))
+            .map(Cow::into_owned);
+
+        match output {
+            Some(output) => {
+                info!("║ Pipeline complete");
+                info!("╚══════════════════════════════════════════════════════════════");
+                Ok((EngineOutput::Complete(output), context))
+            }
+            None => {
+                info!("║ Pipeline complete (no output produced)");
+                info!("╚══════════════════════════════════════════════════════════════");
+                Ok((EngineOutput::Complete(String::new()), context))
+            }
+        }
+    }
 
-            let model = self.get_node_model(node);
-            let start = std::time::Instant::now();
-            let start_time_ms = now_ms();
-            let (output, exec_metrics) = execute_node(node_id, node.node_type, &model, node.prompt.as_deref(), &input, &node.tools, &self.tool_registry, current_step, &outgoing_targets).await?;
-            let elapsed_ms = start.elapsed().as_millis() as u64;
-            let end_time_ms = now_ms();
-
-            // Record metrics and span if observe is enabled
-            let should_record = node.observe.as_ref().is_some_and(|o| o.enabled) && self.collector.is_some();
-            if should_record {
-                let node_metrics = NodeMetrics {
-                    node_id: node_id.to_string(),
-                    input_tokens: exec_metrics.input_tokens,
-                    output_tokens: exec_metrics.output_tokens,
-                    elapsed_ms,
-                    tool_call_count: exec_metrics.tool_call_count,
-                    iteration_count: exec_metrics.iteration_count,
-                    estimated_cost_usd: None,
-                };
-                let collector = self.collector.as_ref().unwrap();
+    /// Starts streaming a deferred output node, recording its metrics once the stream ends.
+    async fn stream_node(&self, node_id: &str, run: &RunState<'_>, schedule: &Schedule) -> Result<LlmStream, AgentError> {
+        let node = self.get_node(node_id)
+            .ok_or_else(|| AgentError::LlmError(format!("Node not found: {}", node_id)))?;
+        let env = run.env();
+        let node = self.render_prompt(node, env).await;
+        let node = node.as_ref();
+        let input = self.get_input_for_node(node_id, run, schedule).await;
+        let models = self.get_node_models(node);
+
+        let current_step = next_step(&run.step).await;
+        self.emit(EngineEvent::NodeStarted {
+            node_id: node.id.clone(),
+            node_type: node.node_type,
+            step: current_step,
+        });
+
+        info!("╠──────────────────────────────────────────────────────────────");
+        info!("║ [{}] NODE: {} ({:?})", current_step, node_id, node.node_type);
+        info!("║     Model: {}", models[0].name);
+        info!("║     → Streaming LLM response");
+
+        // Only starting the stream is retried; errors mid-stream reach the caller
+        let input_ref = input.as_str();
+        let started = self.with_retries(node, input_ref, move |model| async move {
+            let history = self.node_history(node, &model, env).await?;
+            let system_prompt = history.system_prompt(node.prompt.as_deref().unwrap_or(""));
+            let stream = self.calls(&node.id, &model).chat_stream(&system_prompt, history.messages, input_ref).await?;
+            Ok((stream, history.metrics))
+        }).await;
+        let attempt = match started {
+            Ok(attempt) => attempt,
+            Err(e) => {
+                self.emit(EngineEvent::Error { node_id: Some(node.id.clone()), message: e.to_string() });
+                return Err(e);
+            }
+        };
+        let (stream, history_metrics) = attempt.value;
+        let stream = run.limits.with_deadline(attempt.deadline).bound_stream(stream);
+        let (start, start_time_ms) = (attempt.start, attempt.start_time_ms);
+        let (attempt_number, model_id) = (attempt.number, attempt.model.id.clone());
+        let permit = attempt.permit;
+
+        let collector = self.node_collector(node);
+        let events = self.events.clone();
+        let node_id = node_id.to_string();
+        let node_type = node.node_type.to_string();
+        Ok(observe_stream(stream, move |tally| {
+            let node_metrics = NodeMetrics {
+                node_id: node_id.clone(),
+                input_tokens: tally.input_tokens + history_metrics.input_tokens,
+                output_tokens: tally.output_tokens + history_metrics.output_tokens,
+                elapsed_ms: start.elapsed().as_millis() as u64,
+                tool_call_count: 0,
+                iteration_count: 1,
+                estimated_cost_usd: None,
+                score: None,
+                attempt: attempt_number,
+                model: Some(model_id.clone()),
+                error: tally.error,
+            };
+            permit.record((node_metrics.input_tokens + node_metrics.output_tokens) as u64);
+            drop(permit);
+            if let Some(collector) = collector {
                 collector.record(node_metrics.clone());
                 collector.record_span(
-                    node_id,
-                    &node.node_type.to_string(),
+                    &node_id,
+                    &node_type,
                     start_time_ms,
-                    end_time_ms,
+                    now_ms(),
                     &input,
-                    &output.content,
+                    &tally.content,
                     &node_metrics,
                 );
             }
+            if let Some(events) = events {
+                let _ = events.send(EngineEvent::NodeFinished { node_id, metrics: node_metrics });
+            }
+        }))
+    }
 
-            context.write().await.insert(node_id.to_string(), output.content.clone());
-            executed.insert(node_id.to_string());
+    /// Runs every node once its required inputs are ready (see [`Schedule`]),
+    /// running nodes that become ready together concurrently, until no node
+    /// can run.
+    ///
+    /// Streamable output nodes are deferred rather than executed. Once a node
+    /// halts the run, nodes already running finish but no new ones start.
+    async fn run_graph(&self, run: &RunState<'_>, schedule: &mut Schedule) -> Result<(), AgentError> {
+        self.follow_edges("input", &[], run, schedule).await;
+
+        let mut running = FuturesUnordered::new();
+        loop {
+            if schedule.halted.is_none() {
+                let mut started = Vec::new();
+                for node_id in schedule.ready() {
+                    let Some(node) = self.get_node(&node_id) else { continue };
+                    if self.is_streamable(node) {
+                        debug!("║ Deferring output node [{}] for streaming", node_id);
+                        schedule.set_state(&node_id, NodeState::Deferred);
+                        schedule.deferred.push(node_id);
+                        continue;
+                    }
 
-            // Process outgoing edges - filter by router decision if applicable
-            self.process_outgoing_edges(node_id, &output.next_nodes, context, executed, history, step).await?;
-        }
+                    // A dynamic worker runs once per subtask it was given
+                    let inputs = match schedule.subtasks.remove(&node_id) {
+                        Some(subtasks) => subtasks,
+                        None => vec![self.get_input_for_node(&node_id, run, schedule).await],
+                    };
+                    let fail_fast = !schedule.branches.tolerates(&node_id);
+                    let env = run.env();
+                    running.push(async move { (node, self.run_tasks(node, &inputs, fail_fast, env).await) });
+                    started.push(node_id);
+                }
+                if started.len() > 1 {
+                    info!("╠══════════════════════════════════════════════════════════════");
+                    info!("║ CONCURRENT EXECUTION: {:?}", started);
+                }
+            }
 
+            let Some((node, results)) = running.next().await else { break };
+            self.complete(node, results, run, schedule).await?;
+        }
         Ok(())
     }
 
-    /// Gets the input text for a node from its incoming edges.
-    async fn get_input_for_node(&self, node_id: &str, context: &Arc<RwLock<HashMap<String, String>>>) -> String {
-        let ctx = context.read().await;
+    /// Runs a node on each of its inputs concurrently (one per subtask for a
+    /// dynamic worker, otherwise one) and returns each result in input order.
+    ///
+    /// With `fail_fast`, the first error is returned alone and the other runs
+    /// are cancelled.
+    async fn run_tasks(
+        &self,
+        node: &NodeConfig,
+        inputs: &[String],
+        fail_fast: bool,
+        env: NodeEnv<'_>,
+    ) -> Vec<Result<NodeOutput, AgentError>> {
+        let runs = inputs.iter().map(|input| self.run_node(node, input, env));
+        if fail_fast {
+            return match try_join_all(runs).await {
+                Ok(outputs) => outputs.into_iter().map(Ok).collect(),
+                Err(e) => vec![Err(e)],
+            };
+        }
+        join_all(runs).await
+    }
 
-        for edge in &self.config.edges {
-            if !edge.to.as_vec().contains(&node_id) {
-                continue;
+    /// Stores what a node's runs produced and settles its edges.
+    ///
+    /// An error fails the run unless the node is a branch of a parallel or
+    /// dynamic edge whose failure policy tolerates it; a node none of whose
+    /// runs succeeded is then marked failed and its edges aren't followed.
+    /// A dynamic worker's outputs are joined in plan order; if any subtask
+    /// produced a structured value, the values are stored as an array. A
+    /// node that didn't pass its feedback edge re-enters the loop instead of
+    /// following its forward edges.
+    async fn complete(
+        &self,
+        node: &NodeConfig,
+        results: Vec<Result<NodeOutput, AgentError>>,
+        run: &RunState<'_>,
+        schedule: &mut Schedule,
+    ) -> Result<(), AgentError> {
+        let node_id = node.id.as_str();
+        let tolerated = schedule.branches.tolerates(node_id);
+        let mut outputs = Vec::new();
+        let mut outcomes = Vec::new();
+        for result in results {
+            match result {
+                Ok(output) => {
+                    outputs.push(output);
+                    outcomes.push(Ok(()));
+                }
+                Err(e) if tolerated => {
+                    run.context.write().await.store_failure(node_id, &e.to_string());
+                    outcomes.push(Err(e));
+                }
+                Err(e) => return Err(e),
             }
+        }
+        schedule.branches.record(node_id, outcomes)?;
 
-            let inputs: Vec<String> = edge.from.as_vec()
-                .iter()
-                .filter_map(|id| ctx.get(*id).cloned())
-                .collect();
-
-            if !inputs.is_empty() {
-                return inputs.join("\n\n---\n\n");
+        let output = match outputs.len() {
+            0 => {
+                schedule.set_state(node_id, NodeState::Failed);
+                schedule.kill(node_id);
+                return Ok(());
             }
+            1 => outputs.remove(0),
+            _ => NodeOutput {
+                content: outputs.iter().map(|o| o.content.as_str()).collect::<Vec<_>>().join("\n\n---\n\n"),
+                next_nodes: outputs.iter().flat_map(|o| o.next_nodes.iter().cloned()).collect(),
+                verdict: combined_verdict(&outputs),
+                halted: outputs.iter().any(|o| o.halted),
+                value: combined_value(&outputs),
+                usage: Some(combined_usage(&outputs)),
+            },
+        };
+        run.context.write().await.store(node_id, &output);
+        schedule.set_state(node_id, NodeState::Done);
+        if output.halted {
+            schedule.halted.get_or_insert_with(|| node_id.to_string());
+            return Ok(());
         }
 
-        ctx.get("input").cloned().unwrap_or_default()
+        if !self.follow_feedback(node_id, run, schedule).await {
+            self.follow_edges(node_id, &output.next_nodes, run, schedule).await;
+        }
+        Ok(())
     }
 
-    /// Processes outgoing edges for a node, filtering by router decisions if applicable.
-    async fn process_outgoing_edges(
-        &self,
-        node_id: &str,
-        router_targets: &[String],
-        context: &Arc<RwLock<HashMap<String, String>>>,
-        executed: &mut HashSet<String>,
-        history: &[fissio_core::Message],
-        step: &Arc<RwLock<usize>>,
-    ) -> Result<(), AgentError> {
-        let edges_to_process: Vec<_> = self.get_outgoing_edges(node_id)
-            .into_iter()
-            .filter(|edge| {
-                let targets = edge.to.as_vec();
-                let none_executed = !targets.iter().any(|t: &&str| executed.contains(*t));
-                let matches_router = router_targets.is_empty() ||
-                    targets.iter().any(|t: &&str| router_targets.contains(&t.to_string()));
-                none_executed && matches_router
-            })
-            .collect();
+    /// Settles the pending edges leaving `node_id` (or `input`) once it has run.
+    ///
+    /// An edge is followed to a target if the target is among `next_nodes`
+    /// (when the node chose any) and the edge's condition holds. A dynamic
+    /// edge dispatches the node's plan: targets with subtasks run once per
+    /// subtask, with the subtask (plus the original request) as input, and
+    /// the others are skipped; without a plan it behaves like a direct edge.
+    /// Targets of parallel and dynamic edges are grouped under the edge's
+    /// failure policy.
+    async fn follow_edges(&self, node_id: &str, next_nodes: &[String], run: &RunState<'_>, schedule: &mut Schedule) {
+        let ctx = run.context.read().await;
+        let links = schedule.pending_links(node_id);
+        let mut edges: Vec<usize> = links.iter().map(|&i| schedule.link(i).edge).collect();
+        edges.dedup();
+
+        for index in edges {
+            let edge = &self.config.edges[index];
+            let holds = self.edge_holds(edge, run, &ctx);
+            let plan = match edge.edge_type {
+                EdgeType::Dynamic => ctx.text(node_id).and_then(|content| Plan::parse(&content)),
+                _ => None,
+            };
+            if edge.edge_type == EdgeType::Dynamic && plan.is_none() {
+                debug!("║ No plan for dynamic edge to {:?}, treating as direct", edge.to.as_vec());
+            }
 
-        for next_edge in edges_to_process {
-            self.process_edge(next_edge, context, executed, history, step).await?;
+            let mut dispatched = Vec::new();
+            let edge_links: Vec<usize> = links.iter().copied().filter(|&i| schedule.link(i).edge == index).collect();
+            for link in edge_links {
+                let target = schedule.link(link).to.clone();
+                let subtasks: Option<Vec<String>> = plan.as_ref().map(|plan| {
+                    plan.subtasks.iter()
+                        .filter(|s| s.worker == target)
+                        .map(|s| format!("{}\n\n---\n\nOriginal request:\n{}", s.task, run.input))
+                        .collect()
+                });
+                let taken = holds
+                    && (next_nodes.is_empty() || next_nodes.contains(&target))
+                    && subtasks.as_ref().is_none_or(|s| !s.is_empty());
+                schedule.settle(link, taken);
+                if !taken || schedule.state(&target) != Some(NodeState::Waiting) {
+                    continue;
+                }
+                if let Some(subtasks) = subtasks {
+                    info!("║ DYNAMIC DISPATCH: {} subtasks to [{}]", subtasks.len(), target);
+                    schedule.subtasks.insert(target.clone(), subtasks);
+                }
+                if matches!(edge.edge_type, EdgeType::Parallel | EdgeType::Dynamic) {
+                    dispatched.push(target);
+                }
+            }
+            schedule.branches.add(edge.on_failure, dispatched);
         }
-        Ok(())
     }
-}
 
-/// Maximum number of tool call iterations to prevent infinite loops.
-const MAX_TOOL_ITERATIONS: usize = 10;
+    /// Follows a feedback edge from `node_id` if its latest output did not pass.
+    ///
+    /// Resets the loop body so it runs again, with the critique attached to
+    /// the target's input. Returns `true` if the loop was re-entered, in which
+    /// case `node_id`'s forward edges must not be followed yet.
+    async fn follow_feedback(&self, node_id: &str, run: &RunState<'_>, schedule: &mut Schedule) -> bool {
+        let Some((index, edge)) = self.config.edges.iter().enumerate()
+            .find(|(_, e)| e.is_feedback() && e.from.as_vec().contains(&node_id))
+        else {
+            return false;
+        };
+
+        let verdict = {
+            let ctx = run.context.read().await;
+            ctx.verdict(node_id)
+                .cloned()
+                .unwrap_or_else(|| Verdict::parse(&ctx.text(node_id).unwrap_or_default()))
+        };
+        let critique = verdict.critique;
+        if verdict.pass {
+            debug!("║ [{}] passed, not following feedback edge", node_id);
+            return false;
+        }
 
-/// Returns current time in milliseconds since UNIX epoch.
-fn now_ms() -> i64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|d| d.as_millis() as i64)
-        .unwrap_or(0)
+        let iteration = schedule.iterations.entry(index).or_insert(0);
+        if *iteration >= edge.max_iterations() {
+            warn!("║ ⚠ Feedback from [{}] hit max iterations ({}), continuing", node_id, edge.max_iterations());
+            return false;
+        }
+        *iteration += 1;
+        let iteration = *iteration;
+
+        let targets = edge.to.as_vec();
+        let mut body = HashSet::new();
+        for target in &targets {
+            body.extend(self.get_loop_body(target, node_id));
+        }
+        if body.is_empty() {
+            warn!("║ ⚠ Feedback edge from [{}] to {:?} does not form a loop, ignoring", node_id, targets);
+            return false;
+        }
+
+        info!("╠══════════════════════════════════════════════════════════════");
+        info!("║ FEEDBACK: {} → {:?} (iteration {}/{})", node_id, targets, iteration, edge.max_iterations());
+        self.emit(EngineEvent::FeedbackIteration {
+            node_id: node_id.to_string(),
+            targets: targets.iter().map(|t| t.to_string()).collect(),
+            iteration,
+            max_iterations: edge.max_iterations(),
+        });
+
+        {
+            let ctx = run.context.read().await;
+            for target in &targets {
+                let mut section = String::new();
+                if let Some(previous) = ctx.text(target) {
+                    section.push_str(&format!("Previous attempt:\n{}\n\n", previous));
+                }
+                section.push_str(&format!("Feedback from {}:\n{}", node_id, critique));
+                schedule.feedback.insert(target.to_string(), section);
+            }
+        }
+        schedule.reset(&body);
+        true
+    }
+
+    /// Executes a node with lifecycle events, recording metrics and a span if observed.
+    async fn run_node(&self, node: &NodeConfig, input: &str, env: NodeEnv<'_>) -> Result<NodeOutput, AgentError> {
+        let node = self.render_prompt(node, env).await;
+        let node = node.as_ref();
+        if let Some(output) = env.journal.replay(node, input) {
+            info!("╠──────────────────────────────────────────────────────────────");
+            info!("║ NODE: {} restored from checkpoint", node.id);
+            self.emit(EngineEvent::NodeRestored { node_id: node.id.clone() });
+            return Ok(output);
+        }
+        if node.node_type == NodeType::Approval {
+            return self.run_approval(node, input, env).await;
+        }
+
+        // Queue for a slot under the pipeline's max_concurrency before the node starts
+        let _slot = match env.slots {
+            Some(slots) => slots.acquire().await.ok(),
+            None => None,
+        };
+        let current_step = next_step(env.step).await;
+        self.emit(EngineEvent::NodeStarted {
+            node_id: node.id.clone(),
+            node_type: node.node_type,
+            step: current_step,
+        });
+
+        let result = self.with_retries(node, input, move |model| async move {
+            self.execute_node(node, input, current_step, &model, env).await
+        }).await;
+        let attempt = match result {
+            Ok(attempt) => attempt,
+            Err(e) => {
+                self.emit(EngineEvent::Error { node_id: Some(node.id.clone()), message: e.to_string() });
+                return Err(e);
+            }
+        };
+        let (mut output, exec_metrics) = attempt.value;
+        attempt.permit.record((exec_metrics.input_tokens + exec_metrics.output_tokens) as u64);
+
+        let node_metrics = NodeMetrics {
+            node_id: node.id.clone(),
+            input_tokens: exec_metrics.input_tokens,
+            output_tokens: exec_metrics.output_tokens,
+            elapsed_ms: attempt.start.elapsed().as_millis() as u64,
+            tool_call_count: exec_metrics.tool_call_count,
+            iteration_count: exec_metrics.iteration_count,
+            estimated_cost_usd: None,
+            score: output.verdict.as_ref().map(|v| v.score),
+            attempt: attempt.number,
+            model: Some(attempt.model.id.clone()),
+            error: None,
+        };
+        output.usage = Some(NodeUsage {
+            model: node_metrics.model.clone(),
+            input_tokens: node_metrics.input_tokens,
+            output_tokens: node_metrics.output_tokens,
+            elapsed_ms: node_metrics.elapsed_ms,
+        });
+
+        if let Some(collector) = self.node_collector(node) {
+            // A structured output is recorded as its value, e.g. a router's classification
+            let recorded = output.value.as_ref().map(|value| value.to_string());
+            collector.record(node_metrics.clone());
+            collector.record_span(
+                &node.id,
+                &node.node_type.to_string(),
+                attempt.start_time_ms,
+                now_ms(),
+                input,
+                recorded.as_deref().unwrap_or(&output.content),
+                &node_metrics,
+            );
+        }
+
+        if env.journal.is_recording() {
+            let context = env.context.read().await.clone();
+            env.journal.record(node, input, &output, context);
+        }
+
+        self.emit(EngineEvent::NodeFinished { node_id: node.id.clone(), metrics: node_metrics });
+        Ok(output)
+    }
+
+    /// Applies the decision made on an approval node's input, or suspends the
+    /// run at the node if there is none yet.
+    async fn run_approval(&self, node: &NodeConfig, input: &str, env: NodeEnv<'_>) -> Result<NodeOutput, AgentError> {
+        let Some(decision) = env.journal.take_decision(&node.id, input) else {
+            let approval = PendingApproval {
+                node_id: node.id.clone(),
+                content: input.to_string(),
+                instructions: node.prompt.clone(),
+                requested_at: now_ms(),
+            };
+            let run_id = env.journal.suspend(approval)?;
+            info!("╠──────────────────────────────────────────────────────────────");
+            info!("║ NODE: {} awaiting approval", node.id);
+            self.emit(EngineEvent::ApprovalRequested {
+                run_id: run_id.to_string(),
+                node_id: node.id.clone(),
+                content: input.to_string(),
+                instructions: node.prompt.clone(),
+            });
+            return Ok(NodeOutput { halted: true, ..NodeOutput::default() });
+        };
+
+        let start_time_ms = now_ms();
+        let current_step = next_step(env.step).await;
+        self.emit(EngineEvent::NodeStarted { node_id: node.id.clone(), node_type: node.node_type, step: current_step });
+        info!("╠──────────────────────────────────────────────────────────────");
+        info!("║ [{}] NODE: {} ({:?})", current_step, node.id, node.node_type);
+
+        let config = ApprovalConfig::from_value(&node.config);
+        let approved = match decision {
+            Decision::Approve => Ok(input.to_string()),
+            Decision::Edit { content } => Ok(content),
+            Decision::Reject { reason } => Err(reason),
+        };
+        let mut output = match approved {
+            Ok(content) => {
+                info!("║     ✓ Approved{}", if content == input { "" } else { " with edits" });
+                let next_nodes = self.targets_except(&node.id, config.reject_target.as_deref());
+                NodeOutput { content, next_nodes, ..NodeOutput::default() }
+            }
+            Err(reason) => {
+                warn!("║     ✗ Rejected: {}", reason.as_deref().unwrap_or("no reason given"));
+                let content = config.rejection_message(&node.id, reason.as_deref());
+                match config.reject_target {
+                    Some(target) => NodeOutput { content, next_nodes: vec![target], ..NodeOutput::default() },
+                    None => NodeOutput { content, halted: true, ..NodeOutput::default() },
+                }
+            }
+        };
+
+        let node_metrics = NodeMetrics {
+            elapsed_ms: (now_ms() - start_time_ms).max(0) as u64,
+            attempt: 1,
+            ..NodeMetrics::new(&node.id)
+        };
+        output.usage = Some(NodeUsage { elapsed_ms: node_metrics.elapsed_ms, ..NodeUsage::default() });
+        if let Some(collector) = self.node_collector(node) {
+            collector.record(node_metrics.clone());
+            collector.record_span(
+                &node.id,
+                &node.node_type.to_string(),
+                start_time_ms,
+                now_ms(),
+                input,
+                &output.content,
+                &node_metrics,
+            );
+        }
+        let context = env.context.read().await.clone();
+        env.journal.record(node, input, &output, context);
+
+        self.emit(EngineEvent::NodeFinished { node_id: node.id.clone(), metrics: node_metrics });
+        Ok(output)
+    }
+
+    /// Returns the targets of a node's outgoing edges other than `excluded`,
+    /// or no targets (follow every edge) if nothing is excluded.
+    fn targets_except(&self, node_id: &str, excluded: Option<&str>) -> Vec<String> {
+        let Some(excluded) = excluded else {
+            return vec![];
+        };
+        self.get_outgoing_edges(node_id)
+            .iter()
+            .flat_map(|e| e.to.as_vec())
+            .filter(|t| *t != excluded)
+            .map(String::from)
+            .collect()
+    }
+
+    /// Runs `run` against the node's model chain under its retry policy.
+    ///
+    /// Each attempt first waits for the attached rate limits on its model,
+    /// within the node's `timeout_ms`.
+    /// Each model gets up to `max_attempts` tries with exponential backoff
+    /// between them, then the next fallback model is tried. Every failed attempt
+    /// is recorded as a span carrying the error. Errors the policy doesn't retry
+    /// fail immediately; once the chain is exhausted the node fails with
+    /// [`AgentError::MaxRetriesExceeded`], carrying the last attempt's error.
+    async fn with_retries<T, F, Fut>(&self, node: &NodeConfig, input: &str, mut run: F) -> Result<Attempt<T>, AgentError>
+    where
+        F: FnMut(Arc<ModelConfig>) -> Fut,
+        Fut: Future<Output = Result<T, AgentError>>,
+    {
+        let policy = node.retry.clone().unwrap_or_else(RetryPolicy::single_attempt);
+        let tries = policy.max_attempts.max(1);
+        let models = self.get_node_models(node);
+        let total = tries * models.len() as u32;
+
+        for number in 1..=total {
+            let model = &models[((number - 1) / tries) as usize];
+            let start = Instant::now();
+            let start_time_ms = now_ms();
+            let deadline = node.timeout_ms.map(|ms| Deadline::after(ms, &format!("node '{}'", node.id)));
+            // The node's timeout also bounds the wait for a rate-limit slot
+            let attempt = run(Arc::clone(model));
+            let result = within(deadline.as_ref(), async {
+                let permit = self.acquire(model).await;
+                attempt.await.map(|value| (value, permit))
+            }).await;
+            let error = match result {
+                Ok((value, permit)) => {
+                    return Ok(Attempt { value, model: Arc::clone(model), number, start, start_time_ms, deadline, permit });
+                }
+                Err(e) => e,
+            };
+            self.record_failed_attempt(node, input, model, number, &error, start_time_ms);
+
+            if !policy.retries(error.kind()) {
+                return Err(error);
+            }
+            if number == total {
+                warn!("║     ✗ All {} attempts failed, last error: {}", number, error);
+                return Err(if number > 1 {
+                    AgentError::MaxRetriesExceeded { attempts: number, last: Box::new(error) }
+                } else {
+                    error
+                });
+            }
+
+            // Back off between tries on the same model; switch to a fallback immediately
+            let next = &models[(number / tries) as usize];
+            let try_on_model = (number - 1) % tries + 1;
+            let delay = if try_on_model < tries { policy.backoff(try_on_model) } else { Duration::ZERO };
+            warn!("║     ⚠ Attempt {} failed: {}; retrying with {} in {:?}", number, error, next.name, delay);
+            self.emit(EngineEvent::NodeRetry {
+                node_id: node.id.clone(),
+                attempt: number,
+                error: error.to_string(),
+                model: next.id.clone(),
+                delay_ms: delay.as_millis() as u64,
+            });
+            tokio::time::sleep(delay).await;
+        }
+
+        unreachable!("a node's model chain is never empty, so its last attempt returns")
+    }
+
+    /// Waits until an attempt on `model` fits within the attached rate limits.
+    async fn acquire(&self, model: &ModelConfig) -> Permit {
+        match &self.rate_limiter {
+            Some(rate_limiter) => rate_limiter.acquire(model).await,
+            None => Permit::default(),
+        }
+    }
+
+    /// Records a failed attempt as a span with the error and no output.
+    fn record_failed_attempt(
+        &self,
+        node: &NodeConfig,
+        input: &str,
+        model: &ModelConfig,
+        attempt: u32,
+        error: &AgentError,
+        start_time_ms: i64,
+    ) {
+        let Some(collector) = self.node_collector(node) else {
+            return;
+        };
+        let end_time_ms = now_ms();
+        let metrics = NodeMetrics {
+            elapsed_ms: (end_time_ms - start_time_ms).max(0) as u64,
+            attempt,
+            model: Some(model.id.clone()),
+            error: Some(error.to_string()),
+            ..NodeMetrics::new(&node.id)
+        };
+        collector.record_span(&node.id, &node.node_type.to_string(), start_time_ms, end_time_ms, input, "", &metrics);
+    }
+
+    /// Executes a single node and returns its output along with execution metrics.
+    /// If the node has tools configured, runs an agentic loop until the LLM produces final output.
+    /// For Router nodes, executes an LLM call to determine routing and returns the target in next_nodes.
+    async fn execute_node(
+        &self,
+        node: &NodeConfig,
+        input: &str,
+        step: usize,
+        model: &ModelConfig,
+        env: NodeEnv<'_>,
+    ) -> Result<(NodeOutput, ExecutionMetrics), AgentError> {
+        info!("╠──────────────────────────────────────────────────────────────");
+        info!("║ [{}] NODE: {} ({:?})", step, node.id, node.node_type);
+        info!("║     Model: {}", model.name);
+        if !node.tools.is_empty() {
+            info!("║     Tools: {:?}", node.tools);
+        }
+        debug!("║     Input: {}...", input.chars().take(100).collect::<String>());
+
+        let start = Instant::now();
+        info!("║     → {}", node.node_type.action_label());
+        let calls = self.calls(&node.id, model);
+
+        if node.node_type == NodeType::Custom {
+            let output = self.execute_custom(node, model, input, env).await?;
+            info!("║     ✓ Completed in {:?}", start.elapsed());
+            return Ok((output, ExecutionMetrics::default()));
+        }
+
+        if node.node_type == NodeType::Pipeline {
+            let content = self.execute_pipeline_node(node, input, env).await?;
+            info!("║     ✓ Completed in {:?}", start.elapsed());
+            return Ok((NodeOutput::new(content), ExecutionMetrics::default()));
+        }
+
+        if node.node_type == NodeType::Map {
+            let results = self.execute_map(node, input, env).await?;
+            info!("║     ✓ Completed in {:?}", start.elapsed());
+            return Ok((NodeOutput::json(results), ExecutionMetrics::default()));
+        }
+
+        // Router node: execute LLM to classify and determine routing target
+        if node.node_type.is_router() {
+            let outgoing_targets = self.get_outgoing_targets(&node.id);
+            let config = RouterConfig::from_value(&node.config)
+                .map_err(|e| AgentError::ParseError(format!("router '{}' {}", node.id, e)))?;
+            let rules: Vec<_> = self.get_outgoing_edges(&node.id)
+                .into_iter()
+                .filter_map(|e| Some((e.when.as_ref()?, e.to.as_vec())))
+                .collect();
+            let ruled = config.apply_rules(&rules, env.patterns, input, &*env.context.read().await);
+            if let Some(classification) = ruled {
+                let next_nodes = classification.targets.clone();
+                info!("║     ✓ Completed in {:?}, routed by rule to: {:?}", start.elapsed(), next_nodes);
+                self.emit(EngineEvent::RouterDecision { node_id: node.id.clone(), targets: next_nodes.clone() });
+                let classification = serde_json::to_value(&classification)
+                    .map_err(|e| AgentError::ParseError(e.to_string()))?;
+                let output = NodeOutput { content: input.to_string(), next_nodes, value: Some(classification), ..NodeOutput::default() };
+                return Ok((output, ExecutionMetrics::default()));
+            }
+            if let Some(field) = route_field(node) {
+                let next_nodes: Vec<String> = route_on_field(input, field, &outgoing_targets)
+                    .or_else(|| {
+                        let fallback = config.fallback_target(&outgoing_targets);
+                        warn!("║     ⚠ Field '{}' names no target in {:?}, falling back to {:?}", field, outgoing_targets, fallback);
+                        fallback
+                    })
+                    .into_iter()
+                    .collect();
+                info!("║     ✓ Completed in {:?}, routed to: {:?}", start.elapsed(), next_nodes);
+                self.emit(EngineEvent::RouterDecision { node_id: node.id.clone(), targets: next_nodes.clone() });
+                let output = NodeOutput { content: input.to_string(), next_nodes, ..NodeOutput::default() };
+                return Ok((output, ExecutionMetrics::default()));
+            }
+            let history = self.node_history(node, model, env).await?;
+            let (classification, mut metrics) =
+                execute_router(calls, node.prompt.as_deref(), &config, input, &history, &outgoing_targets).await?;
+            metrics.accumulate(&history.metrics);
+            let next_nodes = classification.targets.clone();
+            info!("║     ✓ Completed in {:?}, routed to: {:?}", start.elapsed(), next_nodes);
+            self.emit(EngineEvent::RouterDecision { node_id: node.id.clone(), targets: next_nodes.clone() });
+            let classification = serde_json::to_value(&classification)
+                .map_err(|e| AgentError::ParseError(e.to_string()))?;
+            let output = NodeOutput { content: input.to_string(), next_nodes, value: Some(classification), ..NodeOutput::default() };
+            return Ok((output, metrics));
+        }
+
+        if node.node_type == NodeType::Orchestrator {
+            let workers: Vec<WorkerInfo> = self.get_outgoing_edges(&node.id)
+                .iter()
+                .filter(|e| e.edge_type == EdgeType::Dynamic)
+                .flat_map(|e| e.to.as_vec())
+                .filter_map(|id| self.get_node(id))
+                .map(|worker| WorkerInfo {
+                    id: worker.id.clone(),
+                    description: worker.prompt.as_deref()
+                        .and_then(|p| p.lines().next())
+                        .map(|line| line.chars().take(200).collect()),
+                })
+                .collect();
+
+            if !workers.is_empty() {
+                let (plan, metrics) = execute_orchestrator(calls, node.prompt.as_deref(), &node.config, input, &workers).await?;
+                info!("║     ✓ Completed in {:?}", start.elapsed());
+                self.emit(EngineEvent::PlanCreated { node_id: node.id.clone(), subtasks: plan.subtasks.clone() });
+                let plan = serde_json::to_value(&plan)
+                    .map_err(|e| AgentError::ParseError(e.to_string()))?;
+                return Ok((NodeOutput::json(plan), metrics));
+            }
+        }
+
+        if node.node_type.is_aggregator() {
+            let strategy = MergeStrategy::from_config(&node.config, node.prompt.is_some())?;
+            let (inputs, failures) = {
+                let ctx = env.context.read().await;
+                (self.get_labelled_inputs(&node.id, &ctx), self.get_failed_inputs(&node.id, &ctx))
+            };
+            let (content, metrics) = execute_aggregator(calls, node.prompt.as_deref(), strategy, &inputs, &failures).await?;
+            info!("║     ✓ Completed in {:?}", start.elapsed());
+            return Ok((NodeOutput::new(content), metrics));
+        }
+
+        if node.node_type == NodeType::Gate {
+            let result = self.execute_gate(node, env.patterns, calls, input).await?;
+            info!("║     ✓ Completed in {:?}", start.elapsed());
+            return Ok(result);
+        }
+
+        // Evaluator node: grade the input and pass it through with the verdict
+        if node.node_type == NodeType::Evaluator {
+            let config = EvaluatorConfig::from_value(&node.config);
+            let (verdict, metrics) = execute_evaluator(calls, node.prompt.as_deref(), &config, input).await?;
+            let next_nodes: Vec<String> = config.branch(&verdict).map(String::from).into_iter().collect();
+            info!("║     ✓ Completed in {:?}", start.elapsed());
+            let output = NodeOutput { content: input.to_string(), next_nodes, verdict: Some(verdict), ..NodeOutput::default() };
+            return Ok((output, metrics));
+        }
+
+        let (output, metrics) = if node.node_type.requires_llm() {
+            let history = self.node_history(node, model, env).await?;
+            let (output, mut metrics) = match &node.output_schema {
+                Some(schema) => {
+                    let (value, metrics) = self.execute_structured(node, schema, calls, input, &history).await?;
+                    (NodeOutput::json(value), metrics)
+                }
+                None => {
+                    let (content, metrics) = self.execute_node_with_tools(node, calls, input, &history).await?;
+                    (NodeOutput::new(content), metrics)
+                }
+            };
+            metrics.accumulate(&history.metrics);
+            (output, metrics)
+        } else {
+            (NodeOutput::new(input), ExecutionMetrics::default())
+        };
+
+        info!("║     ✓ Completed in {:?}", start.elapsed());
+
+        Ok((output, metrics))
+    }
+
+    /// Executes a Custom node with its registered [`NodeExecutor`].
+    async fn execute_custom(
+        &self,
+        node: &NodeConfig,
+        model: &ModelConfig,
+        input: &str,
+        env: NodeEnv<'_>,
+    ) -> Result<NodeOutput, AgentError> {
+        let name = executor_name(node).unwrap_or_default();
+        let executor = self.node_registry.get(name)
+            .ok_or_else(|| AgentError::UnknownWorker(name.to_string()))?;
+        info!("║     Executor: {}", name);
+
+        // Snapshot so the executor doesn't hold the context lock while parallel branches write
+        let run = NodeInput {
+            user_input: env.input.to_string(),
+            history: env.history.to_vec(),
+            context: env.context.read().await.clone(),
+        };
+        executor.execute(&NodeContext { node, input, run: &run, model }).await
+    }
+
+    /// Executes a Pipeline node as a nested run of the pipeline it names.
+    ///
+    /// The nested run's tokens are recorded on its own spans, so the node
+    /// itself reports none.
+    async fn execute_pipeline_node(&self, node: &NodeConfig, input: &str, env: NodeEnv<'_>) -> Result<String, AgentError> {
+        let id = pipeline_ref(node).unwrap_or_default();
+        info!("║     Pipeline: {}", id);
+
+        let input = match env.templates.get(&input_key(&node.id)) {
+            Some(template) => {
+                let context = env.context.read().await;
+                template.render(&TemplateContext {
+                    input: env.input,
+                    context: &context,
+                    history: env.history,
+                    variables: &self.config.variables,
+                })
+            }
+            None => input.to_string(),
+        };
+
+        self.run_nested_pipeline(id, &format!("{}/", node.id), &input, env.history).await
+    }
+
+    /// Runs the pipeline with ID `id` nested in this one, recording its spans
+    /// and emitting its events with node IDs under `prefix`, and returns its
+    /// output. Model overrides keyed under `prefix` apply to its nodes.
+    async fn run_nested_pipeline(
+        &self,
+        id: &str,
+        prefix: &str,
+        input: &str,
+        history: &[fissio_core::Message],
+    ) -> Result<String, AgentError> {
+        let config = self.pipelines.get(id)
+            .ok_or_else(|| AgentError::UnknownWorker(format!("pipeline '{}'", id)))?;
+        let mut ancestry = self.ancestry.clone();
+        ancestry.push(self.config.id.clone());
+        let node_overrides = self.node_overrides.iter()
+            .filter_map(|(node_id, model)| Some((node_id.strip_prefix(prefix)?.to_string(), model.clone())))
+            .collect();
+        let nested = PipelineEngine {
+            config: config.clone(),
+            resolver: Arc::clone(&self.resolver),
+            node_overrides,
+            tool_registry: Arc::clone(&self.tool_registry),
+            node_registry: Arc::clone(&self.node_registry),
+            pipelines: Arc::clone(&self.pipelines),
+            ancestry,
+            collector: self.collector.clone().map(|parent| {
+                Arc::new(NestedCollector { parent, prefix: prefix.to_string() }) as Arc<dyn MetricsCollector>
+            }),
+            events: self.events.clone(),
+            event_prefix: format!("{}{}", self.event_prefix, prefix),
+            rate_limiter: self.rate_limiter.clone(),
+            checkpoints: None,
+            provider: Arc::clone(&self.provider),
+            recorder: self.recorder.clone(),
+            replay: self.replay.clone(),
+        };
+
+        // Dropping this future (on timeout or cancellation) drops the nested run with it
+        let output = run_nested(&nested, input, history).await?;
+        collect_output(id, output).await
+    }
+
+    /// Executes a Map node: runs its body once per item of its input, at most
+    /// `max_concurrency` at a time, and returns the results as a JSON array.
+    ///
+    /// The map node already holds a slot under the pipeline's
+    /// `max_concurrency`, so its body runs don't take one; their tokens are
+    /// recorded on their own spans.
+    async fn execute_map(&self, node: &NodeConfig, input: &str, env: NodeEnv<'_>) -> Result<serde_json::Value, AgentError> {
+        let map = MapConfig::from_value(&node.config)
+            .map_err(|e| AgentError::ParseError(format!("map node '{}' {}", node.id, e)))?;
+        let items = map.items(input)?;
+        info!("║     Map: {} items, {} at a time", items.len(), map.max_concurrency);
+
+        // Futures are lazy, so `buffer_unordered` bounds how many items run
+        // at once; a slow item doesn't hold the slots of later ones that finish
+        let env = NodeEnv { slots: None, ..env };
+        let body = &map.body;
+        let runs: Vec<_> = items.iter().enumerate()
+            .map(|(index, item)| async move {
+                let result = self.run_map_item(node, body, index, item, env).await?;
+                Ok::<_, AgentError>((index, serde_json::Value::String(result)))
+            })
+            .collect();
+        let mut results: Vec<(usize, serde_json::Value)> = futures::stream::iter(runs)
+            .buffer_unordered(map.max_concurrency)
+            .try_collect()
+            .await?;
+        results.sort_by_key(|(index, _)| *index);
+        Ok(serde_json::Value::Array(results.into_iter().map(|(_, value)| value).collect()))
+    }
+
+    /// Runs a map node's body on the item at `index`.
+    async fn run_map_item(
+        &self,
+        node: &NodeConfig,
+        body: &MapBody,
+        index: usize,
+        item: &str,
+        env: NodeEnv<'_>,
+    ) -> Result<String, AgentError> {
+        match body {
+            MapBody::Node(id) => {
+                let body = self.get_node(id)
+                    .ok_or_else(|| AgentError::UnknownWorker(format!("node '{}'", id)))?;
+                Ok(run_map_body(self, body, item, env).await?.content)
+            }
+            MapBody::Pipeline(id) => {
+                self.run_nested_pipeline(id, &format!("{}[{}]/", node.id, index), item, env.history).await
+            }
+        }
+    }
+
+    /// Returns the node with its prompt template rendered against the run so far.
+    async fn render_prompt<'n>(&self, node: &'n NodeConfig, env: NodeEnv<'_>) -> Cow<'n, NodeConfig> {
+        let Some(template) = env.templates.get(&node.id) else {
+            return Cow::Borrowed(node);
+        };
+        let context = env.context.read().await;
+        let prompt = template.render(&TemplateContext {
+            input: env.input,
+            context: &context,
+            history: env.history,
+            variables: &self.config.variables,
+        });
+        Cow::Owned(NodeConfig { prompt: Some(prompt), ..node.clone() })
+    }
+
+    /// Resolves the conversation history a node sees under its `history` policy.
+    async fn node_history<'a>(
+        &self,
+        node: &NodeConfig,
+        model: &ModelConfig,
+        env: NodeEnv<'a>,
+    ) -> Result<NodeHistory<'a>, AgentError> {
+        let policy = HistoryPolicy::from_config(&node.id, &node.config)?;
+        resolve_history(policy, env.history, self.calls(&node.id, model), env.summaries).await
+    }
+
+    /// Executes a Gate node: deterministic rules first, then the LLM check if configured.
+    ///
+    /// Passing input flows through unchanged (skipping the rejection edge, if any).
+    /// Rejected input is replaced by the rejection message and either diverted to
+    /// `reject_target` or halts the run.
+    async fn execute_gate(
+        &self,
+        node: &NodeConfig,
+        patterns: &Patterns,
+        calls: Calls<'_>,
+        input: &str,
+    ) -> Result<(NodeOutput, ExecutionMetrics), AgentError> {
+        let config = GateConfig::from_value(&node.config);
+
+        let (rejection, metrics) = match (config.check_rules(patterns, input), config.criterion(node.prompt.as_deref())) {
+            (Err(reason), _) => (Some(reason), ExecutionMetrics::default()),
+            (Ok(()), Some(criterion)) => {
+                let (verdict, metrics) = execute_gate_check(calls, criterion, input).await?;
+                let reason = if verdict.critique.is_empty() {
+                    format!("failed check: {}", criterion)
+                } else {
+                    verdict.critique
+                };
+                ((!verdict.pass).then_some(reason), metrics)
+            }
+            (Ok(()), None) => (None, ExecutionMetrics::default()),
+        };
+
+        let Some(reason) = rejection else {
+            let next_nodes = self.targets_except(&node.id, config.reject_target.as_deref());
+            let output = NodeOutput { content: input.to_string(), next_nodes, ..NodeOutput::default() };
+            return Ok((output, metrics));
+        };
+
+        warn!("║     ✗ Gate rejected input: {}", reason);
+        self.emit(EngineEvent::GateRejected {
+            node_id: node.id.clone(),
+            reason: reason.clone(),
+            reject_target: config.reject_target.clone(),
+        });
+
+        let content = config.rejection_message(&node.id, &reason);
+        let output = match config.reject_target {
+            Some(target) => NodeOutput { content, next_nodes: vec![target], ..NodeOutput::default() },
+            None => NodeOutput { content, halted: true, ..NodeOutput::default() },
+        };
+        Ok((output, metrics))
+    }
+
+    /// Executes an LLM or worker node whose output must match `schema`,
+    /// re-prompting with the validation errors until it does, and returns
+    /// the parsed value.
+    async fn execute_structured(
+        &self,
+        node: &NodeConfig,
+        schema: &serde_json::Value,
+        calls: Calls<'_>,
+        input: &str,
+        history: &NodeHistory<'_>,
+    ) -> Result<(serde_json::Value, ExecutionMetrics), AgentError> {
+        let schema = OutputSchema::compile(schema)
+            .map_err(|e| AgentError::ParseError(format!("output_schema of node '{}' is invalid: {}", node.id, e)))?;
+        let prompt = schema.system_prompt(node.prompt.as_deref().unwrap_or(""));
+        let node = NodeConfig { prompt: Some(prompt), ..node.clone() };
+        let (mut content, mut metrics) = self.execute_node_with_tools(&node, calls, input, history).await?;
+
+        let mut attempt = 1;
+        loop {
+            let error = match schema.parse(&content) {
+                Ok(value) => return Ok((value, metrics)),
+                Err(error) => error,
+            };
+            if attempt == SCHEMA_ATTEMPTS {
+                return Err(AgentError::ParseError(format!(
+                    "output of node '{}' doesn't match its schema after {} attempts: {}",
+                    node.id, SCHEMA_ATTEMPTS, error
+                )));
+            }
+
+            attempt += 1;
+            warn!("║     ⚠ Output doesn't match schema ({}), re-prompting", error);
+            let system_prompt = history.system_prompt(node.prompt.as_deref().unwrap_or(""));
+            let repair = repair_input(input, &content, &error);
+            let response = calls.chat_with_schema(system_prompt.as_ref(), history.messages, &repair, schema.schema()).await?;
+            metrics.accumulate(&response.metrics);
+            metrics.iteration_count += 1;
+            content = response.content;
+        }
+    }
+
+    /// Executes an LLM node, potentially with an agentic tool loop.
+    ///
+    /// If no tools are configured, performs a simple chat completion.
+    /// With tools, runs an iterative loop:
+    /// 1. Send message + tool schemas to LLM
+    /// 2. If LLM returns tool calls, execute them via the registry
+    /// 3. Send tool results back to LLM
+    /// 4. Repeat until LLM returns final content (max 10 iterations)
+    ///
+    /// Prior conversation turns from `history` precede the input in every call.
+    /// Returns the content and accumulated execution metrics.
+    async fn execute_node_with_tools(
+        &self,
+        node: &NodeConfig,
+        calls: Calls<'_>,
+        input: &str,
+        history: &NodeHistory<'_>,
+    ) -> Result<(String, ExecutionMetrics), AgentError> {
+        let system_prompt = history.system_prompt(node.prompt.as_deref().unwrap_or(""));
+        let system_prompt = system_prompt.as_ref();
+        let tools = &node.tools;
+        let mut metrics = ExecutionMetrics::default();
+
+        // No tools configured - simple chat
+        if tools.is_empty() {
+            let response = match &node.output_schema {
+                Some(schema) => calls.chat_with_schema(system_prompt, history.messages, input, schema).await?,
+                None => calls.chat_with_history(system_prompt, history.messages, input).await?,
+            };
+            info!("║     ← Response: {} chars", response.content.len());
+            metrics.accumulate(&response.metrics);
+            metrics.iteration_count = 1;
+            return Ok((response.content, metrics));
+        }
+
+        // Get tool schemas for configured tools
+        let tool_schemas: Vec<ToolSchema> = tools
+            .iter()
+            .filter_map(|name| {
+                self.tool_registry.get(name).map(|t| ToolSchema {
+                    name: t.name().to_string(),
+                    description: t.description().to_string(),
+                    parameters: t.parameters(),
+                })
+            })
+            .collect();
+
+        if tool_schemas.is_empty() {
+            warn!("║     ⚠ No valid tools found in registry for: {:?}", tools);
+            let response = calls.chat_with_history(system_prompt, history.messages, input).await?;
+            metrics.accumulate(&response.metrics);
+            metrics.iteration_count = 1;
+            return Ok((response.content, metrics));
+        }
+
+        info!("║     → Starting agentic loop with {} tools", tool_schemas.len());
+
+        // Agentic loop
+        let mut messages = history.messages
+            .iter()
+            .map(|msg| match msg.role {
+                fissio_core::MessageRole::User => UnifiedLlmClient::user_message(&msg.content),
+                fissio_core::MessageRole::Assistant => UnifiedLlmClient::assistant_message(&msg.content),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        messages.push(UnifiedLlmClient::user_message(input)?);
+        let mut pending_tool_calls: Option<Vec<ToolCall>> = None;
+
+        loop {
+            metrics.iteration_count += 1;
+            if metrics.iteration_count > MAX_TOOL_ITERATIONS as u32 {
+                warn!("║     ⚠ Max tool iterations ({}) reached", MAX_TOOL_ITERATIONS);
+                return Err(AgentError::LlmError(format!(
+                    "Max tool iterations ({}) exceeded",
+                    MAX_TOOL_ITERATIONS
+                )));
+            }
+
+            let response = calls
+                .chat_with_tools(
+                    system_prompt,
+                    history.messages,
+                    input,
+                    &messages,
+                    &tool_schemas,
+                    pending_tool_calls.as_deref(),
+                )
+                .await?;
+
+            match response {
+                ChatResponse::Content(llm_response) => {
+                    info!(
+                        "║     ← Final response: {} chars (after {} iterations)",
+                        llm_response.content.len(),
+                        metrics.iteration_count
+                    );
+                    metrics.accumulate(&llm_response.metrics);
+                    return Ok((llm_response.content, metrics));
+                }
+                ChatResponse::ToolCalls { calls: tool_calls, metrics: llm_metrics } => {
+                    metrics.accumulate(&llm_metrics);
+                    info!(
+                        "║     ← Tool calls: {:?}",
+                        tool_calls.iter().map(|c| &c.name).collect::<Vec<_>>()
+                    );
+
+                    for call in &tool_calls {
+                        let tool = self.tool_registry.get(&call.name).ok_or_else(|| {
+                            AgentError::LlmError(format!("Tool not found: {}", call.name))
+                        })?;
+
+                        info!("║       → Executing tool: {}", call.name);
+                        self.emit(EngineEvent::ToolCallStarted {
+                            node_id: node.id.clone(),
+                            tool: call.name.clone(),
+                            arguments: call.arguments.clone(),
+                        });
+                        let tool_start = Instant::now();
+                        let result = calls.execute_tool(tool.as_ref(), call).await;
+                        self.emit(EngineEvent::ToolCallFinished {
+                            node_id: node.id.clone(),
+                            tool: call.name.clone(),
+                            elapsed_ms: tool_start.elapsed().as_millis() as u64,
+                            success: result.is_ok(),
+                        });
+                        let result = result?;
+
+                        info!("║       ← Tool result: {} chars", result.len());
+                        metrics.tool_call_count += 1;
+
+                        // Add tool result to messages
+                        messages.push(UnifiedLlmClient::tool_result_message(&call.id, &result)?);
+                    }
+
+                    // Store tool calls for next iteration (needed for Anthropic message format)
+                    pending_tool_calls = Some(tool_calls);
+                }
+            }
+        }
+    }
+
+    /// Gets the input text for a node: the outputs of every source whose edge
+    /// to it was followed, joined in edge order (or the user input if there
+    /// are none), plus any pending feedback critique addressed to it.
+    ///
+    /// Router nodes also see the verdicts of upstream evaluators so they can
+    /// branch on them. Aggregating nodes see every upstream output as a
+    /// labelled section.
+    async fn get_input_for_node(&self, node_id: &str, run: &RunState<'_>, schedule: &Schedule) -> String {
+        let ctx = run.context.read().await;
+
+        if self.get_node(node_id).is_some_and(|n| n.node_type.is_aggregator()) {
+            return format_labelled(&self.get_labelled_inputs(node_id, &ctx), &self.get_failed_inputs(node_id, &ctx));
+        }
+
+        let sources: Vec<&str> = schedule.sources(node_id).into_iter().filter(|id| ctx.output(id).is_some()).collect();
+
+        let mut input = if sources.is_empty() {
+            run.input.to_string()
+        } else {
+            sources.iter()
+                .filter_map(|id| ctx.passed_on(id))
+                .collect::<Vec<_>>()
+                .join("\n\n---\n\n")
+        };
+
+        if self.get_node(node_id).is_some_and(|n| n.node_type.is_router()) {
+            for id in &sources {
+                if let Some(verdict) = ctx.verdict(id).and_then(|v| serde_json::to_string(v).ok()) {
+                    input.push_str(&format!("\n\n---\n\nVerdict from {}:\n{}", id, verdict));
+                }
+            }
+        }
+
+        if let Some(feedback) = schedule.feedback.get(node_id) {
+            input.push_str("\n\n---\n\n");
+            input.push_str(feedback);
+        }
+        input
+    }
+
+    /// Gets `(source node ID, output)` pairs from every incoming edge, in edge order.
+    fn get_labelled_inputs(&self, node_id: &str, ctx: &RunContext) -> Vec<(String, String)> {
+        let mut inputs: Vec<(String, String)> = Vec::new();
+        let sources = self.config.edges.iter()
+            .filter(|edge| !edge.is_feedback() && edge.to.as_vec().contains(&node_id))
+            .flat_map(|edge| edge.from.as_vec());
+
+        for source in sources {
+            if inputs.iter().any(|(id, _)| id == source) {
+                continue;
+            }
+            if let Some(content) = ctx.passed_on(source) {
+                inputs.push((source.to_string(), content.into_owned()));
+            }
+        }
+        inputs
+    }
+
+    /// Gets `(source node ID, error)` pairs for incoming sources whose branch failed.
+    fn get_failed_inputs(&self, node_id: &str, ctx: &RunContext) -> Vec<(String, String)> {
+        let mut failures: Vec<(String, String)> = Vec::new();
+        let sources = self.config.edges.iter()
+            .filter(|edge| !edge.is_feedback() && edge.to.as_vec().contains(&node_id))
+            .flat_map(|edge| edge.from.as_vec());
+
+        for source in sources {
+            if failures.iter().any(|(id, _)| id == source) {
+                continue;
+            }
+            if let Some(error) = ctx.error(source) {
+                failures.push((source.to_string(), error.to_string()));
+            }
+        }
+        failures
+    }
 }
 
-/// Executes a single node and returns its output along with execution metrics.
-/// If the node has tools configured, runs an agentic loop until the LLM produces final output.
-/// For Router nodes, executes an LLM call to determine routing and returns the target in next_nodes.
-async fn execute_node(
-    node_id: &str,
-    node_type: NodeType,
-    model: &ModelConfig,
-    prompt: Option<&str>,
-    input: &str,
-    tools: &[String],
-    tool_registry: &ToolRegistry,
-    step: usize,
-    outgoing_targets: &[String],
-) -> Result<(NodeOutput, ExecutionMetrics), AgentError> {
-    info!("╠──────────────────────────────────────────────────────────────");
-    info!("║ [{}] NODE: {} ({:?})", step, node_id, node_type);
-    info!("║     Model: {}", model.name);
-    if !tools.is_empty() {
-        info!("║     Tools: {:?}", tools);
-    }
-    debug!("║     Input: {}...", input.chars().take(100).collect::<String>());
-
-    let start = std::time::Instant::now();
-    info!("║     → {}", node_type.action_label());
-
-    // Router node: execute LLM to classify and determine routing target
-    if node_type.is_router() {
-        let (content, next_nodes, metrics) = execute_router(model, prompt, input, outgoing_targets).await?;
-        info!("║     ✓ Completed in {:?}, routed to: {:?}", start.elapsed(), next_nodes);
-        return Ok((NodeOutput { content, next_nodes }, metrics));
-    }
-
-    let (content, metrics) = if node_type.requires_llm() {
-        execute_node_with_tools(model, prompt, input, tools, tool_registry).await?
-    } else {
-        (input.to_string(), ExecutionMetrics::default())
-    };
-
-    info!("║     ✓ Completed in {:?}", start.elapsed());
-
-    Ok((NodeOutput { content, next_nodes: vec![] }, metrics))
+/// Content and token usage accumulated while a stream is consumed.
+#[derive(Debug, Default)]
+struct StreamTally {
+    content: String,
+    input_tokens: u32,
+    output_tokens: u32,
+    /// The last error the stream yielded.
+    error: Option<String>,
 }
 
-/// Executes a Router node: LLM classifies input and returns the target node(s) with metrics.
-async fn execute_router(
-    model: &ModelConfig,
-    prompt: Option<&str>,
-    input: &str,
-    outgoing_targets: &[String],
-) -> Result<(String, Vec<String>, ExecutionMetrics), AgentError> {
-    let client = UnifiedLlmClient::new(&model.model, model.api_base.as_deref());
-
-    // Build routing prompt
-    let targets_list = outgoing_targets.join(", ");
-    let routing_prompt = format!(
-        "{}\n\nYou are a routing classifier. Based on the input, determine which target to route to.\n\
-        Available targets: [{}]\n\n\
-        IMPORTANT: Respond with ONLY the target name, nothing else. No explanation, no punctuation.",
-        prompt.unwrap_or("Classify the following input and route to the appropriate target."),
-        targets_list
-    );
-
-    let response = client.chat(&routing_prompt, input).await?;
-    let decision = response.content.trim().to_lowercase();
-
-    info!("║     Router decision: '{}'", decision);
-
-    // Match decision to available targets (case-insensitive, exact match only)
-    let matched = outgoing_targets
-        .iter()
-        .find(|t| t.to_lowercase() == decision)
-        .cloned();
-
-    // Fall back to first target if no match
-    let next_nodes = match matched {
-        Some(target) => vec![target],
-        None => {
-            warn!("║     ⚠ No exact match for '{}' in {:?}, defaulting to first", decision, outgoing_targets);
-            outgoing_targets.first().map(|t| vec![t.clone()]).unwrap_or_default()
-        }
-    };
-
-    let mut metrics = ExecutionMetrics::default();
-    metrics.accumulate(&response.metrics);
-    metrics.iteration_count = 1;
-
-    Ok((response.content, next_nodes, metrics))
+/// Wraps a stream so `on_complete` runs with the accumulated content, usage,
+/// and last error once the underlying stream is exhausted.
+fn observe_stream<F>(stream: LlmStream, on_complete: F) -> LlmStream
+where
+    F: FnOnce(StreamTally) + Send + 'static,
+{
+    let state = (stream, StreamTally::default(), Some(on_complete));
+    Box::pin(futures::stream::unfold(state, |(mut inner, mut tally, mut on_complete)| async move {
+        let Some(item) = inner.next().await else {
+            if let Some(f) = on_complete.take() {
+                f(tally);
+            }
+            return None;
+        };
+
+        match &item {
+            Ok(StreamChunk::Content(text)) => tally.content.push_str(text),
+            Ok(StreamChunk::Usage { input_tokens, output_tokens }) => {
+                // Providers may report input and output usage in separate chunks
+                if *input_tokens > 0 {
+                    tally.input_tokens = *input_tokens;
+                }
+                if *output_tokens > 0 {
+                    tally.output_tokens = *output_tokens;
+                }
+            }
+            Err(e) => tally.error = Some(e.to_string()),
+        }
+
+        Some((item, (inner, tally, on_complete)))
+    }))
 }
 
-/// Executes an LLM node, potentially with an agentic tool loop.
+/// Extracts a JSON value from LLM output, tolerating surrounding prose or code fences.
 ///
-/// If no tools are configured, performs a simple chat completion.
-/// With tools, runs an iterative loop:
-/// 1. Send message + tool schemas to LLM
-/// 2. If LLM returns tool calls, execute them via the registry
-/// 3. Send tool results back to LLM
-/// 4. Repeat until LLM returns final content (max 10 iterations)
-///
-/// Returns the content and accumulated execution metrics.
-async fn execute_node_with_tools(
-    model: &ModelConfig,
-    prompt: Option<&str>,
+/// Tries the whole text first, then the outermost `{...}` and `[...]` spans.
+fn extract_json(text: &str) -> Option<serde_json::Value> {
+    let trimmed = text.trim();
+    if let Ok(value) = serde_json::from_str(trimmed) {
+        return Some(value);
+    }
+    [('{', '}'), ('[', ']')].iter().find_map(|&(open, close)| {
+        let start = trimmed.find(open)?;
+        let end = trimmed.rfind(close)?;
+        if start >= end {
+            return None;
+        }
+        serde_json::from_str(&trimmed[start..=end]).ok()
+    })
+}
+
+/// Lists the values of a worker's subtasks in plan order, with the content of
+/// those without one, if any subtask produced a structured value.
+fn combined_value(outputs: &[NodeOutput]) -> Option<serde_json::Value> {
+    outputs.iter().any(|o| o.value.is_some()).then(|| {
+        outputs.iter()
+            .map(|o| o.value.clone().unwrap_or_else(|| serde_json::Value::String(o.content.clone())))
+            .collect()
+    })
+}
+
+/// Combines the verdicts of a worker's subtasks: they pass only if each one
+/// does, with the lowest score and every failing critique.
+fn combined_verdict(outputs: &[NodeOutput]) -> Option<Verdict> {
+    let verdicts: Vec<&Verdict> = outputs.iter().filter_map(|o| o.verdict.as_ref()).collect();
+    if verdicts.is_empty() {
+        return None;
+    }
+    Some(Verdict {
+        score: verdicts.iter().map(|v| v.score).fold(f64::INFINITY, f64::min),
+        pass: verdicts.iter().all(|v| v.pass),
+        critique: verdicts.iter()
+            .filter(|v| !v.pass && !v.critique.is_empty())
+            .map(|v| v.critique.as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    })
+}
+
+/// Adds up the usage of a worker's subtasks; the model is kept if they all used the same one.
+fn combined_usage(outputs: &[NodeOutput]) -> NodeUsage {
+    let usages: Vec<&NodeUsage> = outputs.iter().filter_map(|o| o.usage.as_ref()).collect();
+    let model = usages.first().and_then(|u| u.model.clone())
+        .filter(|model| usages.iter().all(|u| u.model.as_ref() == Some(model)));
+    NodeUsage {
+        model,
+        input_tokens: usages.iter().map(|u| u.input_tokens).sum(),
+        output_tokens: usages.iter().map(|u| u.output_tokens).sum(),
+        elapsed_ms: usages.iter().map(|u| u.elapsed_ms).max().unwrap_or(0),
+    }
+}
+
+/// Maximum number of tool call iterations to prevent infinite loops.
+const MAX_TOOL_ITERATIONS: usize = 10;
+
+/// Increments the shared step counter and returns the new step number.
+async fn next_step(step: &RwLock<usize>) -> usize {
+    let mut s = step.write().await;
+    *s += 1;
+    *s
+}
+
+/// Returns current time in milliseconds since UNIX epoch.
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Runs a nested pipeline; boxed because nested runs can contain pipeline nodes in turn.
+#[async_recursion]
+async fn run_nested(
+    engine: &PipelineEngine,
     input: &str,
-    tools: &[String],
-    tool_registry: &ToolRegistry,
-) -> Result<(String, ExecutionMetrics), AgentError> {
-    let client = UnifiedLlmClient::new(&model.model, model.api_base.as_deref());
-    let system_prompt = prompt.unwrap_or("");
-    let mut metrics = ExecutionMetrics::default();
-
-    // No tools configured - simple chat
-    if tools.is_empty() {
-        let response = client.chat(system_prompt, input).await?;
-        info!("║     ← Response: {} chars", response.content.len());
-        metrics.accumulate(&response.metrics);
-        metrics.iteration_count = 1;
-        return Ok((response.content, metrics));
-    }
-
-    // Get tool schemas for configured tools
-    let tool_schemas: Vec<ToolSchema> = tools
-        .iter()
-        .filter_map(|name| {
-            tool_registry.get(name).map(|t| ToolSchema {
-                name: t.name().to_string(),
-                description: t.description().to_string(),
-                parameters: t.parameters(),
-            })
-        })
-        .collect();
-
-    if tool_schemas.is_empty() {
-        warn!("║     ⚠ No valid tools found in registry for: {:?}", tools);
-        let response = client.chat(system_prompt, input).await?;
-        metrics.accumulate(&response.metrics);
-        metrics.iteration_count = 1;
-        return Ok((response.content, metrics));
-    }
-
-    info!("║     → Starting agentic loop with {} tools", tool_schemas.len());
-
-    // Agentic loop
-    let mut messages = vec![UnifiedLlmClient::user_message(input)?];
-    let mut pending_tool_calls: Option<Vec<ToolCall>> = None;
-
-    loop {
-        metrics.iteration_count += 1;
-        if metrics.iteration_count > MAX_TOOL_ITERATIONS as u32 {
-            warn!("║     ⚠ Max tool iterations ({}) reached", MAX_TOOL_ITERATIONS);
-            return Err(AgentError::LlmError(format!(
-                "Max tool iterations ({}) exceeded",
-                MAX_TOOL_ITERATIONS
-            )));
-        }
-
-        let response = client
-            .chat_with_tools(
-                system_prompt,
-                &messages,
-                &tool_schemas,
-                pending_tool_calls.as_deref(),
-            )
-            .await?;
+    history: &[fissio_core::Message],
+) -> Result<EngineOutput, AgentError> {
+    engine.execute_stream(input, history).await
+}
 
-        match response {
-            ChatResponse::Content(llm_response) => {
-                info!(
-                    "║     ← Final response: {} chars (after {} iterations)",
-                    llm_response.content.len(),
-                    metrics.iteration_count
-                );
-                metrics.accumulate(&llm_response.metrics);
-                return Ok((llm_response.content, metrics));
-            }
-            ChatResponse::ToolCalls { calls, metrics: llm_metrics } => {
-                metrics.accumulate(&llm_metrics);
-                info!(
-                    "║     ← Tool calls: {:?}",
-                    calls.iter().map(|c| &c.name).collect::<Vec<_>>()
-                );
+/// Runs a map node's body node on one item; boxed because the body can be a map node in turn.
+#[async_recursion]
+async fn run_map_body<'a>(
+    engine: &'a PipelineEngine,
+    body: &'a NodeConfig,
+    item: &'a str,
+    env: NodeEnv<'a>,
+) -> Result<NodeOutput, AgentError> {
+    engine.run_node(body, item, env).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fissio_config::{Condition, FailurePolicy, NodeBuilder};
+    use fissio_core::ErrorKind;
+    use std::sync::Mutex;
+
+    fn engine(config: PipelineConfig) -> PipelineEngine {
+        let model = ModelConfig {
+            id: "m".to_string(),
+            name: "M".to_string(),
+            model: "m".to_string(),
+            api_base: None,
+        };
+        PipelineEngine::new(config, vec![], model, HashMap::new())
+    }
+
+    #[test]
+    fn test_extract_json() {
+        assert_eq!(extract_json(r#"{"a": 1}"#), Some(serde_json::json!({"a": 1})));
+        assert_eq!(
+            extract_json("```json\n{\"pass\": true}\n```"),
+            Some(serde_json::json!({"pass": true}))
+        );
+        assert_eq!(extract_json("Result: [1, 2]"), Some(serde_json::json!([1, 2])));
+        assert_eq!(extract_json("no json"), None);
+    }
+
+    #[test]
+    fn test_get_loop_body() {
+        let config = PipelineConfig::builder("loop", "Loop")
+            .edge("input", "generator")
+            .edge("generator", "reviewer")
+            .edge("generator", "logger")
+            .edge("reviewer", "evaluator")
+            .edge("evaluator", "output")
+            .feedback_edge("evaluator", "generator", 2)
+            .build();
+        let engine = engine(config);
+
+        let body = engine.get_loop_body("generator", "evaluator");
+        let expected: HashSet<String> = ["generator", "reviewer", "evaluator"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(body, expected);
+
+        // Feedback edges are not forward paths
+        assert!(engine.get_loop_body("evaluator", "generator").is_empty());
+    }
+
+    struct Shout;
+
+    #[async_trait::async_trait]
+    impl NodeExecutor for Shout {
+        async fn execute(&self, ctx: &NodeContext<'_>) -> Result<NodeOutput, AgentError> {
+            let suffix = ctx.node.config["suffix"].as_str().unwrap_or_default();
+            Ok(NodeOutput::new(format!("{}{}", ctx.input.to_uppercase(), suffix)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_node_executor() {
+        let config = PipelineConfig::builder("custom", "Custom")
+            .node("shout", NodeType::Custom)
+                .config(serde_json::json!({ "executor": "shout", "suffix": "!" }))
+                .done()
+            .edge("input", "shout")
+            .edge("shout", "output")
+            .build();
+
+        let unregistered = engine(config.clone());
+        assert!(unregistered.validate().unwrap_err().to_string().contains("unregistered executor 'shout'"));
+        assert!(unregistered.execute_stream("hello", &[]).await.is_err());
+
+        let mut nodes = NodeRegistry::new();
+        nodes.register("shout", Shout);
+        let engine = engine(config).with_nodes(nodes);
+        assert!(engine.validate().is_ok());
+        match engine.execute_stream("hello", &[]).await.unwrap() {
+            EngineOutput::Complete(text) => assert_eq!(text, "HELLO!"),
+            _ => panic!("expected a complete output"),
+        }
+    }
+
+    /// Fails with an LLM error whenever it runs on the model with ID `broken`.
+    struct Flaky {
+        calls: Arc<Mutex<u32>>,
+    }
+
+    #[async_trait::async_trait]
+    impl NodeExecutor for Flaky {
+        async fn execute(&self, ctx: &NodeContext<'_>) -> Result<NodeOutput, AgentError> {
+            let mut calls = self.calls.lock().unwrap();
+            *calls += 1;
+            if ctx.model.id == "broken" {
+                return Err(AgentError::LlmError("overloaded".into()));
+            }
+            Ok(NodeOutput::new(format!("{} via {}", ctx.input, ctx.model.id)))
+        }
+    }
+
+    fn flaky_engine(node: impl FnOnce(NodeBuilder) -> NodeBuilder) -> (PipelineEngine, Arc<Mutex<u32>>) {
+        let config = node(PipelineConfig::builder("retry", "Retry").node("flaky", NodeType::Custom))
+            .config(serde_json::json!({ "executor": "flaky" }))
+            .done()
+            .edge("input", "flaky")
+            .edge("flaky", "output")
+            .build();
+        let model = |id: &str| ModelConfig {
+            id: id.to_string(),
+            name: id.to_string(),
+            model: id.to_string(),
+            api_base: None,
+        };
+
+        let calls = Arc::new(Mutex::new(0));
+        let mut nodes = NodeRegistry::new();
+        nodes.register("flaky", Flaky { calls: Arc::clone(&calls) });
+        let engine = PipelineEngine::new(config, vec![model("backup")], model("broken"), HashMap::new()).with_nodes(nodes);
+        (engine, calls)
+    }
+
+    fn fast_retries(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy { max_attempts, backoff_ms: 1, ..RetryPolicy::default() }
+    }
+
+    #[tokio::test]
+    async fn test_retry_with_backoff() {
+        let (engine, calls) = flaky_engine(|n| n.retry(fast_retries(3)).fallback_models(["backup"]));
+        let (tx, mut rx) = event_channel();
+        let engine = engine.with_events(tx);
+
+        // The default model always fails; after three tries the fallback answers
+        match engine.execute_stream("hi", &[]).await.unwrap() {
+            EngineOutput::Complete(text) => assert_eq!(text, "hi via backup"),
+            _ => panic!("expected a complete output"),
+        }
+        assert_eq!(*calls.lock().unwrap(), 4);
+
+        let mut retries = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if let EngineEvent::NodeRetry { attempt, model, delay_ms, .. } = event {
+                retries.push((attempt, model, delay_ms));
+            }
+        }
+        assert_eq!(
+            retries,
+            vec![(1, "broken".to_string(), 1), (2, "broken".to_string(), 2), (3, "backup".to_string(), 0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_exhaustion_and_defaults() {
+        // Without a policy a node gets a single attempt and keeps its error
+        let (engine, calls) = flaky_engine(|n| n);
+        assert!(matches!(engine.execute_stream("hi", &[]).await, Err(AgentError::LlmError(_))));
+        assert_eq!(*calls.lock().unwrap(), 1);
+
+        let (engine, calls) = flaky_engine(|n| n.retry(fast_retries(2)));
+        match engine.execute_stream("hi", &[]).await {
+            Err(AgentError::MaxRetriesExceeded { attempts, last }) => {
+                assert_eq!(attempts, 2);
+                assert!(matches!(*last, AgentError::LlmError(_)));
+            }
+            _ => panic!("expected max retries exceeded"),
+        }
+        assert_eq!(*calls.lock().unwrap(), 2);
+
+        // Errors outside `retry_on` fail on the first attempt
+        let policy = RetryPolicy { retry_on: vec![ErrorKind::ExternalApi], ..fast_retries(3) };
+        let (engine, calls) = flaky_engine(|n| n.retry(policy));
+        assert!(matches!(engine.execute_stream("hi", &[]).await, Err(AgentError::LlmError(_))));
+        assert_eq!(*calls.lock().unwrap(), 1);
+    }
+
+    /// Sleeps for `config.sleep_ms` before echoing its input.
+    struct Sleepy;
+
+    #[async_trait::async_trait]
+    impl NodeExecutor for Sleepy {
+        async fn execute(&self, ctx: &NodeContext<'_>) -> Result<NodeOutput, AgentError> {
+            let ms = ctx.node.config["sleep_ms"].as_u64().unwrap_or_default();
+            tokio::time::sleep(Duration::from_millis(ms)).await;
+            Ok(NodeOutput::new(ctx.input))
+        }
+    }
+
+    fn sleepy_engine(sleep_ms: u64, node_timeout_ms: Option<u64>, pipeline_timeout_ms: Option<u64>) -> PipelineEngine {
+        let node = PipelineConfig::builder("sleepy", "Sleepy")
+            .node("sleepy", NodeType::Custom)
+            .config(serde_json::json!({ "executor": "sleepy", "sleep_ms": sleep_ms }));
+        let node = match node_timeout_ms {
+            Some(ms) => node.timeout_ms(ms),
+            None => node,
+        };
+        let pipeline = node.done().edge("input", "sleepy").edge("sleepy", "output");
+        let pipeline = match pipeline_timeout_ms {
+            Some(ms) => pipeline.timeout_ms(ms),
+            None => pipeline,
+        };
+
+        let mut nodes = NodeRegistry::new();
+        nodes.register("sleepy", Sleepy);
+        engine(pipeline.build()).with_nodes(nodes)
+    }
+
+    #[tokio::test]
+    async fn test_timeouts() {
+        let result = sleepy_engine(5_000, Some(10), None).execute_stream("hi", &[]).await;
+        assert!(matches!(result, Err(AgentError::Timeout(m)) if m == "node 'sleepy' exceeded 10ms"));
+
+        let result = sleepy_engine(5_000, None, Some(10)).execute_stream("hi", &[]).await;
+        assert!(matches!(result, Err(AgentError::Timeout(m)) if m == "pipeline 'sleepy' exceeded 10ms"));
+
+        let result = sleepy_engine(1, Some(5_000), Some(5_000)).execute_stream("hi", &[]).await;
+        assert!(matches!(result, Ok(EngineOutput::Complete(text)) if text == "hi"));
+
+        // Waiting for a rate-limit slot counts against the node's timeout
+        let limit = fissio_config::RateLimit { max_concurrency: Some(1), tokens_per_minute: None };
+        let limits = fissio_config::RateLimits { models: HashMap::from([("m".to_string(), limit)]), ..Default::default() };
+        let limiter = Arc::new(RateLimiter::new(&limits));
+        let busy = sleepy_engine(300, None, None).with_rate_limiter(Arc::clone(&limiter));
+        let queued = sleepy_engine(1, Some(20), None).with_rate_limiter(limiter);
+        let (_, result) = tokio::join!(busy.execute_stream("hi", &[]), async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            queued.execute_stream("hi", &[]).await
+        });
+        assert!(matches!(result, Err(AgentError::Timeout(m)) if m == "node 'sleepy' exceeded 20ms"));
+    }
+
+    #[tokio::test]
+    async fn test_cancellation() {
+        let (tx, mut rx) = event_channel();
+        let engine = sleepy_engine(5_000, None, None).with_events(tx);
+        let cancel = CancellationToken::new();
+
+        let run = engine.execute_stream_with_cancel("hi", &[], cancel.clone());
+        let cancel_soon = async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            cancel.cancel();
+        };
+        let (result, ()) = tokio::join!(run, cancel_soon);
+        assert!(matches!(result, Err(AgentError::Cancelled)));
+
+        let mut stopped = false;
+        while let Ok(event) = rx.try_recv() {
+            stopped |= matches!(event, EngineEvent::Error { node_id: None, .. });
+        }
+        assert!(stopped);
+    }
+
+    struct Unreachable;
+
+    #[async_trait::async_trait]
+    impl NodeExecutor for Unreachable {
+        async fn execute(&self, _ctx: &NodeContext<'_>) -> Result<NodeOutput, AgentError> {
+            Err(AgentError::ExternalApi("connection refused".into()))
+        }
+    }
+
+    fn fan_out_engine(policy: FailurePolicy) -> PipelineEngine {
+        let custom = |executor: &str| serde_json::json!({ "executor": executor });
+        let config = PipelineConfig::builder("fan-out", "Fan-out")
+            .node("web", NodeType::Custom).config(custom("shout")).done()
+            .node("papers", NodeType::Custom).config(custom("unreachable")).done()
+            .node("notes", NodeType::Custom).config(custom("shout")).done()
+            .node("merge", NodeType::Aggregator).config(serde_json::json!({ "strategy": "concat" })).done()
+            .parallel_edge_with_policy("input", &["web", "papers", "notes"], policy)
+            .edge("web", "merge")
+            .edge("papers", "merge")
+            .edge("notes", "merge")
+            .edge("merge", "output")
+            .build();
+
+        let mut nodes = NodeRegistry::new();
+        nodes.register("shout", Shout);
+        nodes.register("unreachable", Unreachable);
+        engine(config).with_nodes(nodes)
+    }
+
+    #[tokio::test]
+    async fn test_parallel_failure_policies() {
+        let result = fan_out_engine(FailurePolicy::FailFast).execute_stream("q", &[]).await;
+        assert!(matches!(result, Err(AgentError::ExternalApi(_))));
+
+        match fan_out_engine(FailurePolicy::Continue).execute_stream("q", &[]).await.unwrap() {
+            EngineOutput::Complete(text) => assert_eq!(
+                text,
+                "## web\nQ\n\n## notes\nQ\n\n## papers (failed)\nExternal API error: connection refused"
+            ),
+            _ => panic!("expected a complete output"),
+        }
+
+        assert!(fan_out_engine(FailurePolicy::Quorum(2)).execute_stream("q", &[]).await.is_ok());
+        let result = fan_out_engine(FailurePolicy::Quorum(3)).execute_stream("q", &[]).await;
+        assert!(matches!(result, Err(AgentError::WorkerFailed(m)) if m.starts_with("quorum not met: 2 of 3")));
+    }
+
+    /// Tracks the peak number of executions running at once.
+    #[derive(Clone, Default)]
+    struct Gauge {
+        running: Arc<std::sync::atomic::AtomicUsize>,
+        peak: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl NodeExecutor for Gauge {
+        async fn execute(&self, ctx: &NodeContext<'_>) -> Result<NodeOutput, AgentError> {
+            use std::sync::atomic::Ordering;
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            Ok(NodeOutput::new(ctx.input))
+        }
+    }
+
+    async fn peak_concurrency(max_concurrency: Option<usize>, rate_limiter: Option<RateLimiter>) -> usize {
+        let gauge = serde_json::json!({ "executor": "gauge" });
+        let mut builder = PipelineConfig::builder("wide", "Wide");
+        for id in ["a", "b", "c", "d"] {
+            builder = builder.node(id, NodeType::Custom).config(gauge.clone()).done();
+        }
+        if let Some(n) = max_concurrency {
+            builder = builder.max_concurrency(n);
+        }
+        let config = builder.parallel_edge("input", &["a", "b", "c", "d"]).build();
+
+        let probe = Gauge::default();
+        let mut nodes = NodeRegistry::new();
+        nodes.register("gauge", probe.clone());
+        let mut engine = engine(config).with_nodes(nodes);
+        if let Some(rate_limiter) = rate_limiter {
+            engine = engine.with_rate_limiter(Arc::new(rate_limiter));
+        }
+        engine.execute_stream("q", &[]).await.unwrap();
+        probe.peak.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limits() {
+        assert_eq!(peak_concurrency(None, None).await, 4);
+        assert_eq!(peak_concurrency(Some(2), None).await, 2);
 
-                for call in &calls {
-                    let tool = tool_registry.get(&call.name).ok_or_else(|| {
-                        AgentError::LlmError(format!("Tool not found: {}", call.name))
-                    })?;
+        let limit = fissio_config::RateLimit { max_concurrency: Some(1), tokens_per_minute: None };
+        let limits = fissio_config::RateLimits { models: HashMap::from([("m".to_string(), limit)]), ..Default::default() };
+        assert_eq!(peak_concurrency(Some(3), Some(RateLimiter::new(&limits))).await, 1);
+    }
 
-                    info!("║       → Executing tool: {}", call.name);
-                    let result = tool.execute(call.arguments.clone()).await.map_err(|e| {
-                        AgentError::LlmError(format!("Tool execution failed: {}", e))
-                    })?;
+    /// Counts its executions and passes its input through with a suffix.
+    #[derive(Clone, Default)]
+    struct Counted(Arc<std::sync::atomic::AtomicUsize>);
+
+    #[async_trait::async_trait]
+    impl NodeExecutor for Counted {
+        async fn execute(&self, ctx: &NodeContext<'_>) -> Result<NodeOutput, AgentError> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(NodeOutput::new(format!("{}!", ctx.input)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_checkpoint() {
+        let custom = |executor: &str| serde_json::json!({ "executor": executor });
+        let pipeline = |second: &str| PipelineConfig::builder("resumable", "Resumable")
+            .node("first", NodeType::Custom).config(custom("counted")).done()
+            .node("second", NodeType::Custom).config(custom(second)).done()
+            .edge("input", "first")
+            .edge("first", "second")
+            .edge("second", "output")
+            .build();
+        let counted = Counted::default();
+        let nodes = || {
+            let mut nodes = NodeRegistry::new();
+            nodes.register("counted", counted.clone());
+            nodes.register("shout", Shout);
+            nodes.register("unreachable", Unreachable);
+            nodes
+        };
+        let store: Arc<dyn CheckpointStore> = Arc::new(fissio_monitor::TraceStore::in_memory().unwrap());
+
+        let original = engine(pipeline("unreachable")).with_nodes(nodes()).with_checkpoints(Arc::clone(&store), "run-1");
+        assert!(original.execute_stream("hi", &[]).await.is_err());
+        let checkpoint = Checkpoint::load(store.as_ref(), "run-1").unwrap().unwrap();
+        assert_eq!(checkpoint.executed, ["first"]);
+        assert_eq!(checkpoint.context.text("first").unwrap(), "hi!");
+
+        // Resume with the failing node fixed; the first node isn't run again
+        let (tx, mut rx) = event_channel();
+        let resumed = engine(pipeline("shout"))
+            .with_nodes(nodes())
+            .with_checkpoints(Arc::clone(&store), "run-2")
+            .with_events(tx);
+        match resumed.resume("run-1", CancellationToken::new()).await.unwrap() {
+            EngineOutput::Complete(text) => assert_eq!(text, "HI!"),
+            _ => panic!("expected a complete output"),
+        }
+        assert_eq!(counted.0.load(std::sync::atomic::Ordering::SeqCst), 1);
+        drop(resumed);
+        let mut restored = Vec::new();
+        while let Some(event) = rx.recv().await {
+            if let EngineEvent::NodeRestored { node_id } = event {
+                restored.push(node_id);
+            }
+        }
+        assert_eq!(restored, ["first"]);
+        assert_eq!(Checkpoint::load(store.as_ref(), "run-2").unwrap().unwrap().executed, ["first", "second"]);
+
+        let result = engine(pipeline("shout")).resume("run-1", CancellationToken::new()).await;
+        assert!(matches!(result, Err(AgentError::Checkpoint(m)) if m == "no checkpoint store attached"));
+        let unknown = engine(pipeline("shout")).with_checkpoints(Arc::clone(&store), "run-3");
+        let result = unknown.resume("run-9", CancellationToken::new()).await;
+        assert!(matches!(result, Err(AgentError::Checkpoint(m)) if m == "no checkpoint for run 'run-9'"));
+    }
 
-                    info!("║       ← Tool result: {} chars", result.len());
-                    metrics.tool_call_count += 1;
+    #[tokio::test]
+    async fn test_approval_suspends_and_continues() {
+        let custom = |executor: &str| serde_json::json!({ "executor": executor });
+        let config = PipelineConfig::builder("reviewed", "Reviewed")
+            .node("draft", NodeType::Custom).config(custom("counted")).done()
+            .node("review", NodeType::Approval).prompt("Check the draft.")
+                .config(serde_json::json!({ "reject_target": "redraft" })).done()
+            .node("send", NodeType::Custom).config(custom("shout")).done()
+            .node("redraft", NodeType::Custom).config(custom("shout")).done()
+            .edge("input", "draft")
+            .edge("draft", "review")
+            .edge("review", "send")
+            .edge("review", "redraft")
+            .edge("send", "output")
+            .edge("redraft", "output")
+            .build();
+        let counted = Counted::default();
+        let store: Arc<dyn CheckpointStore> = Arc::new(fissio_monitor::TraceStore::in_memory().unwrap());
+        let nodes = || {
+            let mut nodes = NodeRegistry::new();
+            nodes.register("counted", counted.clone());
+            nodes.register("shout", Shout);
+            nodes
+        };
+        let run = |run_id: &str| engine(config.clone()).with_nodes(nodes()).with_checkpoints(Arc::clone(&store), run_id);
+        let complete = |output: EngineOutput| match output {
+            EngineOutput::Complete(text) => text,
+            _ => panic!("expected a complete output"),
+        };
+
+        let unsaved = engine(config.clone()).with_nodes(nodes());
+        assert!(unsaved.validate().unwrap_err().to_string().contains("needs a checkpoint store"));
+        let mut mistyped = config.clone();
+        mistyped.nodes[1].config = serde_json::json!({ "reject_target": "redraf" });
+        let err = engine(mistyped).with_nodes(nodes()).with_checkpoints(Arc::clone(&store), "run-0").validate().unwrap_err();
+        assert!(err.to_string().contains("rejects to 'redraf', which isn't one of its targets"), "{}", err);
+
+        let (tx, mut rx) = event_channel();
+        let suspended = match run("run-1").with_events(tx).execute_stream("hi", &[]).await.unwrap() {
+            EngineOutput::Suspended(approvals) => approvals,
+            _ => panic!("expected the run to suspend"),
+        };
+        assert_eq!(suspended.len(), 1);
+        assert_eq!((suspended[0].node_id.as_str(), suspended[0].content.as_str()), ("review", "hi!"));
+        assert_eq!(suspended[0].instructions.as_deref(), Some("Check the draft."));
+        let mut requested = Vec::new();
+        while let Some(event) = rx.recv().await {
+            if let EngineEvent::ApprovalRequested { run_id, node_id, .. } = event {
+                requested.push((run_id, node_id));
+            }
+        }
+        assert_eq!(requested, [("run-1".to_string(), "review".to_string())]);
+
+        // The edited draft is sent without drafting again
+        let edit = Decision::Edit { content: "hello!".into() };
+        let output = run("run-1").decide("review", edit, CancellationToken::new()).await.unwrap();
+        assert_eq!(complete(output), "HELLO!");
+        assert_eq!(counted.0.load(std::sync::atomic::Ordering::SeqCst), 1);
+        let result = run("run-1").decide("review", Decision::Approve, CancellationToken::new()).await;
+        assert!(matches!(result, Err(AgentError::Checkpoint(m)) if m.contains("not awaiting approval")));
+
+        // A rejection follows only the edge to the reject target
+        assert!(matches!(run("run-2").execute_stream("hi", &[]).await.unwrap(), EngineOutput::Suspended(_)));
+        let reject = Decision::Reject { reason: Some("too short".into()) };
+        let output = run("run-2").decide("review", reject, CancellationToken::new()).await.unwrap();
+        assert_eq!(complete(output), "REJECTED AT REVIEW: TOO SHORT");
+    }
 
-                    // Add tool result to messages
-                    messages.push(UnifiedLlmClient::tool_result_message(&call.id, &result)?);
+    #[tokio::test]
+    async fn test_replay_recorded_calls() {
+        let pipeline = |polish: &str| PipelineConfig::builder("replayed", "Replayed")
+            .node("draft", NodeType::Llm).prompt("Draft a reply.").done()
+            .node("polish", NodeType::Llm).prompt(polish).done()
+            .edge("input", "draft")
+            .edge("draft", "polish")
+            .edge("polish", "output")
+            .build();
+        let call = |node_id: &str, system: &str, input: &str, content: &str| fissio_monitor::CallRecord {
+            run_id: "run-1".into(),
+            node_id: node_id.into(),
+            kind: fissio_monitor::CallKind::Llm,
+            request: serde_json::json!({ "model": "m", "system": system, "history": [], "input": input }),
+            response: serde_json::json!({ "content": content, "metrics": { "input_tokens": 5, "output_tokens": 2, "elapsed_ms": 9 } }),
+            timestamp: 0,
+        };
+        let recorded = vec![call("draft", "Draft a reply.", "hi", "Hello there"), call("polish", "Polish it.", "Hello there", "Hello there!")];
+        let store = Arc::new(fissio_monitor::TraceStore::in_memory().unwrap());
+
+        // The streamed output is served from the recording and recorded again
+        let replayed = engine(pipeline("Polish it."))
+            .with_replay(Replay::new(recorded.clone()))
+            .with_recording(store.clone(), "run-2");
+        let EngineOutput::Stream(stream) = replayed.execute_stream("hi", &[]).await.unwrap() else {
+            panic!("expected a streamed output");
+        };
+        let content: String = stream
+            .filter_map(|chunk| async move {
+                match chunk.unwrap() {
+                    StreamChunk::Content(text) => Some(text),
+                    StreamChunk::Usage { .. } => None,
                 }
+            })
+            .collect()
+            .await;
+        assert_eq!(content, "Hello there!");
+        let rerecorded = store.load_calls("run-2").unwrap();
+        assert_eq!(rerecorded.iter().map(|c| c.node_id.as_str()).collect::<Vec<_>>(), ["draft", "polish"]);
+
+        // An edited prompt has no recording to replay
+        let edited = engine(pipeline("Polish it harder.")).with_replay(Replay::new(recorded));
+        let result = edited.execute_stream("hi", &[]).await;
+        assert!(matches!(result, Err(AgentError::Replay(m)) if m.contains("node 'polish'")));
+    }
+
+    /// Looks up an order's status.
+    struct Lookup;
+
+    #[async_trait::async_trait]
+    impl fissio_tools::Tool for Lookup {
+        fn name(&self) -> &str {
+            "lookup"
+        }
+
+        fn description(&self) -> &str {
+            "Looks up an order"
+        }
+
+        fn parameters(&self) -> serde_json::Value {
+            serde_json::json!({ "type": "object", "properties": { "order": { "type": "integer" } } })
+        }
+
+        async fn execute(&self, args: serde_json::Value) -> Result<String, fissio_tools::ToolError> {
+            Ok(format!("order {} shipped", args["order"]))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_routing_and_tool_loop() {
+        let config = PipelineConfig::builder("support", "Support")
+            .node("classify", NodeType::Router).prompt("Route the request.").done()
+            .node("billing", NodeType::Worker).tools(["lookup"]).done()
+            .node("general", NodeType::Llm).done()
+            .edge("input", "classify")
+            .conditional_edge("classify", &["billing", "general"])
+            .edge("billing", "output")
+            .edge("general", "output")
+            .build();
+        let llm = Arc::new(
+            MockLlm::builder()
+                .node("classify").reply("billing").done()
+                .node("billing").call_tool("lookup", serde_json::json!({ "order": 12 })).reply("It shipped.").done()
+                .build(),
+        );
+        let mut tools = ToolRegistry::new();
+        tools.register(Lookup);
+        let model = ModelConfig { id: "m".into(), name: "M".into(), model: "m".into(), api_base: None };
+        let engine = PipelineEngine::with_tools(config, vec![], model, HashMap::new(), tools).with_provider(llm.clone());
+
+        match engine.execute_stream("Where is order 12?", &[]).await.unwrap() {
+            EngineOutput::Complete(text) => assert_eq!(text, "It shipped."),
+            _ => panic!("expected a complete output"),
+        }
+        let billing = llm.requests_for("billing");
+        assert_eq!(billing.len(), 2);
+        assert_eq!(billing[0].tools, ["lookup"]);
+        assert!(llm.requests_for("general").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_mock_fan_in() {
+        let config = PipelineConfig::builder("fan-in", "Fan-in")
+            .node("web", NodeType::Llm).prompt("Search the web.").done()
+            .node("notes", NodeType::Llm).prompt("Search the notes.").done()
+            .node("merge", NodeType::Aggregator).config(serde_json::json!({ "strategy": "concat" })).done()
+            .parallel_edge("input", &["web", "notes"])
+            .edge("web", "merge")
+            .edge("notes", "merge")
+            .edge("merge", "output")
+            .build();
+        let run = |llm: MockLlm| engine(config.clone()).with_provider(Arc::new(llm));
+
+        // The slower branch still lands in its own section
+        let llm = MockLlm::builder()
+            .node("web").delay(Duration::from_millis(20)).reply("W").done()
+            .prompt("notes").reply("N").done()
+            .build();
+        match run(llm).execute_stream("q", &[]).await.unwrap() {
+            EngineOutput::Complete(text) => assert_eq!(text, "## web\nW\n\n## notes\nN"),
+            _ => panic!("expected a complete output"),
+        }
+
+        let llm = MockLlm::builder()
+            .node("web").reply("W").done()
+            .node("notes").fail(|| AgentError::ExternalApi("connection refused".into())).done()
+            .build();
+        assert!(matches!(run(llm).execute_stream("q", &[]).await, Err(AgentError::ExternalApi(_))));
+    }
+
+    #[tokio::test]
+    async fn test_dependency_scheduling() {
+        let config = PipelineConfig::builder("join", "Join")
+            .node("outline", NodeType::Llm).done()
+            .node("research", NodeType::Llm).done()
+            .node("summarize", NodeType::Llm).done()
+            .node("write", NodeType::Llm).done()
+            .edge("input", "outline")
+            .edge("input", "research")
+            .edge("research", "summarize")
+            .edge("outline", "write")
+            .edge("summarize", "write")
+            .edge("write", "output")
+            .build();
+        let llm = Arc::new(
+            MockLlm::builder()
+                .node("outline").delay(Duration::from_millis(100)).reply("Outline.").done()
+                .node("research").delay(Duration::from_millis(40)).reply("Research.").done()
+                .node("summarize").delay(Duration::from_millis(40)).reply("Summary.").done()
+                .any().reply("Article.").done()
+                .build(),
+        );
+
+        let (tx, mut rx) = event_channel();
+        let engine = engine(config).with_provider(llm.clone()).with_events(tx);
+        let run = engine.execute_with_context("q", &[], CancellationToken::new()).await.unwrap();
+        let mut order = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            match event {
+                EngineEvent::NodeStarted { node_id, .. } => order.push(format!("start {}", node_id)),
+                EngineEvent::NodeFinished { node_id, .. } => order.push(format!("finish {}", node_id)),
+                _ => {}
+            }
+        }
+        let position = |entry: &str| order.iter().position(|e| e == entry).unwrap();
+
+        // Both branches run while outline does, and write waits for the longer one
+        assert!(position("start research") < position("finish outline"));
+        assert!(position("start write") > position("finish outline"));
+        let write = llm.requests_for("write");
+        assert_eq!(write.len(), 1);
+        assert!(write[0].input.contains("Outline.") && write[0].input.contains("Summary."));
+        assert_eq!(run.context.text("write").as_deref(), Some("Article."));
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_node_runs_nested() {
+        let research = PipelineConfig::builder("research", "Research")
+            .node("search", NodeType::Llm).prompt("Search.").observe().done()
+            .node("summarize", NodeType::Llm).prompt("Summarize.").observe().done()
+            .edge("input", "search")
+            .edge("search", "summarize")
+            .edge("summarize", "output")
+            .build();
+        let config = PipelineConfig::builder("answer", "Answer")
+            .variable("audience", "kids")
+            .node("research", NodeType::Pipeline)
+                .config(serde_json::json!({ "pipeline": "research", "input": "For {{vars.audience}}: {{input}}" }))
+                .observe()
+                .done()
+            .node("write", NodeType::Llm).prompt("Write.").done()
+            .edge("input", "research")
+            .edge("research", "write")
+            .edge("write", "output")
+            .build();
+        let mut pipelines = PresetRegistry::new();
+        pipelines.register(research);
+        let llm = Arc::new(
+            MockLlm::builder()
+                .node("search").reply("found").done()
+                .node("summarize").reply("summary").done()
+                .node("write").reply("answer").done()
+                .build(),
+        );
+        let store = Arc::new(fissio_monitor::TraceStore::in_memory().unwrap());
+        let collector = Arc::new(fissio_monitor::TracingCollector::new(store.clone(), "answer", "Answer", "why?"));
+        let model = |id: &str| ModelConfig { id: id.into(), name: id.into(), model: id.into(), api_base: None };
+        let overrides = HashMap::from([("research/summarize".to_string(), "big".to_string())]);
+        let (tx, mut rx) = event_channel();
+        let answer = PipelineEngine::new(config, vec![model("big")], model("m"), overrides)
+            .with_pipelines(Arc::new(pipelines))
+            .with_provider(llm.clone())
+            .with_collector(collector.clone())
+            .with_events(tx);
+
+        let EngineOutput::Stream(stream) = answer.execute_stream("why?", &[]).await.unwrap() else {
+            panic!("expected a streamed output");
+        };
+        assert_eq!(subpipeline::collect_output("answer", EngineOutput::Stream(stream)).await.unwrap(), "answer");
+        assert_eq!(llm.requests_for("search")[0].input, "For kids: why?");
+        assert_eq!(llm.requests_for("write")[0].input, "summary");
+        // The UI's model choice for a nested node applies to it alone
+        assert_eq!(llm.requests_for("summarize")[0].model, "big");
+        assert_eq!(llm.requests_for("search")[0].model, "m");
+
+        // Nested nodes report their progress under the pipeline node's ID
+        let mut started = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if let EngineEvent::NodeStarted { node_id, .. } = event {
+                started.push(node_id);
+            }
+        }
+        assert_eq!(started, ["research", "research/search", "research/summarize", "write"]);
+
+        let spans = store.get_spans(collector.trace_id()).unwrap();
+        let nodes: Vec<&str> = spans.iter().map(|s| s.node_id.as_str()).collect();
+        assert!(nodes.contains(&"research/search") && nodes.contains(&"research/summarize"), "{:?}", nodes);
+        assert!(nodes.contains(&"research"), "{:?}", nodes);
+
+        // A pipeline can't run itself
+        let looping = PipelineConfig::builder("loop", "Loop")
+            .node("again", NodeType::Pipeline).config(serde_json::json!({ "pipeline": "loop" })).done()
+            .edge("input", "again")
+            .edge("again", "output")
+            .build();
+        let mut pipelines = PresetRegistry::new();
+        pipelines.register(looping.clone());
+        let looping = engine(looping).with_pipelines(Arc::new(pipelines));
+        assert!(looping.validate().unwrap_err().to_string().contains("creates a cycle: loop -> loop"));
+    }
 
-                // Store tool calls for next iteration (needed for Anthropic message format)
-                pending_tool_calls = Some(calls);
+    #[tokio::test]
+    async fn test_map_node_runs_body_per_item() {
+        let config = PipelineConfig::builder("expand", "Expand")
+            .node("plan", NodeType::Llm).prompt("Plan.").done()
+            .node("each", NodeType::Map).config(serde_json::json!({ "node": "expand", "max_concurrency": 2 })).done()
+            .node("expand", NodeType::Llm).prompt("Expand.").done()
+            .edge("input", "plan")
+            .edge("plan", "each")
+            .edge("each", "output")
+            .build();
+        let delay = Duration::from_millis(40);
+        let llm = Arc::new(
+            MockLlm::builder()
+                .node("plan").reply(r#"Topics: ["alpha", "beta", "gamma", "delta"]"#).done()
+                .prompt("^alpha$").delay(delay * 4).reply("A").done()
+                .prompt("^beta$").delay(delay).reply("B").done()
+                .prompt("^gamma$").delay(delay).reply("C").done()
+                .prompt("^delta$").delay(delay).reply("D").done()
+                .build(),
+        );
+
+        let (tx, mut rx) = event_channel();
+        let output = engine(config).with_provider(llm.clone()).with_events(tx).execute_stream("go", &[]).await.unwrap();
+        assert_eq!(subpipeline::collect_output("expand", output).await.unwrap(), r#"["A","B","C","D"]"#);
+        assert_eq!(llm.requests_for("expand").len(), 4);
+        // Two at a time: alpha runs alongside beta, then gamma, then delta,
+        // so each item after the first starts with exactly one other running
+        let mut running = 0;
+        let mut started_with = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            match event {
+                EngineEvent::NodeStarted { node_id, .. } if node_id == "expand" => {
+                    started_with.push(running);
+                    running += 1;
+                }
+                EngineEvent::NodeFinished { node_id, .. } if node_id == "expand" => running -= 1,
+                _ => {}
             }
         }
+        assert_eq!(started_with, [0, 1, 1, 1]);
+
+        // A pipeline body runs nested once per line, its spans under the item's index
+        let shout = PipelineConfig::builder("shout", "Shout")
+            .node("upper", NodeType::Llm).prompt("Shout.").observe().done()
+            .edge("input", "upper")
+            .edge("upper", "output")
+            .build();
+        let config = PipelineConfig::builder("lines", "Lines")
+            .node("each", NodeType::Map).config(serde_json::json!({ "pipeline": "shout", "split": "lines" })).done()
+            .edge("input", "each")
+            .edge("each", "output")
+            .build();
+        let mut pipelines = PresetRegistry::new();
+        pipelines.register(shout);
+        let llm = Arc::new(
+            MockLlm::builder()
+                .prompt("^hi$").reply("HI").done()
+                .prompt("^there$").reply("THERE").done()
+                .build(),
+        );
+        let store = Arc::new(fissio_monitor::TraceStore::in_memory().unwrap());
+        let collector = Arc::new(fissio_monitor::TracingCollector::new(store.clone(), "lines", "Lines", "hi"));
+        let lines = engine(config)
+            .with_pipelines(Arc::new(pipelines))
+            .with_provider(llm)
+            .with_collector(collector.clone());
+
+        let output = lines.execute_stream("hi\n\nthere\n", &[]).await.unwrap();
+        assert_eq!(subpipeline::collect_output("lines", output).await.unwrap(), r#"["HI","THERE"]"#);
+        let spans = store.get_spans(collector.trace_id()).unwrap();
+        let nodes: Vec<&str> = spans.iter().map(|s| s.node_id.as_str()).collect();
+        assert!(nodes.contains(&"each[0]/upper") && nodes.contains(&"each[1]/upper"), "{:?}", nodes);
+    }
+
+    #[tokio::test]
+    async fn test_structured_output() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": { "team": { "enum": ["billing", "tech"] }, "urgent": { "type": "boolean" } },
+            "required": ["team", "urgent"]
+        });
+        let config = PipelineConfig::builder("triage", "Triage")
+            .node("triage", NodeType::Llm).prompt("Triage the ticket.").output_schema(schema.clone()).done()
+            .node("route", NodeType::Router).config(serde_json::json!({ "field": "team" })).done()
+            .node("billing", NodeType::Llm).prompt("Urgent: {{nodes.triage.urgent}}").done()
+            .node("tech", NodeType::Llm).done()
+            .edge("input", "triage")
+            .edge("triage", "route")
+            .conditional_edge("route", &["billing", "tech"])
+            .edge("billing", "output")
+            .edge("tech", "output")
+            .build();
+        let llm = Arc::new(
+            MockLlm::builder()
+                .node("triage").reply(r#"{"team": "sales"}"#).reply(r#"Sure: {"team": "billing", "urgent": true}"#).done()
+                .node("billing").reply("Refund issued.").done()
+                .build(),
+        );
+
+        let output = engine(config).with_provider(llm.clone()).execute_stream("Refund me!", &[]).await.unwrap();
+        assert_eq!(subpipeline::collect_output("triage", output).await.unwrap(), "Refund issued.");
+
+        let triage = llm.requests_for("triage");
+        assert_eq!(triage.len(), 2);
+        assert!(triage[0].system_prompt.contains("matching this JSON Schema"));
+        assert_eq!(triage[0].output_schema.as_ref(), Some(&schema));
+        assert!(triage[1].input.contains("/team") && triage[1].input.contains("\"urgent\" is a required property"));
+        assert_eq!(llm.requests_for("billing")[0].system_prompt, "Urgent: true");
+        assert!(llm.requests_for("tech").is_empty());
+
+        // A response that never matches fails the node after the last attempt
+        let config = PipelineConfig::builder("strict", "Strict")
+            .node("triage", NodeType::Llm).output_schema(schema).done()
+            .edge("input", "triage")
+            .edge("triage", "output")
+            .build();
+        let llm = Arc::new(MockLlm::builder().node("triage").reply("no idea").done().build());
+        let err = engine(config).with_provider(llm.clone()).execute_stream("?", &[]).await.err().unwrap();
+        assert!(err.to_string().contains("doesn't match its schema after 3 attempts"), "{}", err);
+        assert_eq!(llm.requests_for("triage").len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_dynamic_worker_keeps_subtask_values() {
+        let config = PipelineConfig::builder("extract", "Extract")
+            .node("plan", NodeType::Orchestrator).done()
+            .node("extract", NodeType::Llm).prompt("Extract the priority.")
+                .output_schema(serde_json::json!({ "type": "object", "properties": { "priority": { "type": "integer" } } })).done()
+            .node("report", NodeType::Llm).prompt("First {{nodes.extract.0.priority}}, then {{nodes.extract.1.priority}}.").done()
+            .edge("input", "plan")
+            .edge_typed("plan", "extract", EdgeType::Dynamic)
+            .edge("extract", "report")
+            .edge("report", "output")
+            .build();
+        let llm = Arc::new(
+            MockLlm::builder()
+                .node("plan")
+                .reply(r#"{"subtasks": [{"worker": "extract", "task": "first"}, {"worker": "extract", "task": "second"}]}"#)
+                .done()
+                .prompt("^first").delay(Duration::from_millis(20)).reply(r#"{"priority": 1}"#).done()
+                .prompt("^second").reply(r#"{"priority": 3}"#).done()
+                .node("report").reply("Done.").done()
+                .build(),
+        );
+
+        let result = engine(config).with_provider(llm.clone())
+            .execute_with_context("triage these", &[], CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!(result.context.output("extract"), Some(&serde_json::json!([{ "priority": 1 }, { "priority": 3 }])));
+        assert_eq!(llm.requests_for("report")[0].system_prompt, "First 1, then 3.");
+        assert_eq!(llm.requests_for("report")[0].input, "{\"priority\":1}\n\n---\n\n{\"priority\":3}");
+    }
+
+    #[tokio::test]
+    async fn test_router_classification() {
+        let config = |router: serde_json::Value| {
+            PipelineConfig::builder("support", "Support")
+                .node("triage", NodeType::Router).config(router).done()
+                .node("billing", NodeType::Llm).done()
+                .node("tech", NodeType::Llm).done()
+                .node("human", NodeType::Llm).done()
+                .edge("input", "triage")
+                .conditional_edge("triage", &["billing", "tech", "human"])
+                .edge("billing", "output")
+                .edge("tech", "output")
+                .edge("human", "output")
+                .build()
+        };
+        let mock = || {
+            Arc::new(
+                MockLlm::builder()
+                    .node("triage")
+                    .reply(r#"{"routes": [{"target": "Billing", "confidence": 0.55}, {"target": "tech-support", "confidence": 0.8}],
+                        "rationale": "A refund for a broken device."}"#)
+                    .done()
+                    .any().reply("Handled.").done()
+                    .build(),
+            )
+        };
+
+        // Every confident route is followed; the rationale is kept in the context
+        let llm = mock();
+        let multi = engine(config(serde_json::json!({ "multi_label": true, "min_confidence": 0.5 }))).with_provider(llm.clone());
+        let run = multi.execute_with_context("My laptop broke, refund it", &[], CancellationToken::new()).await.unwrap();
+        assert_eq!(llm.requests_for("billing").len(), 1);
+        assert_eq!(llm.requests_for("tech").len(), 1);
+        assert!(llm.requests_for("human").is_empty());
+        // Routed nodes get the request, not the classification
+        assert_eq!(llm.requests_for("tech")[0].input, "My laptop broke, refund it");
+        assert_eq!(run.context.field("triage", &["targets"]), Some(&serde_json::json!(["tech", "billing"])));
+        assert_eq!(run.context.field("triage", &["rationale"]), Some(&serde_json::json!("A refund for a broken device.")));
+
+        // No route is confident enough, so the fallback is followed
+        let llm = mock();
+        let unsure = engine(config(serde_json::json!({ "min_confidence": 0.9, "fallback": "human" }))).with_provider(llm.clone());
+        let run = unsure.execute_with_context("My laptop broke", &[], CancellationToken::new()).await.unwrap();
+        assert_eq!(llm.requests_for("human")[0].input, "My laptop broke");
+        assert!(llm.requests_for("billing").is_empty() && llm.requests_for("tech").is_empty());
+        assert_eq!(run.context.field("triage", &["fallback"]), Some(&serde_json::json!(true)));
+
+        let err = engine(config(serde_json::json!({ "fallback": "sales" }))).validate().unwrap_err();
+        assert!(err.to_string().contains("falls back to 'sales'"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_edge_conditions() {
+        let config = PipelineConfig::builder("support", "Support")
+            .node("triage", NodeType::Router).done()
+            .node("billing", NodeType::Llm).done()
+            .node("tech", NodeType::Llm).done()
+            .node("legal", NodeType::Llm).done()
+            .edge("input", "triage")
+            .edge_when("input", "legal", Condition::keywords(["lawyer", "lawsuit"]))
+            .edge_when("triage", "billing", Condition::keywords(["refund", "invoice"]))
+            .conditional_edge("triage", &["billing", "tech"])
+            .edge("billing", "output")
+            .edge("tech", "output")
+            .build();
+        let mock = || {
+            Arc::new(
+                MockLlm::builder()
+                    .node("triage").reply(r#"{"routes": [{"target": "tech", "confidence": 0.9}]}"#).done()
+                    .any().reply("Handled.").done()
+                    .build(),
+            )
+        };
+
+        // A rule matches, so the router decides without an LLM call
+        let llm = mock();
+        let run = engine(config.clone()).with_provider(llm.clone())
+            .execute_with_context("Please refund my invoice", &[], CancellationToken::new()).await.unwrap();
+        assert!(llm.requests_for("triage").is_empty());
+        assert_eq!(llm.requests_for("billing").len(), 1);
+        // The routed node gets the request, not the classification
+        assert_eq!(llm.requests_for("billing")[0].input, "Please refund my invoice");
+        assert!(llm.requests_for("tech").is_empty() && llm.requests_for("legal").is_empty());
+        assert_eq!(run.context.field("triage", &["rule"]), Some(&serde_json::json!(true)));
+
+        // No rule matches, so the router classifies; the condition from input holds
+        let llm = mock();
+        engine(config).with_provider(llm.clone())
+            .execute_with_context("My screen broke, I'll call my lawyer", &[], CancellationToken::new()).await.unwrap();
+        assert_eq!(llm.requests_for("triage").len(), 1);
+        assert_eq!(llm.requests_for("tech").len(), 1);
+        assert_eq!(llm.requests_for("legal").len(), 1);
+        assert!(llm.requests_for("billing").is_empty());
+    }
+
+    /// Tags the user input with the team an upstream triage node picked.
+    struct Tag;
+
+    #[async_trait::async_trait]
+    impl NodeExecutor for Tag {
+        async fn execute(&self, ctx: &NodeContext<'_>) -> Result<NodeOutput, AgentError> {
+            let team = ctx.run.context.field("triage", &["team"]).and_then(|v| v.as_str()).unwrap_or("none");
+            Ok(NodeOutput::json(serde_json::json!({ "team": team, "ticket": ctx.run.user_input })))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_context() {
+        let schema = serde_json::json!({ "type": "object", "properties": { "team": { "type": "string" } } });
+        let config = PipelineConfig::builder("context", "Context")
+            .node("triage", NodeType::Llm).output_schema(schema).done()
+            .node("tag", NodeType::Custom).config(serde_json::json!({ "executor": "tag" })).done()
+            .node("writer", NodeType::Llm).prompt("Ticket for {{nodes.tag.team}}").done()
+            .edge("input", "triage")
+            .edge("triage", "tag")
+            .edge("tag", "writer")
+            .edge("writer", "output")
+            .build();
+        let llm = Arc::new(
+            MockLlm::builder()
+                .node("triage").reply(r#"{"team": "billing"}"#).done()
+                .node("writer").reply("Routed to billing.").done()
+                .build(),
+        );
+        let mut nodes = NodeRegistry::new();
+        nodes.register("tag", Tag);
+        let engine = engine(config).with_provider(llm.clone()).with_nodes(nodes);
+
+        // The output node isn't streamed, so the context is complete
+        let run = engine.execute_with_context("Refund me!", &[], CancellationToken::new()).await.unwrap();
+        assert!(matches!(run.output, EngineOutput::Complete(ref text) if text == "Routed to billing."));
+        assert_eq!(llm.requests_for("writer")[0].system_prompt, "Ticket for billing");
+
+        let context = run.context;
+        assert_eq!(context.output("triage"), Some(&serde_json::json!({ "team": "billing" })));
+        assert_eq!(context.field("tag", &["ticket"]), Some(&serde_json::json!("Refund me!")));
+        assert_eq!(context.text("writer").unwrap(), "Routed to billing.");
+        let writer = context.get("writer").unwrap();
+        assert_eq!(writer.status, NodeStatus::Completed);
+        assert_eq!(writer.usage.model.as_deref(), Some("m"));
+    }
+
+    #[test]
+    fn test_resolve_chain() {
+        let model = |id: &str| ModelConfig {
+            id: id.to_string(),
+            name: id.to_string(),
+            model: id.to_string(),
+            api_base: None,
+        };
+        let resolver = ModelResolver::new(vec![model("a"), model("b")], model("default"));
+        let ids = |chain: Vec<Arc<ModelConfig>>| chain.iter().map(|m| m.id.clone()).collect::<Vec<_>>();
+
+        let fallbacks = ["b", "a", "missing", "default"].map(String::from);
+        assert_eq!(ids(resolver.resolve_chain(Some("a"), &fallbacks)), ["a", "b", "default"]);
+        assert_eq!(ids(resolver.resolve_chain(None, &[])), ["default"]);
+    }
+
+    #[tokio::test]
+    async fn test_observe_stream_tallies_on_completion() {
+        let chunks: Vec<Result<StreamChunk, AgentError>> = vec![
+            Ok(StreamChunk::Usage { input_tokens: 12, output_tokens: 0 }),
+            Ok(StreamChunk::Content("Hello".to_string())),
+            Ok(StreamChunk::Content(", world".to_string())),
+            Ok(StreamChunk::Usage { input_tokens: 0, output_tokens: 3 }),
+        ];
+        let seen = Arc::new(Mutex::new(None));
+        let seen_clone = Arc::clone(&seen);
+
+        let stream = observe_stream(Box::pin(futures::stream::iter(chunks)), move |tally| {
+            *seen_clone.lock().unwrap() = Some(tally);
+        });
+        let forwarded: Vec<_> = stream.collect().await;
+        assert_eq!(forwarded.len(), 4);
+
+        let tally = seen.lock().unwrap().take().expect("on_complete not called");
+        assert_eq!(tally.content, "Hello, world");
+        assert_eq!(tally.input_tokens, 12);
+        assert_eq!(tally.output_tokens, 3);
     }
 }
@@ -0,0 +1,185 @@
+//! Map nodes: run a node or pipeline once per item of a list.
+//!
+//! A `map` node splits its input into items and runs its body once per item,
+//! with the item as input, at most `max_concurrency` (default 4) at a time.
+//! Its output is the results as a JSON array of strings, in item order,
+//! stored as JSON in the run context.
+//!
+//! ```json
+//! { "id": "each_source", "type": "map", "config": { "node": "summarize_source", "max_concurrency": 3 } }
+//! ```
+//!
+//! | Key | Value |
+//! |-----|-------|
+//! | `node` | A node of the same pipeline to run per item; it must not be connected by edges |
+//! | `pipeline` | A pipeline to run per item as a nested run, like a `pipeline` node |
+//! | `split` | `"json"`: the input is a JSON array; `"lines"`: one item per non-empty line; `"auto"` (default): a JSON array if the input contains one, lines otherwise |
+//! | `max_concurrency` | How many items run at once |
+//!
+//! Exactly one of `node` and `pipeline` is set. Non-string JSON items are
+//! passed on as JSON text. The first failing item fails the node.
+//! Spans of a pipeline body are recorded under `<map>[<index>]/`.
+
+use fissio_config::{ConfigError, EdgeConfig, NodeType, PipelineConfig};
+use fissio_core::AgentError;
+use serde_json::Value;
+
+/// How many items run at once unless the node sets `max_concurrency`.
+const DEFAULT_MAP_CONCURRENCY: usize = 4;
+
+/// What a map node runs per item.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum MapBody {
+    Node(String),
+    Pipeline(String),
+}
+
+/// How a map node's input is split into items.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Split {
+    Auto,
+    Json,
+    Lines,
+}
+
+/// Map settings read from a node's `config`.
+#[derive(Debug)]
+pub(crate) struct MapConfig {
+    pub(crate) body: MapBody,
+    split: Split,
+    pub(crate) max_concurrency: usize,
+}
+
+impl MapConfig {
+    /// Reads the settings, returning a description of the first invalid one.
+    pub(crate) fn from_value(config: &Value) -> Result<Self, String> {
+        let text = |key: &str| config.get(key).and_then(|v| v.as_str()).map(String::from);
+
+        let body = match (text("node"), text("pipeline")) {
+            (Some(node), None) => MapBody::Node(node),
+            (None, Some(pipeline)) => MapBody::Pipeline(pipeline),
+            (Some(_), Some(_)) => return Err("sets both config.node and config.pipeline".to_string()),
+            (None, None) => return Err("has no config.node or config.pipeline".to_string()),
+        };
+        let split = match text("split").as_deref() {
+            None | Some("auto") => Split::Auto,
+            Some("json") => Split::Json,
+            Some("lines") => Split::Lines,
+            Some(other) => return Err(format!("has unknown split '{}'", other)),
+        };
+        let max_concurrency = match config.get("max_concurrency") {
+            None => DEFAULT_MAP_CONCURRENCY,
+            Some(value) => match value.as_u64() {
+                Some(n) if n > 0 => n as usize,
+                _ => return Err("needs a positive max_concurrency".to_string()),
+            },
+        };
+
+        Ok(Self { body, split, max_concurrency })
+    }
+
+    /// Splits a map node's input into items.
+    pub(crate) fn items(&self, input: &str) -> Result<Vec<String>, AgentError> {
+        let array = match extract_array(input) {
+            Some(values) if self.split != Split::Lines => Some(values),
+            _ if self.split == Split::Json => {
+                return Err(AgentError::ParseError("map input is not a JSON array".to_string()));
+            }
+            _ => None,
+        };
+
+        let Some(values) = array else {
+            return Ok(input.lines().map(str::trim).filter(|l| !l.is_empty()).map(String::from).collect());
+        };
+        Ok(values
+            .into_iter()
+            .map(|value| match value {
+                Value::String(s) => s,
+                other => other.to_string(),
+            })
+            .collect())
+    }
+}
+
+/// Extracts the outermost JSON array from a map node's input, tolerating
+/// surrounding prose or code fences.
+fn extract_array(text: &str) -> Option<Vec<Value>> {
+    let start = text.find('[')?;
+    let end = text.rfind(']')?;
+    if start >= end {
+        return None;
+    }
+    match serde_json::from_str(&text[start..=end]).ok()? {
+        Value::Array(values) => Some(values),
+        _ => None,
+    }
+}
+
+/// Checks every map node's settings and body node.
+///
+/// A body node runs only on the map's behalf, so it must not be connected by
+/// edges, and can't be an approval node, which couldn't suspend an item.
+pub(crate) fn check_map_nodes(config: &PipelineConfig) -> Result<(), ConfigError> {
+    let touches = |edge: &EdgeConfig, id: &str| edge.from.as_vec().contains(&id) || edge.to.as_vec().contains(&id);
+
+    for node in config.nodes.iter().filter(|n| n.node_type == NodeType::Map) {
+        let invalid = |message: String| ConfigError::validation(&config.id, format!("map node '{}' {}", node.id, message));
+        let map = MapConfig::from_value(&node.config).map_err(invalid)?;
+        let MapBody::Node(ref body_id) = map.body else {
+            continue;
+        };
+
+        let Some(body) = config.nodes.iter().find(|n| &n.id == body_id) else {
+            return Err(invalid(format!("runs unknown node '{}'", body_id)));
+        };
+        if body.id == node.id {
+            return Err(invalid("can't run itself".to_string()));
+        }
+        if body.node_type == NodeType::Approval {
+            return Err(invalid(format!("runs approval node '{}', which can't suspend an item", body.id)));
+        }
+        if config.edges.iter().any(|e| touches(e, &body.id)) {
+            return Err(invalid(format!("runs node '{}', which must not be connected by edges", body.id)));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_items() {
+        let items = |config: Value, input: &str| MapConfig::from_value(&config).unwrap().items(input);
+
+        let auto = json!({ "node": "each" });
+        assert_eq!(items(auto.clone(), "Sources:\n```json\n[\"a\", {\"b\": 1}, 2]\n```").unwrap(), ["a", "{\"b\":1}", "2"]);
+        assert_eq!(items(auto, "  first\n\nsecond  \n").unwrap(), ["first", "second"]);
+        assert_eq!(items(json!({ "node": "each", "split": "lines" }), "[1]\n[2]").unwrap(), ["[1]", "[2]"]);
+        assert!(items(json!({ "node": "each", "split": "json" }), "a\nb").is_err());
+        assert!(items(json!({ "node": "each", "split": "json" }), "[]").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_check_map_nodes() {
+        let pipeline = |config: Value, connect_body: bool| {
+            let builder = PipelineConfig::builder("map", "Map")
+                .node("each", NodeType::Map).config(config).done()
+                .node("summarize", NodeType::Llm).done()
+                .edge("input", "each")
+                .edge("each", "output");
+            let builder = if connect_body { builder.edge("each", "summarize") } else { builder };
+            check_map_nodes(&builder.build()).map_err(|e| e.to_string())
+        };
+
+        assert!(pipeline(json!({ "node": "summarize" }), false).is_ok());
+        assert!(pipeline(json!({ "pipeline": "research" }), false).is_ok());
+        assert!(pipeline(json!({ "node": "summarize" }), true).unwrap_err().contains("must not be connected by edges"));
+        assert!(pipeline(json!({ "node": "missing" }), false).unwrap_err().contains("unknown node 'missing'"));
+        assert!(pipeline(json!({ "node": "each" }), false).unwrap_err().contains("can't run itself"));
+        assert!(pipeline(json!({}), false).unwrap_err().contains("no config.node or config.pipeline"));
+        assert!(pipeline(json!({ "node": "summarize", "max_concurrency": 0 }), false).is_err());
+    }
+}
@@ -0,0 +1,318 @@
+//! A scripted LLM provider for testing pipelines without network access.
+//!
+//! [`MockLlm`] answers each request from the first rule that matches it, by
+//! node ID or by a regex over the system prompt and input. A rule's script
+//! of steps is played in order, one step per request; its last step repeats
+//! once the script runs out.
+//!
+//! ```rust,ignore
+//! let llm = Arc::new(
+//!     MockLlm::builder()
+//!         .node("classify").reply("billing").done()
+//!         .node("agent").call_tool("lookup", json!({ "order": 12 })).reply("It shipped.").done()
+//!         .prompt("(?i)summari[sz]e").delay(Duration::from_millis(20)).reply("In short: ...").done()
+//!         .node("flaky").fail(|| AgentError::ExternalApi("429".into())).reply("ok").done()
+//!         .build(),
+//! );
+//! let engine = PipelineEngine::new(config, models, default_model, HashMap::new())
+//!     .with_provider(llm.clone());
+//! engine.execute_stream("Where is order 12?", &[]).await?;
+//! assert_eq!(llm.requests_for("agent").len(), 2);
+//! ```
+//!
+//! | Step | Chat | Tool loop turn |
+//! |------|------|----------------|
+//! | `reply(text)` | `text` | `text` as the final content |
+//! | `call_tool(name, args)` | Fails | A tool call (consecutive ones are batched) |
+//! | `fail(error)` | Fails with the error | Fails with the error |
+//!
+//! Requests no rule matches fail with [`AgentError::LlmError`]. Every request
+//! is kept for assertions, in the order it was made.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_openai::types::ChatCompletionRequestMessage;
+use async_trait::async_trait;
+use fissio_core::AgentError;
+use fissio_llm::{ChatResponse, LlmMetrics, LlmResponse, LlmStream, StreamChunk, ToolCall, ToolSchema};
+use regex::Regex;
+
+use crate::provider::{LlmProvider, LlmRequest};
+
+/// Creates a scripted error each time its step is played.
+type ErrorFn = Arc<dyn Fn() -> AgentError + Send + Sync>;
+
+/// One scripted response.
+#[derive(Clone)]
+enum Step {
+    Reply(String),
+    ToolCalls(Vec<ToolCall>),
+    Fail(ErrorFn),
+}
+
+/// What a rule matches.
+enum Matcher {
+    Node(String),
+    Prompt(Regex),
+    Any,
+}
+
+impl Matcher {
+    fn matches(&self, request: &LlmRequest<'_>) -> bool {
+        match self {
+            Matcher::Node(id) => request.node_id == id,
+            Matcher::Prompt(pattern) => pattern.is_match(request.system_prompt) || pattern.is_match(request.input),
+            Matcher::Any => true,
+        }
+    }
+}
+
+struct Rule {
+    matcher: Matcher,
+    delay: Option<Duration>,
+    steps: Vec<Step>,
+    /// Index of the next step to play.
+    next: usize,
+}
+
+impl Rule {
+    fn play(&mut self) -> Step {
+        let step = self.steps[self.next.min(self.steps.len() - 1)].clone();
+        self.next += 1;
+        step
+    }
+}
+
+/// A request received by a [`MockLlm`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockRequest {
+    pub node_id: String,
+    /// ID of the model the request was for.
+    pub model: String,
+    pub system_prompt: String,
+    pub input: String,
+    /// Names of the tools offered, for a tool loop turn.
+    pub tools: Vec<String>,
+    /// The JSON Schema the response was asked to match, if any.
+    pub output_schema: Option<serde_json::Value>,
+}
+
+/// A scripted [`LlmProvider`] for testing pipelines without network access.
+///
+/// Each request is answered by the first rule that matches it, by node ID or
+/// by a regex over the system prompt and input. A rule plays its steps in
+/// order, one per request, and repeats the last once they run out. Requests
+/// no rule matches fail with [`AgentError::LlmError`].
+pub struct MockLlm {
+    rules: Mutex<Vec<Rule>>,
+    requests: Mutex<Vec<MockRequest>>,
+}
+
+impl MockLlm {
+    /// Starts building a mock.
+    pub fn builder() -> MockLlmBuilder {
+        MockLlmBuilder { rules: Vec::new() }
+    }
+
+    /// Returns every request received so far.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Returns the requests received from node `node_id`.
+    pub fn requests_for(&self, node_id: &str) -> Vec<MockRequest> {
+        self.requests().into_iter().filter(|r| r.node_id == node_id).collect()
+    }
+
+    /// Records the request and plays the next step of the first matching rule.
+    async fn respond(&self, request: &LlmRequest<'_>, tools: &[ToolSchema]) -> Result<Step, AgentError> {
+        self.requests.lock().unwrap().push(MockRequest {
+            node_id: request.node_id.to_string(),
+            model: request.model.id.clone(),
+            system_prompt: request.system_prompt.to_string(),
+            input: request.input.to_string(),
+            tools: tools.iter().map(|t| t.name.clone()).collect(),
+            output_schema: request.output_schema.cloned(),
+        });
+        let (step, delay) = {
+            let mut rules = self.rules.lock().unwrap();
+            let rule = rules
+                .iter_mut()
+                .find(|rule| rule.matcher.matches(request))
+                .ok_or_else(|| AgentError::LlmError(format!("no mock response for node '{}'", request.node_id)))?;
+            (rule.play(), rule.delay)
+        };
+        if let Some(delay) = delay {
+            tokio::time::sleep(delay).await;
+        }
+        match step {
+            Step::Fail(error) => Err(error()),
+            step => Ok(step),
+        }
+    }
+}
+
+fn response(content: String) -> LlmResponse {
+    LlmResponse { content, metrics: LlmMetrics::default() }
+}
+
+fn tools_unavailable(request: &LlmRequest<'_>) -> AgentError {
+    AgentError::LlmError(format!("mock tool calls scripted for node '{}', which has no tools", request.node_id))
+}
+
+#[async_trait]
+impl LlmProvider for MockLlm {
+    async fn chat(&self, request: &LlmRequest<'_>) -> Result<LlmResponse, AgentError> {
+        match self.respond(request, &[]).await? {
+            Step::Reply(content) => Ok(response(content)),
+            _ => Err(tools_unavailable(request)),
+        }
+    }
+
+    async fn chat_stream(&self, request: &LlmRequest<'_>) -> Result<LlmStream, AgentError> {
+        let content = self.chat(request).await?.content;
+        let chunks = [Ok(StreamChunk::Content(content)), Ok(StreamChunk::Usage { input_tokens: 0, output_tokens: 0 })];
+        Ok(Box::pin(futures::stream::iter(chunks)))
+    }
+
+    async fn chat_with_tools(
+        &self,
+        request: &LlmRequest<'_>,
+        _messages: &[ChatCompletionRequestMessage],
+        tools: &[ToolSchema],
+        _pending_tool_calls: Option<&[ToolCall]>,
+    ) -> Result<ChatResponse, AgentError> {
+        match self.respond(request, tools).await? {
+            Step::Reply(content) => Ok(ChatResponse::Content(response(content))),
+            Step::ToolCalls(calls) => Ok(ChatResponse::ToolCalls { calls, metrics: LlmMetrics::default() }),
+            Step::Fail(_) => unreachable!("respond returns scripted failures as errors"),
+        }
+    }
+}
+
+/// Builder for a [`MockLlm`], created with [`MockLlm::builder`].
+pub struct MockLlmBuilder {
+    rules: Vec<Rule>,
+}
+
+impl MockLlmBuilder {
+    /// Adds a rule for requests from node `node_id`.
+    pub fn node(self, node_id: impl Into<String>) -> MockRuleBuilder {
+        MockRuleBuilder::new(self, Matcher::Node(node_id.into()))
+    }
+
+    /// Adds a rule for requests whose system prompt or input matches the regex `pattern`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pattern` isn't a valid regex.
+    pub fn prompt(self, pattern: &str) -> MockRuleBuilder {
+        let regex = Regex::new(pattern).unwrap_or_else(|e| panic!("invalid mock prompt pattern '{}': {}", pattern, e));
+        MockRuleBuilder::new(self, Matcher::Prompt(regex))
+    }
+
+    /// Adds a rule for every request; earlier rules take precedence.
+    pub fn any(self) -> MockRuleBuilder {
+        MockRuleBuilder::new(self, Matcher::Any)
+    }
+
+    /// Builds the mock.
+    pub fn build(self) -> MockLlm {
+        MockLlm { rules: Mutex::new(self.rules), requests: Mutex::new(Vec::new()) }
+    }
+}
+
+/// Builder for one rule of a [`MockLlm`], created by [`MockLlmBuilder`].
+pub struct MockRuleBuilder {
+    mock: MockLlmBuilder,
+    rule: Rule,
+}
+
+impl MockRuleBuilder {
+    fn new(mock: MockLlmBuilder, matcher: Matcher) -> Self {
+        Self { mock, rule: Rule { matcher, delay: None, steps: Vec::new(), next: 0 } }
+    }
+
+    /// Replies with `content`.
+    pub fn reply(mut self, content: impl Into<String>) -> Self {
+        self.rule.steps.push(Step::Reply(content.into()));
+        self
+    }
+
+    /// Calls tool `name` with `arguments`. Consecutive tool calls are
+    /// returned together in one turn of the tool loop.
+    pub fn call_tool(mut self, name: impl Into<String>, arguments: serde_json::Value) -> Self {
+        let calls = match self.rule.steps.last_mut() {
+            Some(Step::ToolCalls(calls)) => calls,
+            _ => {
+                self.rule.steps.push(Step::ToolCalls(Vec::new()));
+                let Some(Step::ToolCalls(calls)) = self.rule.steps.last_mut() else { unreachable!() };
+                calls
+            }
+        };
+        let id = format!("call_{}", calls.len() + 1);
+        calls.push(ToolCall { id, name: name.into(), arguments });
+        self
+    }
+
+    /// Fails with the error `error` creates.
+    pub fn fail(mut self, error: impl Fn() -> AgentError + Send + Sync + 'static) -> Self {
+        self.rule.steps.push(Step::Fail(Arc::new(error)));
+        self
+    }
+
+    /// Waits `delay` before every response from this rule.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.rule.delay = Some(delay);
+        self
+    }
+
+    /// Finishes the rule and returns to the mock builder.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no steps were scripted.
+    pub fn done(mut self) -> MockLlmBuilder {
+        assert!(!self.rule.steps.is_empty(), "no steps scripted for a mock rule");
+        self.mock.rules.push(self.rule);
+        self.mock
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fissio_core::{ErrorKind, ModelConfig};
+
+    fn request<'a>(model: &'a ModelConfig, node_id: &'a str, input: &'a str) -> LlmRequest<'a> {
+        LlmRequest { node_id, model, system_prompt: "Be brief.", history: &[], input, output_schema: None }
+    }
+
+    #[tokio::test]
+    async fn test_scripts_and_matching() {
+        let model = ModelConfig { id: "m".into(), name: "M".into(), model: "m".into(), api_base: None };
+        let llm = MockLlm::builder()
+            .node("flaky").fail(|| AgentError::ExternalApi("429".into())).reply("ok").done()
+            .prompt("(?i)refund").reply("billing").done()
+            .node("agent").call_tool("lookup", serde_json::json!({ "order": 12 })).call_tool("lookup", serde_json::json!({ "order": 13 })).reply("done").done()
+            .build();
+
+        let err = llm.chat(&request(&model, "flaky", "hi")).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ExternalApi);
+        // The last step repeats
+        assert_eq!(llm.chat(&request(&model, "flaky", "hi")).await.unwrap().content, "ok");
+        assert_eq!(llm.chat(&request(&model, "flaky", "hi")).await.unwrap().content, "ok");
+
+        assert_eq!(llm.chat(&request(&model, "router", "I want a REFUND")).await.unwrap().content, "billing");
+        assert!(llm.chat(&request(&model, "router", "hello")).await.is_err());
+
+        let turn = llm.chat_with_tools(&request(&model, "agent", "hi"), &[], &[], None).await.unwrap();
+        assert!(matches!(turn, ChatResponse::ToolCalls { calls, .. } if calls.len() == 2 && calls[1].id == "call_2"));
+        let turn = llm.chat_with_tools(&request(&model, "agent", "hi"), &[], &[], None).await.unwrap();
+        assert!(matches!(turn, ChatResponse::Content(response) if response.content == "done"));
+
+        assert_eq!(llm.requests().len(), 7);
+        assert_eq!(llm.requests_for("agent").len(), 2);
+    }
+}
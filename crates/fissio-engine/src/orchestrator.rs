@@ -0,0 +1,180 @@
+//! Orchestrator nodes: runtime task decomposition over dynamic edges.
+//!
+//! An orchestrator asks its model for a [`Plan`] assigning subtasks to the
+//! targets of its `dynamic` edges. The engine then dispatches each subtask to
+//! its worker in parallel, with the subtask as that worker's input. Workers
+//! that receive no subtask are skipped.
+//!
+//! # Configuration
+//!
+//! ```json
+//! { "id": "orchestrator", "type": "orchestrator", "config": { "max_subtasks": 6 } }
+//! ```
+
+use fissio_core::AgentError;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::recording::Calls;
+use crate::{extract_json, ExecutionMetrics};
+
+/// Default cap on the number of subtasks in a plan.
+const DEFAULT_MAX_SUBTASKS: usize = 8;
+
+/// A unit of work assigned to one worker.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Subtask {
+    /// ID of the worker node that should handle this subtask.
+    pub worker: String,
+    /// Self-contained instructions for the worker.
+    pub task: String,
+}
+
+/// Subtasks produced by an orchestrator node.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Plan {
+    pub subtasks: Vec<Subtask>,
+}
+
+impl Plan {
+    /// Parses a plan from model output, accepting either `{"subtasks": [...]}`
+    /// or a bare array of subtasks.
+    pub fn parse(output: &str) -> Option<Self> {
+        let json = extract_json(output)?;
+        let subtasks = match json {
+            serde_json::Value::Array(_) => json,
+            serde_json::Value::Object(mut obj) => obj.remove("subtasks")?,
+            _ => return None,
+        };
+        serde_json::from_value(subtasks).ok().map(|subtasks| Self { subtasks })
+    }
+
+    /// Keeps only subtasks assigned to known workers (matched case-insensitively,
+    /// normalized to the worker's ID) and caps the plan at `max` subtasks.
+    fn restrict_to(mut self, workers: &[WorkerInfo], max: usize) -> Self {
+        self.subtasks.retain_mut(|subtask| {
+            match workers.iter().find(|w| w.id.eq_ignore_ascii_case(subtask.worker.trim())) {
+                Some(worker) => {
+                    subtask.worker = worker.id.clone();
+                    true
+                }
+                None => {
+                    warn!("║     ⚠ Plan assigned unknown worker '{}', dropping subtask", subtask.worker);
+                    false
+                }
+            }
+        });
+        self.subtasks.truncate(max);
+        self
+    }
+}
+
+/// A dispatchable worker as described to the orchestrator's model.
+pub(crate) struct WorkerInfo {
+    pub(crate) id: String,
+    pub(crate) description: Option<String>,
+}
+
+/// Executes an Orchestrator node: the LLM decomposes the input into subtasks.
+///
+/// Falls back to sending the original input to every worker if the model's
+/// plan can't be parsed or assigns nothing.
+pub(crate) async fn execute_orchestrator(
+    calls: Calls<'_>,
+    prompt: Option<&str>,
+    config: &serde_json::Value,
+    input: &str,
+    workers: &[WorkerInfo],
+) -> Result<(Plan, ExecutionMetrics), AgentError> {
+    let max_subtasks = config
+        .get("max_subtasks")
+        .and_then(|v| v.as_u64())
+        .map(|n| n as usize)
+        .unwrap_or(DEFAULT_MAX_SUBTASKS);
+
+    let worker_list = workers
+        .iter()
+        .map(|w| match w.description {
+            Some(ref d) => format!("- {}: {}", w.id, d),
+            None => format!("- {}", w.id),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let system_prompt = format!(
+        "{}\n\nYou are an orchestrator. Break the task into at most {} subtasks and assign each \
+        to one of these workers:\n{}\n\n\
+        IMPORTANT: Respond with ONLY a JSON object of the form \
+        {{\"subtasks\": [{{\"worker\": \"<worker id>\", \"task\": \"<self-contained instructions>\"}}]}}. \
+        Use only the listed worker ids and omit workers that aren't needed.",
+        prompt.unwrap_or("Decompose the following task."),
+        max_subtasks,
+        worker_list
+    );
+
+    let response = calls.chat(&system_prompt, input).await?;
+
+    let plan = Plan::parse(&response.content)
+        .map(|plan| plan.restrict_to(workers, max_subtasks))
+        .filter(|plan| !plan.subtasks.is_empty())
+        .unwrap_or_else(|| {
+            warn!("║     ⚠ No usable plan from orchestrator, dispatching input to all workers");
+            Plan {
+                subtasks: workers
+                    .iter()
+                    .map(|w| Subtask { worker: w.id.clone(), task: input.to_string() })
+                    .collect(),
+            }
+        });
+
+    info!(
+        "║     Plan: {:?}",
+        plan.subtasks.iter().map(|s| &s.worker).collect::<Vec<_>>()
+    );
+
+    let mut metrics = ExecutionMetrics::default();
+    metrics.accumulate(&response.metrics);
+    metrics.iteration_count = 1;
+
+    Ok((plan, metrics))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workers() -> Vec<WorkerInfo> {
+        ["search_worker", "general_worker"]
+            .into_iter()
+            .map(|id| WorkerInfo { id: id.to_string(), description: None })
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_parse() {
+        let plan = Plan::parse(
+            r#"Here is the plan: {"subtasks": [{"worker": "search_worker", "task": "Find sources"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(plan.subtasks, vec![Subtask { worker: "search_worker".into(), task: "Find sources".into() }]);
+
+        let plan = Plan::parse(r#"[{"worker": "general_worker", "task": "Draft"}]"#).unwrap();
+        assert_eq!(plan.subtasks.len(), 1);
+
+        assert!(Plan::parse("no plan here").is_none());
+    }
+
+    #[test]
+    fn test_plan_restrict_to_workers() {
+        let plan = Plan {
+            subtasks: vec![
+                Subtask { worker: "Search_Worker".into(), task: "a".into() },
+                Subtask { worker: "unknown".into(), task: "b".into() },
+                Subtask { worker: "general_worker".into(), task: "c".into() },
+                Subtask { worker: "general_worker".into(), task: "d".into() },
+            ],
+        };
+        let plan = plan.restrict_to(&workers(), 2);
+        let assigned: Vec<_> = plan.subtasks.iter().map(|s| s.worker.as_str()).collect();
+        assert_eq!(assigned, ["search_worker", "general_worker"]);
+    }
+}
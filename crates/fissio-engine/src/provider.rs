@@ -0,0 +1,110 @@
+//! LLM providers: where nodes' chat requests are sent.
+//!
+//! Every LLM call a node makes goes through the engine's [`LlmProvider`].
+//! The default, [`UnifiedProvider`], sends it to OpenAI or Anthropic with
+//! [`UnifiedLlmClient`]. Attach another with
+//! [`PipelineEngine::with_provider`](crate::PipelineEngine::with_provider),
+//! such as a [`MockLlm`](crate::MockLlm) for offline tests:
+//!
+//! ```rust,ignore
+//! use fissio_engine::{LlmProvider, LlmRequest};
+//! use async_trait::async_trait;
+//!
+//! struct Canned;
+//!
+//! #[async_trait]
+//! impl LlmProvider for Canned {
+//!     async fn chat(&self, request: &LlmRequest<'_>) -> Result<LlmResponse, AgentError> {
+//!         Ok(LlmResponse { content: format!("{} says hi", request.node_id), metrics: LlmMetrics::default() })
+//!     }
+//!     // chat_stream and chat_with_tools ...
+//! }
+//!
+//! let engine = PipelineEngine::new(config, models, default_model, HashMap::new())
+//!     .with_provider(Arc::new(Canned));
+//! ```
+//!
+//! Recording and replay wrap the provider, so replayed calls never reach it.
+
+use async_openai::types::ChatCompletionRequestMessage;
+use async_trait::async_trait;
+use fissio_core::{AgentError, Message, ModelConfig};
+use fissio_llm::{ChatResponse, LlmResponse, LlmStream, ToolCall, ToolSchema, UnifiedLlmClient};
+
+/// A chat request made by a node.
+#[derive(Debug, Clone, Copy)]
+pub struct LlmRequest<'a> {
+    /// The node making the request.
+    pub node_id: &'a str,
+    /// The model the request is for.
+    pub model: &'a ModelConfig,
+    pub system_prompt: &'a str,
+    /// Conversation history preceding the input.
+    pub history: &'a [Message],
+    /// The user message: the node's input, or text the node built from it.
+    pub input: &'a str,
+    /// JSON Schema the response must match, for providers that can constrain
+    /// their output to one. The system prompt describes it either way.
+    pub output_schema: Option<&'a serde_json::Value>,
+}
+
+/// Sends nodes' chat requests to an LLM.
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// Sends a chat request and returns the complete response.
+    async fn chat(&self, request: &LlmRequest<'_>) -> Result<LlmResponse, AgentError>;
+
+    /// Sends a chat request and returns a stream of chunks.
+    async fn chat_stream(&self, request: &LlmRequest<'_>) -> Result<LlmStream, AgentError>;
+
+    /// Sends one turn of a tool loop, offering `tools`, and returns either
+    /// content or tool calls to execute.
+    ///
+    /// `messages` holds the conversation so far: `request.history`, the
+    /// input, and any earlier tool calls' results. `pending_tool_calls` are
+    /// the calls from the previous turn whose results it ends with.
+    async fn chat_with_tools(
+        &self,
+        request: &LlmRequest<'_>,
+        messages: &[ChatCompletionRequestMessage],
+        tools: &[ToolSchema],
+        pending_tool_calls: Option<&[ToolCall]>,
+    ) -> Result<ChatResponse, AgentError>;
+}
+
+/// Sends requests to each model's provider with [`UnifiedLlmClient`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnifiedProvider;
+
+fn client(model: &ModelConfig) -> UnifiedLlmClient {
+    UnifiedLlmClient::new(&model.model, model.api_base.as_deref())
+}
+
+#[async_trait]
+impl LlmProvider for UnifiedProvider {
+    async fn chat(&self, request: &LlmRequest<'_>) -> Result<LlmResponse, AgentError> {
+        let client = client(request.model);
+        match request.output_schema {
+            Some(schema) => client.chat_with_schema(request.system_prompt, request.history, request.input, schema).await,
+            None => client.chat_with_history(request.system_prompt, request.history, request.input).await,
+        }
+    }
+
+    async fn chat_stream(&self, request: &LlmRequest<'_>) -> Result<LlmStream, AgentError> {
+        client(request.model)
+            .chat_stream(request.system_prompt, request.history, request.input)
+            .await
+    }
+
+    async fn chat_with_tools(
+        &self,
+        request: &LlmRequest<'_>,
+        messages: &[ChatCompletionRequestMessage],
+        tools: &[ToolSchema],
+        pending_tool_calls: Option<&[ToolCall]>,
+    ) -> Result<ChatResponse, AgentError> {
+        client(request.model)
+            .chat_with_tools(request.system_prompt, messages, tools, pending_tool_calls)
+            .await
+    }
+}
@@ -0,0 +1,392 @@
+//! Recording provider calls and replaying them deterministically.
+//!
+//! Attach a [`CallStore`] with
+//! [`PipelineEngine::with_recording`](crate::PipelineEngine::with_recording)
+//! and every successful LLM request and tool execution of the run is saved
+//! with its response under the run's ID. A [`Replay`] of those calls, attached
+//! with [`PipelineEngine::with_replay`](crate::PipelineEngine::with_replay),
+//! serves the recorded responses instead of calling providers or tools:
+//!
+//! ```rust,ignore
+//! let store = Arc::new(TraceStore::new("traces.db")?);
+//! let replay = Replay::load(store.as_ref(), "run-1")?.live("draft");
+//! let engine = PipelineEngine::new(edited_config, models, default_model, HashMap::new())
+//!     .with_replay(replay)
+//!     .with_recording(store, "run-2");
+//! let output = engine.execute_stream(&input, &history).await?;
+//! ```
+//!
+//! A call is served from the recording when an unused recorded call has the
+//! same request: system prompt, history or messages, input, and tools for an
+//! LLM call, tool name and arguments for a tool execution. Recorded calls of
+//! the same node are preferred, and each is served once. The model a request
+//! went to isn't compared, so calls that succeeded on a fallback model replay
+//! on the first attempt.
+//!
+//! | Call | Replayed |
+//! |------|----------|
+//! | Made by a node marked [`live`](Replay::live) | Never; the provider or tool is called |
+//! | Matching a recorded call | Yes |
+//! | Not matching, by default | No; fails with [`AgentError::Replay`] |
+//! | Not matching, with [`live_on_miss`](Replay::live_on_miss) | No; the provider or tool is called |
+//!
+//! A replayed stream yields the recorded content as a single chunk followed
+//! by its usage.
+
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use fissio_core::{AgentError, Message, ModelConfig};
+use fissio_llm::{ChatResponse, LlmMetrics, LlmResponse, LlmStream, StreamChunk, ToolCall, ToolSchema};
+use fissio_monitor::{CallKind, CallRecord, CallStore};
+use fissio_tools::Tool;
+use async_openai::types::ChatCompletionRequestMessage;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::provider::{LlmProvider, LlmRequest};
+use crate::{now_ms, observe_stream};
+
+/// Where a run's calls are recorded.
+#[derive(Clone)]
+pub(crate) struct Recorder {
+    pub(crate) store: Arc<dyn CallStore>,
+    pub(crate) run_id: String,
+}
+
+impl Recorder {
+    fn record(&self, node_id: &str, model: &ModelConfig, kind: CallKind, mut request: Value, response: Value) {
+        if kind == CallKind::Llm {
+            request["model"] = Value::String(model.model.clone());
+        }
+        let call = CallRecord {
+            run_id: self.run_id.clone(),
+            node_id: node_id.to_string(),
+            kind,
+            request,
+            response,
+            timestamp: now_ms(),
+        };
+        if let Err(e) = self.store.record_call(&call) {
+            warn!("Failed to record call: {}", e);
+        }
+    }
+}
+
+/// Recorded calls served in place of provider requests and tool executions.
+pub struct Replay {
+    /// Recorded calls that haven't been served yet.
+    calls: Mutex<Vec<CallRecord>>,
+    /// Nodes whose calls always go to providers and tools.
+    live: HashSet<String>,
+    /// Whether calls without a recording go to providers and tools instead of failing.
+    live_on_miss: bool,
+}
+
+impl Replay {
+    /// Creates a replay of the given calls.
+    pub fn new(calls: Vec<CallRecord>) -> Self {
+        Self { calls: Mutex::new(calls), live: HashSet::new(), live_on_miss: false }
+    }
+
+    /// Loads the calls recorded by run `run_id`.
+    pub fn load(store: &dyn CallStore, run_id: &str) -> Result<Self, AgentError> {
+        let calls = store.load_calls(run_id).map_err(|e| AgentError::Replay(e.to_string()))?;
+        Ok(Self::new(calls))
+    }
+
+    /// Runs node `node_id` live: its calls go to providers and tools.
+    pub fn live(mut self, node_id: impl Into<String>) -> Self {
+        self.live.insert(node_id.into());
+        self
+    }
+
+    /// Sends calls that have no recording to providers and tools instead of
+    /// failing the run, e.g. for nodes downstream of an edited one.
+    pub fn live_on_miss(mut self) -> Self {
+        self.live_on_miss = true;
+        self
+    }
+
+    /// Takes the recorded response to a call, or returns `None` if the call
+    /// should go to the provider or tool.
+    pub(crate) fn take(&self, node_id: &str, kind: CallKind, request: &Value) -> Result<Option<Value>, AgentError> {
+        if self.live.contains(node_id) {
+            return Ok(None);
+        }
+        let mut calls = self.calls.lock().unwrap();
+        let matches = |call: &CallRecord| call.kind == kind && same_request(&call.request, request);
+        let index = calls
+            .iter()
+            .position(|call| call.node_id == node_id && matches(call))
+            .or_else(|| calls.iter().position(matches));
+        match index {
+            Some(index) => Ok(Some(calls.remove(index).response)),
+            None if self.live_on_miss => Ok(None),
+            None => Err(AgentError::Replay(format!("no recorded {} call matches the request from node '{}'", kind.as_str(), node_id))),
+        }
+    }
+}
+
+/// Compares a recorded request with a new one, ignoring the model it went to.
+fn same_request(recorded: &Value, request: &Value) -> bool {
+    match recorded {
+        Value::Object(fields) => {
+            let mut fields = fields.clone();
+            fields.remove("model");
+            Value::Object(fields) == *request
+        }
+        _ => recorded == request,
+    }
+}
+
+/// The provider requests and tool executions of one node attempt, recorded
+/// and replayed when the engine is set up to.
+#[derive(Clone, Copy)]
+pub(crate) struct Calls<'a> {
+    node_id: &'a str,
+    model: &'a ModelConfig,
+    provider: &'a dyn LlmProvider,
+    recorder: Option<&'a Recorder>,
+    replay: Option<&'a Replay>,
+}
+
+impl<'a> Calls<'a> {
+    pub(crate) fn new(
+        node_id: &'a str,
+        model: &'a ModelConfig,
+        provider: &'a dyn LlmProvider,
+        recorder: Option<&'a Recorder>,
+        replay: Option<&'a Replay>,
+    ) -> Self {
+        Self { node_id, model, provider, recorder, replay }
+    }
+
+    /// Returns the model LLM requests go to.
+    pub(crate) fn model(&self) -> &'a ModelConfig {
+        self.model
+    }
+
+    fn request<'r>(&'r self, system_prompt: &'r str, history: &'r [Message], input: &'r str) -> LlmRequest<'r> {
+        LlmRequest { node_id: self.node_id, model: self.model, system_prompt, history, input, output_schema: None }
+    }
+
+    /// Sends a chat request without history.
+    pub(crate) async fn chat(&self, system_prompt: &str, user_input: &str) -> Result<LlmResponse, AgentError> {
+        self.chat_with_history(system_prompt, &[], user_input).await
+    }
+
+    /// Sends a chat request with history.
+    pub(crate) async fn chat_with_history(
+        &self,
+        system_prompt: &str,
+        history: &[Message],
+        user_input: &str,
+    ) -> Result<LlmResponse, AgentError> {
+        let request = chat_request(system_prompt, history, user_input);
+        let live = async { self.provider.chat(&self.request(system_prompt, history, user_input)).await };
+        self.exchange(CallKind::Llm, request, live).await
+    }
+
+    /// Sends a chat request with history whose response must match `schema`.
+    pub(crate) async fn chat_with_schema(
+        &self,
+        system_prompt: &str,
+        history: &[Message],
+        user_input: &str,
+        schema: &Value,
+    ) -> Result<LlmResponse, AgentError> {
+        let mut request = chat_request(system_prompt, history, user_input);
+        request["schema"] = schema.clone();
+        let llm_request = LlmRequest { output_schema: Some(schema), ..self.request(system_prompt, history, user_input) };
+        let live = async { self.provider.chat(&llm_request).await };
+        self.exchange(CallKind::Llm, request, live).await
+    }
+
+    /// Sends a chat request with history and returns a stream of chunks.
+    ///
+    /// The stream is recorded like a complete response once it ends without errors.
+    pub(crate) async fn chat_stream(
+        &self,
+        system_prompt: &str,
+        history: &[Message],
+        user_input: &str,
+    ) -> Result<LlmStream, AgentError> {
+        let request = chat_request(system_prompt, history, user_input);
+        if let Some(response) = self.replayed::<LlmResponse>(CallKind::Llm, &request)? {
+            self.record(CallKind::Llm, request, &response);
+            return Ok(replay_stream(response));
+        }
+
+        let stream = self.provider.chat_stream(&self.request(system_prompt, history, user_input)).await?;
+        let Some(recorder) = self.recorder.cloned() else {
+            return Ok(stream);
+        };
+        let (node_id, model) = (self.node_id.to_string(), self.model.clone());
+        Ok(observe_stream(stream, move |tally| {
+            if tally.error.is_some() {
+                return;
+            }
+            let response = LlmResponse {
+                content: tally.content,
+                metrics: LlmMetrics { input_tokens: tally.input_tokens, output_tokens: tally.output_tokens, elapsed_ms: 0 },
+            };
+            if let Ok(response) = serde_json::to_value(&response) {
+                recorder.record(&node_id, &model, CallKind::Llm, request, response);
+            }
+        }))
+    }
+
+    /// Sends one turn of a tool loop; see [`LlmProvider::chat_with_tools`].
+    pub(crate) async fn chat_with_tools(
+        &self,
+        system_prompt: &str,
+        history: &[Message],
+        user_input: &str,
+        messages: &[ChatCompletionRequestMessage],
+        tools: &[ToolSchema],
+        pending_tool_calls: Option<&[ToolCall]>,
+    ) -> Result<ChatResponse, AgentError> {
+        let request = json!({
+            "system": system_prompt,
+            "messages": messages,
+            "tools": tools.iter().map(|t| t.name.as_str()).collect::<Vec<_>>(),
+        });
+        let llm_request = self.request(system_prompt, history, user_input);
+        let live = async { self.provider.chat_with_tools(&llm_request, messages, tools, pending_tool_calls).await };
+        self.exchange(CallKind::Llm, request, live).await
+    }
+
+    /// Executes a tool call, mapping a tool failure to an [`AgentError`].
+    pub(crate) async fn execute_tool(&self, tool: &dyn Tool, call: &ToolCall) -> Result<String, AgentError> {
+        let request = json!({ "tool": call.name, "arguments": call.arguments });
+        let live = async {
+            tool.execute(call.arguments.clone())
+                .await
+                .map_err(|e| AgentError::LlmError(format!("Tool execution failed: {}", e)))
+        };
+        self.exchange(CallKind::Tool, request, live).await
+    }
+
+    /// Serves a call from the replay if it has a recording, otherwise makes it
+    /// with `live`, and records the response.
+    async fn exchange<T, Fut>(&self, kind: CallKind, request: Value, live: Fut) -> Result<T, AgentError>
+    where
+        T: Serialize + DeserializeOwned,
+        Fut: Future<Output = Result<T, AgentError>>,
+    {
+        let response = match self.replayed(kind, &request)? {
+            Some(response) => response,
+            None => live.await?,
+        };
+        self.record(kind, request, &response);
+        Ok(response)
+    }
+
+    fn replayed<T: DeserializeOwned>(&self, kind: CallKind, request: &Value) -> Result<Option<T>, AgentError> {
+        let Some(response) = self.replay.map(|r| r.take(self.node_id, kind, request)).transpose()?.flatten() else {
+            return Ok(None);
+        };
+        info!("║     ↺ Replaying recorded {} call", kind.as_str());
+        serde_json::from_value(response)
+            .map(Some)
+            .map_err(|e| AgentError::Replay(format!("recorded response for node '{}': {}", self.node_id, e)))
+    }
+
+    fn record<T: Serialize>(&self, kind: CallKind, request: Value, response: &T) {
+        let Some(recorder) = self.recorder else {
+            return;
+        };
+        match serde_json::to_value(response) {
+            Ok(response) => recorder.record(self.node_id, self.model, kind, request, response),
+            Err(e) => warn!("Failed to serialize call response: {}", e),
+        }
+    }
+}
+
+/// Returns the recorded form of a chat request.
+fn chat_request(system_prompt: &str, history: &[Message], user_input: &str) -> Value {
+    json!({ "system": system_prompt, "history": history, "input": user_input })
+}
+
+/// Returns a stream yielding a recorded response's content and usage.
+fn replay_stream(response: LlmResponse) -> LlmStream {
+    let chunks = [
+        Ok(StreamChunk::Content(response.content)),
+        Ok(StreamChunk::Usage {
+            input_tokens: response.metrics.input_tokens,
+            output_tokens: response.metrics.output_tokens,
+        }),
+    ];
+    Box::pin(futures::stream::iter(chunks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::UnifiedProvider;
+    use fissio_monitor::TraceStore;
+
+    fn model() -> ModelConfig {
+        ModelConfig {
+            id: "m".into(),
+            name: "M".into(),
+            model: "unreachable-model".into(),
+            api_base: Some("http://127.0.0.1:9".into()),
+        }
+    }
+
+    fn recorded(node_id: &str, input: &str, content: &str) -> CallRecord {
+        let mut request = chat_request("Be brief.", &[], input);
+        request["model"] = json!("original-model");
+        let response = LlmResponse { content: content.into(), metrics: LlmMetrics::default() };
+        CallRecord {
+            run_id: "run-1".into(),
+            node_id: node_id.into(),
+            kind: CallKind::Llm,
+            request,
+            response: serde_json::to_value(response).unwrap(),
+            timestamp: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_matches_requests() {
+        let model = model();
+        let replay = Replay::new(vec![recorded("a", "hi", "from a"), recorded("b", "hi", "from b")]);
+        let calls = |node_id| Calls::new(node_id, &model, &UnifiedProvider, None, Some(&replay));
+
+        // The node's own recording is preferred, then any with the same request
+        assert_eq!(calls("b").chat("Be brief.", "hi").await.unwrap().content, "from b");
+        assert_eq!(calls("c").chat("Be brief.", "hi").await.unwrap().content, "from a");
+        let err = calls("a").chat("Be brief.", "hi").await.unwrap_err();
+        assert!(matches!(err, AgentError::Replay(_)), "{}", err);
+        assert!(calls("a").chat("Be thorough.", "hi").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replay_records_calls() {
+        let model = model();
+        let store = Arc::new(TraceStore::in_memory().unwrap());
+        let recorder = Recorder { store: store.clone(), run_id: "run-2".into() };
+        let replay = Replay::new(vec![recorded("a", "hi", "hello")]);
+        let calls = Calls::new("a", &model, &UnifiedProvider, Some(&recorder), Some(&replay));
+
+        let mut stream = calls.chat_stream("Be brief.", &[], "hi").await.unwrap();
+        let mut content = String::new();
+        while let Some(chunk) = futures::StreamExt::next(&mut stream).await {
+            if let StreamChunk::Content(text) = chunk.unwrap() {
+                content.push_str(&text);
+            }
+        }
+        assert_eq!(content, "hello");
+
+        let rerecorded = store.load_calls("run-2").unwrap();
+        assert_eq!(rerecorded.len(), 1);
+        assert_eq!(rerecorded[0].request["model"], "unreachable-model");
+        assert!(same_request(&rerecorded[0].request, &chat_request("Be brief.", &[], "hi")));
+    }
+}
@@ -0,0 +1,454 @@
+//! Router nodes: classify the input and follow the edges to the chosen targets.
+//!
+//! A router asks its model to classify the input against the targets of its
+//! outgoing edges. The model answers with the route it picks (or, with
+//! `multi_label`, every route that applies), a confidence for each, and a
+//! rationale:
+//!
+//! ```json
+//! { "routes": [{ "target": "billing", "confidence": 0.92 }], "rationale": "Asks for a refund." }
+//! ```
+//!
+//! Labels are matched to targets leniently: case, punctuation, and separators
+//! are ignored, a label that mentions exactly one target picks it, and a near
+//! miss (a typo or two) picks the closest target. A bare target name instead
+//! of JSON counts as one route with full confidence.
+//!
+//! # Configuration
+//!
+//! ```json
+//! {
+//!   "id": "triage",
+//!   "type": "router",
+//!   "prompt": "Route the support request.",
+//!   "config": { "multi_label": true, "min_confidence": 0.6, "fallback": "human_review" }
+//! }
+//! ```
+//!
+//! | Key | Value |
+//! |-----|-------|
+//! | `multi_label` | Follow every route that applies instead of only the most confident one |
+//! | `min_confidence` | Routes below this confidence (0 to 1) are dropped |
+//! | `fallback` | Target to follow when no route remains; one of the router's targets |
+//!
+//! Without `fallback`, a router left with no route follows its first target.
+//!
+//! Conditions (`when`) on a router's edges are rules checked before the model
+//! is asked: if any holds for the router's input, the router follows those
+//! edges and makes no LLM call (see [`crate::condition`]).
+//!
+//! Either way the router's output, stored in the run context and recorded as
+//! its span output, is the classification as JSON: the targets followed, the
+//! routes the model proposed with the targets they matched, the rationale,
+//! whether the fallback was taken, and whether a rule decided. The nodes it
+//! routes to receive the router's input, not the classification.
+
+use std::cmp::Ordering;
+
+use fissio_config::{Condition, ConfigError, NodeType, PipelineConfig};
+use fissio_core::AgentError;
+use serde::Serialize;
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::condition::{holds, Patterns};
+use crate::history::NodeHistory;
+use crate::recording::Calls;
+use crate::schedule::is_target;
+use crate::{extract_json, ExecutionMetrics, RunContext};
+
+/// Router settings read from a node's `config`.
+#[derive(Debug, Default)]
+pub(crate) struct RouterConfig {
+    multi_label: bool,
+    min_confidence: f64,
+    fallback: Option<String>,
+}
+
+impl RouterConfig {
+    /// Reads the settings, returning a description of the first invalid one.
+    pub(crate) fn from_value(config: &Value) -> Result<Self, String> {
+        let multi_label = match config.get("multi_label") {
+            None => false,
+            Some(value) => value.as_bool().ok_or("needs a boolean multi_label")?,
+        };
+        let min_confidence = match config.get("min_confidence") {
+            None => 0.0,
+            Some(value) => match value.as_f64() {
+                Some(c) if (0.0..=1.0).contains(&c) => c,
+                _ => return Err("needs a min_confidence from 0 to 1".to_string()),
+            },
+        };
+        let fallback = match config.get("fallback") {
+            None | Some(Value::Null) => None,
+            Some(Value::String(target)) => Some(target.clone()),
+            Some(_) => return Err("needs a target name as fallback".to_string()),
+        };
+        Ok(Self { multi_label, min_confidence, fallback })
+    }
+
+    /// Returns the target to follow when no route remains: the fallback, or
+    /// else the first target.
+    pub(crate) fn fallback_target(&self, targets: &[String]) -> Option<String> {
+        self.fallback.clone().or_else(|| targets.first().cloned())
+    }
+
+    /// Classifies a model's response: matches its routes to `targets`, drops
+    /// unmatched and unconfident ones, and picks the targets to follow.
+    fn classify(&self, response: &str, targets: &[String]) -> Classification {
+        let (proposed, rationale) = parse_routes(response, self.multi_label);
+        let routes: Vec<Route> = proposed
+            .into_iter()
+            .map(|(label, confidence)| Route { target: match_target(&label, targets).cloned(), label, confidence })
+            .collect();
+
+        let mut accepted: Vec<&Route> = routes
+            .iter()
+            .filter(|r| r.target.is_some() && r.confidence >= self.min_confidence)
+            .collect();
+        accepted.sort_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap_or(Ordering::Equal));
+        if !self.multi_label {
+            accepted.truncate(1);
+        }
+
+        let mut chosen: Vec<String> = Vec::new();
+        for target in accepted.into_iter().filter_map(|r| r.target.clone()) {
+            if !chosen.contains(&target) {
+                chosen.push(target);
+            }
+        }
+        let fallback = chosen.is_empty();
+        if fallback {
+            chosen.extend(self.fallback_target(targets));
+        }
+
+        Classification { targets: chosen, routes, rationale, fallback, rule: false }
+    }
+
+    /// Routes by the rules on the router's edges, given as each edge's
+    /// condition and targets: every edge whose rule holds for `input`
+    /// contributes its targets, in edge order, and without `multi_label` only
+    /// the first target is kept. Returns `None` if no rule matches.
+    pub(crate) fn apply_rules(
+        &self,
+        rules: &[(&Condition, Vec<&str>)],
+        patterns: &Patterns,
+        input: &str,
+        context: &RunContext,
+    ) -> Option<Classification> {
+        let mut chosen: Vec<String> = Vec::new();
+        for (_, targets) in rules.iter().filter(|(condition, _)| holds(condition, patterns, input, context)) {
+            for target in targets.iter().filter(|t| **t != "output") {
+                if !chosen.iter().any(|c| c == target) {
+                    chosen.push(target.to_string());
+                }
+            }
+        }
+        if !self.multi_label {
+            chosen.truncate(1);
+        }
+        if chosen.is_empty() {
+            return None;
+        }
+
+        let routes = chosen.iter().map(|t| Route { label: t.clone(), target: Some(t.clone()), confidence: 1.0 }).collect();
+        let rationale = format!("Matched the rule for {}", chosen.join(", "));
+        Some(Classification { targets: chosen, routes, rationale, fallback: false, rule: true })
+    }
+}
+
+/// A route proposed by the model.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Route {
+    /// The label as the model gave it.
+    label: String,
+    /// The target the label matched, if any.
+    target: Option<String>,
+    confidence: f64,
+}
+
+/// A router's classification of its input.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Classification {
+    /// Targets the router follows.
+    pub(crate) targets: Vec<String>,
+    routes: Vec<Route>,
+    rationale: String,
+    /// Set when no route was matched with enough confidence, so the fallback was followed.
+    fallback: bool,
+    /// Set when a rule on the router's edges chose the targets, without an LLM call.
+    rule: bool,
+}
+
+/// Reads the routes and rationale from a model's response. Responses that
+/// aren't JSON are read as bare labels (comma- or line-separated for
+/// `multi_label`), each with full confidence.
+fn parse_routes(response: &str, multi_label: bool) -> (Vec<(String, f64)>, String) {
+    let route = |value: &Value| -> Option<(String, f64)> {
+        match value {
+            Value::String(label) => Some((label.clone(), 1.0)),
+            Value::Object(fields) => {
+                let label = ["target", "label", "route"].iter().find_map(|k| fields.get(*k)?.as_str())?;
+                let confidence = match fields.get("confidence").and_then(Value::as_f64) {
+                    Some(confidence) => normalize_confidence(confidence)?,
+                    None => 1.0,
+                };
+                Some((label.to_string(), confidence))
+            }
+            _ => None,
+        }
+    };
+
+    match extract_json(response) {
+        Some(Value::Object(fields)) => {
+            let routes = match ["routes", "targets"].iter().find_map(|k| fields.get(*k)) {
+                Some(Value::Array(items)) => items.iter().filter_map(route).collect(),
+                Some(single) => route(single).into_iter().collect(),
+                None => route(&Value::Object(fields.clone())).into_iter().collect(),
+            };
+            let rationale = ["rationale", "reason"].iter().find_map(|k| fields.get(*k)?.as_str()).unwrap_or_default();
+            (routes, rationale.to_string())
+        }
+        Some(Value::Array(items)) => (items.iter().filter_map(route).collect(), String::new()),
+        _ => {
+            let labels: Vec<&str> = if multi_label {
+                response.split([',', '\n']).collect()
+            } else {
+                vec![response]
+            };
+            let routes = labels.into_iter().map(str::trim).filter(|l| !l.is_empty()).map(|l| (l.to_string(), 1.0)).collect();
+            (routes, String::new())
+        }
+    }
+}
+
+/// Reads a confidence given as a fraction or a percentage. A fraction
+/// slightly above 1 is taken as 1; anything else outside 0–100 is rejected.
+fn normalize_confidence(confidence: f64) -> Option<f64> {
+    match confidence {
+        c if (0.0..=1.0).contains(&c) => Some(c),
+        c if c > 1.0 && c <= 1.5 => Some(1.0),
+        c if c > 1.5 && c <= 100.0 => Some(c / 100.0),
+        _ => None,
+    }
+}
+
+/// Lowercases a label and collapses every run of other characters into `_`.
+fn normalize(label: &str) -> String {
+    let mut out = String::new();
+    for c in label.chars() {
+        if c.is_alphanumeric() {
+            out.extend(c.to_lowercase());
+        } else if !out.is_empty() && !out.ends_with('_') {
+            out.push('_');
+        }
+    }
+    out.trim_end_matches('_').to_string()
+}
+
+/// Levenshtein distance between two strings, in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Matches a label to a target, ignoring case, punctuation, and separators.
+///
+/// A label that mentions exactly one target as whole words matches it.
+/// Otherwise the closest target matches if it's the only one that close and
+/// within a quarter of its length (at least one edit) of the label.
+pub(crate) fn match_target<'t>(label: &str, targets: &'t [String]) -> Option<&'t String> {
+    let label = normalize(label);
+    if label.is_empty() {
+        return None;
+    }
+    let normalized: Vec<String> = targets.iter().map(|t| normalize(t)).collect();
+    if let Some(i) = normalized.iter().position(|t| *t == label) {
+        return Some(&targets[i]);
+    }
+
+    let words = format!("_{}_", label);
+    let mentioned: Vec<usize> = (0..targets.len())
+        .filter(|&i| !normalized[i].is_empty() && words.contains(&format!("_{}_", normalized[i])))
+        .collect();
+    if let [i] = mentioned[..] {
+        return Some(&targets[i]);
+    }
+
+    let distances: Vec<usize> = normalized.iter().map(|t| edit_distance(&label, t)).collect();
+    let best = *distances.iter().min()?;
+    let closest: Vec<usize> = (0..targets.len()).filter(|&i| distances[i] == best).collect();
+    match closest[..] {
+        [i] if best <= (normalized[i].chars().count() / 4).max(1) => Some(&targets[i]),
+        _ => None,
+    }
+}
+
+/// Checks every router's settings, and that its fallback is one of its targets.
+pub(crate) fn check_router_nodes(config: &PipelineConfig) -> Result<(), ConfigError> {
+    for node in config.nodes.iter().filter(|n| n.node_type == NodeType::Router) {
+        let invalid = |message: String| ConfigError::validation(&config.id, format!("router '{}' {}", node.id, message));
+        let router = RouterConfig::from_value(&node.config).map_err(invalid)?;
+        let Some(fallback) = router.fallback else {
+            continue;
+        };
+        if !is_target(config, &node.id, &fallback) || fallback == "output" {
+            return Err(invalid(format!("falls back to '{}', which isn't one of its targets", fallback)));
+        }
+    }
+    Ok(())
+}
+
+/// Executes a Router node: the LLM classifies the input against `targets`.
+pub(crate) async fn execute_router(
+    calls: Calls<'_>,
+    prompt: Option<&str>,
+    config: &RouterConfig,
+    input: &str,
+    history: &NodeHistory<'_>,
+    targets: &[String],
+) -> Result<(Classification, ExecutionMetrics), AgentError> {
+    let choice = if config.multi_label {
+        "every target that applies"
+    } else {
+        "the one target that fits best"
+    };
+    let routing_prompt = format!(
+        "{}\n\nYou are a routing classifier. Based on the input, choose {} from these targets: [{}]\n\n\
+        Respond with ONLY a JSON object of the form \
+        {{\"routes\": [{{\"target\": \"<target name>\", \"confidence\": <number from 0.0 to 1.0>}}], \
+        \"rationale\": \"<one sentence explaining the choice>\"}}. \
+        If no target fits, respond with an empty routes list.",
+        prompt.unwrap_or("Classify the following input and route to the appropriate target."),
+        choice,
+        targets.join(", ")
+    );
+
+    let routing_prompt = history.system_prompt(&routing_prompt);
+    let response = calls.chat_with_history(&routing_prompt, history.messages, input).await?;
+    let classification = config.classify(&response.content, targets);
+
+    if classification.fallback {
+        let labels: Vec<&str> = classification.routes.iter().map(|r| r.label.as_str()).collect();
+        warn!(
+            "║     ⚠ No route in {:?} matched {:?} with confidence >= {}, falling back to {:?}",
+            labels, targets, config.min_confidence, classification.targets
+        );
+    }
+    info!("║     Router decision: {:?} ({})", classification.targets, classification.rationale);
+
+    let mut metrics = ExecutionMetrics::default();
+    metrics.accumulate(&response.metrics);
+    metrics.iteration_count = 1;
+
+    Ok((classification, metrics))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+    use serde_json::json;
+
+    fn targets() -> Vec<String> {
+        ["billing", "tech_support", "sales"].map(String::from).to_vec()
+    }
+
+    #[test]
+    fn test_match_target() {
+        let targets = targets();
+        let matched = |label: &str| match_target(label, &targets).map(String::as_str);
+
+        assert_eq!(matched("Billing."), Some("billing"));
+        assert_eq!(matched("Tech-Support"), Some("tech_support"));
+        assert_eq!(matched("Route to billing"), Some("billing"));
+        assert_eq!(matched("biling"), Some("billing"));
+        assert_eq!(matched("tech suport"), Some("tech_support"));
+        assert_eq!(matched("billing or sales"), None);
+        assert_eq!(matched("legal"), None);
+        assert_eq!(matched(""), None);
+    }
+
+    #[test]
+    fn test_classify() {
+        let targets = targets();
+        let config = |value: Value| RouterConfig::from_value(&value).unwrap();
+        let response = r#"{"routes": [{"target": "sales", "confidence": 0.4}, {"target": "Billing", "confidence": 0.9}],
+            "rationale": "Mentions a refund."}"#;
+
+        let single = config(json!({})).classify(response, &targets);
+        assert_eq!(single.targets, ["billing"]);
+        assert_eq!(single.rationale, "Mentions a refund.");
+        assert!(!single.fallback);
+
+        let multi = config(json!({ "multi_label": true })).classify(response, &targets);
+        assert_eq!(multi.targets, ["billing", "sales"]);
+        let confident = config(json!({ "multi_label": true, "min_confidence": 0.5 })).classify(response, &targets);
+        assert_eq!(confident.targets, ["billing"]);
+
+        let unsure = config(json!({ "min_confidence": 0.95, "fallback": "tech_support" })).classify(response, &targets);
+        assert_eq!(unsure.targets, ["tech_support"]);
+        assert!(unsure.fallback);
+        let unknown = config(json!({})).classify("legal", &targets);
+        assert_eq!((unknown.targets, unknown.fallback), (vec!["billing".to_string()], true));
+
+        // Bare labels and percentages are accepted
+        assert_eq!(config(json!({ "multi_label": true })).classify("sales, billing", &targets).targets, ["sales", "billing"]);
+        let percent = config(json!({ "min_confidence": 0.8 })).classify(r#"{"target": "sales", "confidence": 85}"#, &targets);
+        assert_eq!(percent.targets, ["sales"]);
+        let over = config(json!({ "min_confidence": 0.8 })).classify(r#"{"target": "sales", "confidence": 1.5}"#, &targets);
+        assert_eq!(over.targets, ["sales"]);
+        // A confidence that is neither a fraction nor a percentage drops the route
+        let invalid = config(json!({ "fallback": "tech_support" })).classify(r#"{"target": "sales", "confidence": 150}"#, &targets);
+        assert_eq!((invalid.targets, invalid.fallback), (vec!["tech_support".to_string()], true));
+
+        assert!(RouterConfig::from_value(&json!({ "min_confidence": 2 })).is_err());
+        assert!(RouterConfig::from_value(&json!({ "fallback": 3 })).is_err());
+    }
+
+    #[test]
+    fn test_apply_rules() {
+        let refund = Condition::keywords(["refund", "invoice"]);
+        let order = Condition::regex(r"order #\d+");
+        let rules = [(&refund, vec!["billing"]), (&order, vec!["tech_support", "output"])];
+        let patterns = Patterns::from([(r"order #\d+".to_string(), Regex::new(r"order #\d+").unwrap())]);
+        let context = RunContext::default();
+        let config = |value: Value| RouterConfig::from_value(&value).unwrap();
+
+        let single = config(json!({})).apply_rules(&rules, &patterns, "Refund order #12", &context).unwrap();
+        assert_eq!(single.targets, ["billing"]);
+        assert!(single.rule);
+        let multi = config(json!({ "multi_label": true })).apply_rules(&rules, &patterns, "Refund order #12", &context).unwrap();
+        assert_eq!(multi.targets, ["billing", "tech_support"]);
+        assert_eq!(config(json!({})).apply_rules(&rules, &patterns, "Where is order #7?", &context).unwrap().targets, ["tech_support"]);
+        assert_eq!(config(json!({})).apply_rules(&rules, &patterns, "Hello", &context), None);
+    }
+
+    #[test]
+    fn test_check_router_nodes() {
+        let pipeline = |config: Value| {
+            let config = PipelineConfig::builder("route", "Route")
+                .node("triage", NodeType::Router).config(config).done()
+                .node("billing", NodeType::Llm).done()
+                .node("human", NodeType::Llm).done()
+                .edge("input", "triage")
+                .conditional_edge("triage", &["billing", "human"])
+                .build();
+            check_router_nodes(&config).map_err(|e| e.to_string())
+        };
+
+        assert!(pipeline(json!({ "fallback": "human", "min_confidence": 0.7 })).is_ok());
+        assert!(pipeline(json!({ "fallback": "sales" })).unwrap_err().contains("isn't one of its targets"));
+        assert!(pipeline(json!({ "multi_label": "yes" })).unwrap_err().contains("boolean multi_label"));
+    }
+}
@@ -0,0 +1,331 @@
+//! Dependency-aware scheduling: which nodes are ready to run.
+//!
+//! Every forward edge is split into links, one per source and target. When a
+//! node finishes, each of its links is either taken (the edge is followed to
+//! that target) or dead (a router or gate chose other targets, the edge's
+//! condition didn't hold, or the node's branch failed). A node is ready once
+//! none of its required links is still pending and at least one was taken,
+//! so a node fed by several branches waits for all of them. A node whose
+//! links all died is skipped, and its own links die with it.
+//!
+//! Optional links (edges with `"optional": true`) don't hold their target
+//! back: the target runs without them unless they're taken first. Links that
+//! close a cycle of forward edges are optional too, so a node on the cycle
+//! runs once, when it's first reached.
+//!
+//! ```json
+//! { "from": "draft", "to": "publish" },
+//! { "from": "fact_check", "to": "publish", "optional": true }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use fissio_config::PipelineConfig;
+
+use crate::branches::BranchGroups;
+
+/// One source and target of a forward edge.
+#[derive(Debug)]
+pub(crate) struct Link {
+    /// Index of the edge in the pipeline config.
+    pub(crate) edge: usize,
+    pub(crate) from: String,
+    pub(crate) to: String,
+    /// Whether the target may run without it.
+    optional: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LinkState {
+    Pending,
+    Taken,
+    Dead,
+}
+
+/// Where a node is in the run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NodeState {
+    /// Waiting for its inputs.
+    Waiting,
+    Running,
+    /// Held back to be streamed as the pipeline output.
+    Deferred,
+    Done,
+    /// Its branch failed and the edge's failure policy tolerated it.
+    Failed,
+    /// None of its links was taken.
+    Skipped,
+}
+
+/// Traversal state of a run: node and link states, plus what feedback loops
+/// and halting nodes have done to it.
+#[derive(Debug)]
+pub(crate) struct Schedule {
+    links: Vec<Link>,
+    states: Vec<LinkState>,
+    /// Node states, in config order.
+    nodes: Vec<(String, NodeState)>,
+    /// Streamable output nodes reached, in the order they became ready.
+    pub(crate) deferred: Vec<String>,
+    /// Subtask inputs of dynamic workers that have been dispatched but not started.
+    pub(crate) subtasks: HashMap<String, Vec<String>>,
+    /// Branches of tolerant parallel and dynamic edges awaiting their policy check.
+    pub(crate) branches: BranchGroups,
+    /// Critique to append to a node's input on its next run, keyed by target node ID.
+    pub(crate) feedback: HashMap<String, String>,
+    /// How many times each feedback edge (by index in the config) has been followed.
+    pub(crate) iterations: HashMap<usize, u32>,
+    /// Node that halted the run, if any; unless the run is suspended, its
+    /// output becomes the pipeline output.
+    pub(crate) halted: Option<String>,
+}
+
+impl Schedule {
+    /// Splits the pipeline's forward edges into links, all pending.
+    pub(crate) fn new(config: &PipelineConfig) -> Self {
+        let mut links = Vec::new();
+        for (index, edge) in config.edges.iter().enumerate().filter(|(_, e)| !e.is_feedback()) {
+            for from in edge.from.as_vec() {
+                for to in edge.to.as_vec() {
+                    links.push(Link { edge: index, from: from.to_string(), to: to.to_string(), optional: edge.optional });
+                }
+            }
+        }
+        for i in 0..links.len() {
+            if reaches(&links, &links[i].to, &links[i].from) {
+                links[i].optional = true;
+            }
+        }
+
+        Self {
+            states: vec![LinkState::Pending; links.len()],
+            links,
+            nodes: config.nodes.iter().map(|n| (n.id.clone(), NodeState::Waiting)).collect(),
+            deferred: Vec::new(),
+            subtasks: HashMap::new(),
+            branches: BranchGroups::default(),
+            feedback: HashMap::new(),
+            iterations: HashMap::new(),
+            halted: None,
+        }
+    }
+
+    /// Returns the state of node `node_id`, if the pipeline has it.
+    pub(crate) fn state(&self, node_id: &str) -> Option<NodeState> {
+        self.nodes.iter().find(|(id, _)| id == node_id).map(|(_, state)| *state)
+    }
+
+    /// Sets the state of node `node_id`.
+    pub(crate) fn set_state(&mut self, node_id: &str, state: NodeState) {
+        if let Some((_, current)) = self.nodes.iter_mut().find(|(id, _)| id == node_id) {
+            *current = state;
+        }
+    }
+
+    /// Returns the indices of `node_id`'s links that are still pending.
+    pub(crate) fn pending_links(&self, node_id: &str) -> Vec<usize> {
+        (0..self.links.len())
+            .filter(|&i| self.links[i].from == node_id && self.states[i] == LinkState::Pending)
+            .collect()
+    }
+
+    pub(crate) fn link(&self, index: usize) -> &Link {
+        &self.links[index]
+    }
+
+    /// Settles a pending link as taken or dead.
+    pub(crate) fn settle(&mut self, index: usize, taken: bool) {
+        self.states[index] = if taken { LinkState::Taken } else { LinkState::Dead };
+    }
+
+    /// Kills every pending link of `node_id`.
+    pub(crate) fn kill(&mut self, node_id: &str) {
+        for index in self.pending_links(node_id) {
+            self.settle(index, false);
+        }
+    }
+
+    /// Returns the waiting nodes that are ready to run, in config order, and
+    /// marks them running. Skips waiting nodes whose links all died.
+    pub(crate) fn ready(&mut self) -> Vec<String> {
+        loop {
+            let mut ready = Vec::new();
+            let mut skipped = Vec::new();
+            for (id, _) in self.nodes.iter().filter(|(_, state)| *state == NodeState::Waiting) {
+                let incoming: Vec<usize> = (0..self.links.len()).filter(|&i| self.links[i].to == *id).collect();
+                if incoming.iter().any(|&i| !self.links[i].optional && self.states[i] == LinkState::Pending) {
+                    continue;
+                }
+                if incoming.iter().any(|&i| self.states[i] == LinkState::Taken) {
+                    ready.push(id.clone());
+                } else if incoming.iter().all(|&i| self.states[i] == LinkState::Dead) {
+                    skipped.push(id.clone());
+                }
+            }
+
+            if skipped.is_empty() {
+                for id in &ready {
+                    self.set_state(id, NodeState::Running);
+                }
+                return ready;
+            }
+            // Skipping a node kills its links, which may settle others
+            for id in skipped {
+                self.set_state(&id, NodeState::Skipped);
+                self.kill(&id);
+            }
+        }
+    }
+
+    /// Makes the nodes in `body` wait to run again, with their links pending.
+    /// Nodes still running are left alone.
+    pub(crate) fn reset(&mut self, body: &HashSet<String>) {
+        for (_, state) in self.nodes.iter_mut().filter(|(id, _)| body.contains(id)) {
+            if *state != NodeState::Running {
+                *state = NodeState::Waiting;
+            }
+        }
+        for (link, state) in self.links.iter().zip(self.states.iter_mut()) {
+            if body.contains(&link.from) {
+                *state = LinkState::Pending;
+            }
+        }
+    }
+
+    /// Returns the nodes whose links to `node_id` were taken, in edge order.
+    /// The `input` endpoint is left out.
+    pub(crate) fn sources(&self, node_id: &str) -> Vec<&str> {
+        let mut sources: Vec<&str> = Vec::new();
+        for (link, state) in self.links.iter().zip(&self.states) {
+            if link.to == node_id && *state == LinkState::Taken && link.from != "input" && !sources.contains(&link.from.as_str()) {
+                sources.push(&link.from);
+            }
+        }
+        sources
+    }
+}
+
+/// Returns `true` if a forward edge leads from node `from` to `to`.
+pub(crate) fn is_target(config: &PipelineConfig, from: &str, to: &str) -> bool {
+    config.edges.iter()
+        .filter(|e| !e.is_feedback() && e.from.as_vec().contains(&from))
+        .any(|e| e.to.as_vec().contains(&to))
+}
+
+/// Returns `true` if `to` can be reached from `from` by following links.
+fn reaches(links: &[Link], from: &str, to: &str) -> bool {
+    let mut seen = HashSet::from([from]);
+    let mut stack = vec![from];
+    while let Some(id) = stack.pop() {
+        if id == to {
+            return true;
+        }
+        for link in links.iter().filter(|l| l.from == id) {
+            if seen.insert(&link.to) {
+                stack.push(&link.to);
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fissio_config::NodeType;
+
+    /// Settles every pending link of `node_id` as taken if `take` says so.
+    fn finish(schedule: &mut Schedule, node_id: &str, take: impl Fn(&str) -> bool) {
+        if node_id != "input" {
+            schedule.set_state(node_id, NodeState::Done);
+        }
+        for index in schedule.pending_links(node_id) {
+            let taken = take(&schedule.link(index).to);
+            schedule.settle(index, taken);
+        }
+    }
+
+    #[test]
+    fn test_fan_in_waits_for_every_branch() {
+        let config = PipelineConfig::builder("join", "Join")
+            .node("a", NodeType::Llm).done()
+            .node("b1", NodeType::Llm).done()
+            .node("b2", NodeType::Llm).done()
+            .node("join", NodeType::Llm).done()
+            .edge("input", "a")
+            .edge("input", "b1")
+            .edge("b1", "b2")
+            .edge("a", "join")
+            .edge("b2", "join")
+            .edge("join", "output")
+            .build();
+        let mut schedule = Schedule::new(&config);
+
+        finish(&mut schedule, "input", |_| true);
+        assert_eq!(schedule.ready(), ["a", "b1"]);
+        finish(&mut schedule, "a", |_| true);
+        assert!(schedule.ready().is_empty());
+        finish(&mut schedule, "b1", |_| true);
+        assert_eq!(schedule.ready(), ["b2"]);
+        finish(&mut schedule, "b2", |_| true);
+        assert_eq!(schedule.ready(), ["join"]);
+        assert_eq!(schedule.sources("join"), ["a", "b2"]);
+    }
+
+    #[test]
+    fn test_dead_paths_are_skipped() {
+        let config = PipelineConfig::builder("route", "Route")
+            .node("triage", NodeType::Router).done()
+            .node("billing", NodeType::Llm).done()
+            .node("refund", NodeType::Llm).done()
+            .node("tech", NodeType::Llm).done()
+            .node("reply", NodeType::Llm).done()
+            .edge("input", "triage")
+            .conditional_edge("triage", &["billing", "tech"])
+            .edge("billing", "refund")
+            .edge("refund", "reply")
+            .edge("tech", "reply")
+            .build();
+        let mut schedule = Schedule::new(&config);
+
+        finish(&mut schedule, "input", |_| true);
+        assert_eq!(schedule.ready(), ["triage"]);
+        finish(&mut schedule, "triage", |to| to == "tech");
+        assert_eq!(schedule.ready(), ["tech"]);
+        assert_eq!(schedule.state("refund"), Some(NodeState::Skipped));
+        finish(&mut schedule, "tech", |_| true);
+        assert_eq!(schedule.ready(), ["reply"]);
+        assert_eq!(schedule.sources("reply"), ["tech"]);
+    }
+
+    #[test]
+    fn test_optional_links_and_cycles() {
+        let mut config = PipelineConfig::builder("optional", "Optional")
+            .node("draft", NodeType::Llm).done()
+            .node("check", NodeType::Llm).done()
+            .node("publish", NodeType::Llm).done()
+            .node("revise", NodeType::Llm).done()
+            .edge("input", "draft")
+            .edge("input", "check")
+            .edge("draft", "publish")
+            .edge("check", "publish")
+            .edge("publish", "revise")
+            .edge("revise", "publish")
+            .build();
+        config.edges[3].optional = true;
+        let mut schedule = Schedule::new(&config);
+
+        finish(&mut schedule, "input", |_| true);
+        assert_eq!(schedule.ready(), ["draft", "check"]);
+        // The link from check is optional, and the one from revise closes a cycle
+        finish(&mut schedule, "draft", |_| true);
+        assert_eq!(schedule.ready(), ["publish"]);
+        finish(&mut schedule, "publish", |_| true);
+        assert_eq!(schedule.ready(), ["revise"]);
+
+        // A reset body waits again; links into it from outside keep their state
+        let body = HashSet::from(["draft".to_string(), "publish".to_string()]);
+        schedule.reset(&body);
+        assert_eq!(schedule.ready(), ["draft"]);
+    }
+}
@@ -0,0 +1,186 @@
+//! Structured outputs: nodes whose output must match a JSON Schema.
+//!
+//! An `llm` or `worker` node with an `output_schema` is asked for JSON
+//! matching it. The schema is appended to the node's system prompt, and
+//! providers that can constrain their output (OpenAI-compatible APIs) also
+//! get it as the response format. The response is parsed, tolerating prose
+//! or code fences around the JSON, and validated. If it doesn't match, the
+//! node is re-prompted with the validation errors, without tools, for up to
+//! [`SCHEMA_ATTEMPTS`] responses in all.
+//!
+//! ```json
+//! {
+//!   "id": "triage",
+//!   "type": "llm",
+//!   "prompt": "Triage the support ticket.",
+//!   "output_schema": {
+//!     "type": "object",
+//!     "properties": { "team": { "enum": ["billing", "tech"] }, "urgent": { "type": "boolean" } },
+//!     "required": ["team", "urgent"]
+//!   }
+//! }
+//! ```
+//!
+//! The node's output is the parsed value, stored as JSON in the run context
+//! and passed downstream as compact JSON text. Prompt templates address its
+//! fields as `{{nodes.triage.team}}`, and a router with
+//! `"config": { "field": "team" }` routes on the field's value instead of
+//! asking an LLM.
+
+use fissio_config::{ConfigError, NodeConfig, NodeType, PipelineConfig};
+use jsonschema::JSONSchema;
+use serde_json::Value;
+
+use crate::extract_json;
+
+/// How many responses a node gets to match its schema.
+pub(crate) const SCHEMA_ATTEMPTS: usize = 3;
+
+/// A node's compiled output schema.
+pub(crate) struct OutputSchema<'a> {
+    schema: &'a Value,
+    compiled: JSONSchema,
+}
+
+impl<'a> OutputSchema<'a> {
+    /// Compiles a schema, returning a description of why it's invalid.
+    pub(crate) fn compile(schema: &'a Value) -> Result<Self, String> {
+        let compiled = JSONSchema::compile(schema).map_err(|e| e.to_string())?;
+        Ok(Self { schema, compiled })
+    }
+
+    /// Returns the schema itself.
+    pub(crate) fn schema(&self) -> &'a Value {
+        self.schema
+    }
+
+    /// Appends an instruction to respond with matching JSON to a system prompt.
+    pub(crate) fn system_prompt(&self, prompt: &str) -> String {
+        let schema = serde_json::to_string_pretty(self.schema).unwrap_or_default();
+        format!("{}\n\nRespond with only a JSON value matching this JSON Schema:\n{}", prompt, schema).trim_start().to_string()
+    }
+
+    /// Parses a response and validates it, returning the validation errors
+    /// as one line if it doesn't match.
+    pub(crate) fn parse(&self, content: &str) -> Result<Value, String> {
+        let value = extract_json(content).ok_or_else(|| "the response is not JSON".to_string())?;
+        if let Err(errors) = self.compiled.validate(&value) {
+            let errors: Vec<String> = errors
+                .map(|e| match e.instance_path.to_string() {
+                    path if path.is_empty() => e.to_string(),
+                    path => format!("{}: {}", path, e),
+                })
+                .collect();
+            return Err(errors.join("; "));
+        }
+        Ok(value)
+    }
+}
+
+/// Builds the input of a re-prompt after `content` failed validation.
+pub(crate) fn repair_input(input: &str, content: &str, error: &str) -> String {
+    format!(
+        "{}\n\nYour previous response:\n{}\n\nIt doesn't match the required JSON Schema: {}\n\
+        Respond again with only the corrected JSON.",
+        input, content, error
+    )
+}
+
+/// Checks that output schemas are set only on `llm` and `worker` nodes and compile.
+pub(crate) fn check_output_schemas(config: &PipelineConfig) -> Result<(), ConfigError> {
+    for node in &config.nodes {
+        let Some(schema) = &node.output_schema else {
+            continue;
+        };
+        let invalid = |message: String| ConfigError::validation(&config.id, format!("output_schema of node '{}' {}", node.id, message));
+        if !matches!(node.node_type, NodeType::Llm | NodeType::Worker) {
+            return Err(invalid(format!("isn't supported on {} nodes", node.node_type)));
+        }
+        OutputSchema::compile(schema).map_err(|e| invalid(format!("is invalid: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// Returns `false` if `node` has an output schema that rules out `field` as a
+/// top-level field of its output.
+pub(crate) fn has_field(node: &NodeConfig, field: &str) -> bool {
+    let properties = node.output_schema.as_ref().and_then(|s| s.get("properties")).and_then(Value::as_object);
+    let closed = node.output_schema.as_ref().and_then(|s| s.get("additionalProperties")) == Some(&Value::Bool(false));
+    match properties {
+        Some(properties) if closed => properties.contains_key(field),
+        _ => true,
+    }
+}
+
+/// Looks up a field path in a JSON value; numeric segments index arrays.
+pub(crate) fn lookup<'v, S: AsRef<str>>(value: &'v Value, path: &[S]) -> Option<&'v Value> {
+    path.iter().try_fold(value, |value, segment| {
+        let segment = segment.as_ref();
+        match value {
+            Value::Object(fields) => fields.get(segment),
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        }
+    })
+}
+
+/// Renders a field for a prompt: strings as-is, other values as JSON.
+pub(crate) fn field_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Returns the field path a router routes on, if it routes on a field.
+pub(crate) fn route_field(node: &NodeConfig) -> Option<&str> {
+    node.config.get("field").and_then(|v| v.as_str())
+}
+
+/// Returns the target named by the `path` field of a router's JSON input
+/// (case-insensitively), or `None` if the field is missing or names no target.
+pub(crate) fn route_on_field(input: &str, path: &str, targets: &[String]) -> Option<String> {
+    let value = extract_json(input)?;
+    let segments: Vec<&str> = path.split('.').collect();
+    let decision = field_text(lookup(&value, &segments)?).to_lowercase();
+    targets.iter().find(|t| t.to_lowercase() == decision).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse() {
+        let schema = json!({
+            "type": "object",
+            "properties": { "team": { "enum": ["billing", "tech"] }, "urgent": { "type": "boolean" } },
+            "required": ["team", "urgent"]
+        });
+        let schema = OutputSchema::compile(&schema).unwrap();
+
+        let value = schema.parse("Here you go:\n```json\n{\"team\": \"tech\", \"urgent\": true}\n```").unwrap();
+        assert_eq!(value, json!({ "team": "tech", "urgent": true }));
+        assert_eq!(schema.parse("no idea").unwrap_err(), "the response is not JSON");
+        let err = schema.parse(r#"{"team": "sales", "urgent": "yes"}"#).unwrap_err();
+        assert!(err.contains("/team") && err.contains("/urgent"), "{}", err);
+        assert!(schema.parse(r#"{"team": "tech"}"#).unwrap_err().contains("\"urgent\" is a required property"));
+
+        assert!(OutputSchema::compile(&json!({ "type": "nonsense" })).is_err());
+    }
+
+    #[test]
+    fn test_lookup_and_routing() {
+        let value = json!({ "ticket": { "tags": ["refund", "late"], "team": "Billing" } });
+        assert_eq!(lookup(&value, &["ticket", "tags", "1"]), Some(&json!("late")));
+        assert_eq!(lookup(&value, &["ticket", "missing"]), None);
+        assert_eq!(field_text(lookup(&value, &["ticket", "tags"]).unwrap()), r#"["refund","late"]"#);
+
+        let targets = ["billing".to_string(), "tech".to_string()];
+        let input = value.to_string();
+        assert_eq!(route_on_field(&input, "ticket.team", &targets).as_deref(), Some("billing"));
+        assert_eq!(route_on_field(&input, "ticket.tags", &targets), None);
+        assert_eq!(route_on_field("not json", "team", &targets), None);
+    }
+}
@@ -0,0 +1,207 @@
+//! Pipeline nodes: run another pipeline as a single node.
+//!
+//! A `pipeline` node runs the pipeline with ID `config.pipeline`, looked up
+//! in the registry attached with
+//! [`PipelineEngine::with_pipelines`](crate::PipelineEngine::with_pipelines),
+//! as a nested run. The nested run's input is the node's input, or
+//! `config.input` rendered as a prompt template against the parent run; its
+//! output becomes the node's output.
+//!
+//! ```json
+//! {
+//!   "id": "research",
+//!   "type": "pipeline",
+//!   "config": { "pipeline": "research-summarize", "input": "Research this for {{vars.audience}}: {{input}}" }
+//! }
+//! ```
+//!
+//! The nested run shares the parent's models, LLM provider, tools, custom
+//! executors, rate limiter, recording, and replay, and sees the same
+//! conversation history. Its spans are recorded in the parent's trace, and
+//! its events sent on the parent's channel, with node IDs under the pipeline
+//! node's, e.g. `research/summarize`. Node model overrides keyed the same way
+//! apply to its nodes.
+//!
+//! Before any node runs, the engine rejects pipeline nodes that name an
+//! unknown pipeline, pipelines that reach themselves through pipeline nodes,
+//! nesting deeper than [`MAX_PIPELINE_DEPTH`], and nested pipelines with
+//! approval nodes, which couldn't suspend the parent run. `map` nodes that
+//! run a pipeline per item get the same checks.
+
+use std::sync::Arc;
+
+use fissio_config::{ConfigError, NodeConfig, NodeType, PipelineConfig, PresetRegistry};
+use fissio_core::AgentError;
+use fissio_llm::StreamChunk;
+use fissio_monitor::{MetricsCollector, NodeMetrics, PipelineMetrics};
+use futures::StreamExt;
+
+use crate::EngineOutput;
+
+/// How many pipelines deep pipeline nodes may nest.
+pub(crate) const MAX_PIPELINE_DEPTH: usize = 4;
+
+/// Returns the pipeline ID configured on a pipeline node.
+pub(crate) fn pipeline_ref(node: &NodeConfig) -> Option<&str> {
+    node.config.get("pipeline").and_then(|v| v.as_str())
+}
+
+/// Returns the input mapping template configured on a pipeline node.
+pub(crate) fn input_mapping(node: &NodeConfig) -> Option<&str> {
+    if node.node_type != NodeType::Pipeline {
+        return None;
+    }
+    node.config.get("input").and_then(|v| v.as_str())
+}
+
+/// Returns the key a pipeline node's compiled input mapping is stored under.
+pub(crate) fn input_key(node_id: &str) -> String {
+    format!("{}.input", node_id)
+}
+
+/// Checks every pipeline node reachable from `config`, which runs nested
+/// inside the pipelines in `ancestry` (outermost first).
+pub(crate) fn check_pipeline_nodes(
+    config: &PipelineConfig,
+    ancestry: &[String],
+    pipelines: &PresetRegistry,
+) -> Result<(), ConfigError> {
+    let mut stack = ancestry.to_vec();
+    stack.push(config.id.clone());
+    check_nested(config, &mut stack, pipelines)
+}
+
+/// Checks the pipeline nodes of `config`, the last pipeline on `stack`, and
+/// the pipelines they reference. Map nodes that run a pipeline are checked
+/// like pipeline nodes.
+fn check_nested(config: &PipelineConfig, stack: &mut Vec<String>, pipelines: &PresetRegistry) -> Result<(), ConfigError> {
+    for node in config.nodes.iter() {
+        let invalid = |message: String| ConfigError::validation(&config.id, message);
+        let id = match (node.node_type, pipeline_ref(node)) {
+            (NodeType::Pipeline | NodeType::Map, Some(id)) => id,
+            (NodeType::Pipeline, None) => {
+                return Err(invalid(format!("pipeline node '{}' has no config.pipeline", node.id)));
+            }
+            _ => continue,
+        };
+        let Some(nested) = pipelines.get(id) else {
+            return Err(invalid(format!("pipeline node '{}' references unknown pipeline '{}'", node.id, id)));
+        };
+        if stack.iter().any(|p| p == id) {
+            let cycle = stack.iter().skip_while(|p| *p != id).cloned().collect::<Vec<_>>().join(" -> ");
+            return Err(invalid(format!("pipeline node '{}' creates a cycle: {} -> {}", node.id, cycle, id)));
+        }
+        if stack.len() > MAX_PIPELINE_DEPTH {
+            return Err(invalid(format!(
+                "pipeline node '{}' nests pipelines more than {} deep",
+                node.id, MAX_PIPELINE_DEPTH
+            )));
+        }
+        if let Some(approval) = nested.nodes.iter().find(|n| n.node_type == NodeType::Approval) {
+            return Err(invalid(format!(
+                "pipeline node '{}' runs '{}', whose approval node '{}' can't suspend a nested run",
+                node.id, id, approval.id
+            )));
+        }
+
+        stack.push(id.to_string());
+        check_nested(nested, stack, pipelines)?;
+        stack.pop();
+    }
+    Ok(())
+}
+
+/// Returns the complete output of a nested run, consuming a streamed one.
+pub(crate) async fn collect_output(pipeline_id: &str, output: EngineOutput) -> Result<String, AgentError> {
+    match output {
+        EngineOutput::Complete(content) => Ok(content),
+        EngineOutput::Stream(mut stream) => {
+            let mut content = String::new();
+            while let Some(chunk) = stream.next().await {
+                if let StreamChunk::Content(text) = chunk? {
+                    content.push_str(&text);
+                }
+            }
+            Ok(content)
+        }
+        EngineOutput::Suspended(_) => Err(AgentError::WorkerFailed(format!("pipeline '{}' suspended", pipeline_id))),
+    }
+}
+
+/// Records a nested run's metrics and spans with the parent's collector,
+/// prefixing node IDs with the pipeline node's.
+pub(crate) struct NestedCollector {
+    pub(crate) parent: Arc<dyn MetricsCollector>,
+    /// The pipeline node's ID, followed by a `/`.
+    pub(crate) prefix: String,
+}
+
+impl MetricsCollector for NestedCollector {
+    fn record(&self, metrics: NodeMetrics) {
+        let node_id = format!("{}{}", self.prefix, metrics.node_id);
+        self.parent.record(NodeMetrics { node_id, ..metrics });
+    }
+
+    fn record_span(
+        &self,
+        node_id: &str,
+        node_type: &str,
+        start_time: i64,
+        end_time: i64,
+        input: &str,
+        output: &str,
+        metrics: &NodeMetrics,
+    ) {
+        let node_id = format!("{}{}", self.prefix, node_id);
+        let metrics = NodeMetrics { node_id: node_id.clone(), ..metrics.clone() };
+        self.parent.record_span(&node_id, node_type, start_time, end_time, input, output, &metrics);
+    }
+
+    fn flush(&self) -> PipelineMetrics {
+        self.parent.flush()
+    }
+
+    fn reset(&self) {
+        // The parent run's metrics aren't the nested run's to clear
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pipeline(id: &str, nested: &[&str]) -> PipelineConfig {
+        let mut builder = PipelineConfig::builder(id, id);
+        for nested in nested {
+            builder = builder
+                .node(format!("run_{}", nested), NodeType::Pipeline)
+                .config(serde_json::json!({ "pipeline": nested }))
+                .done();
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn test_check_pipeline_nodes() {
+        let mut pipelines = PresetRegistry::new();
+        for config in [pipeline("a", &["b"]), pipeline("b", &["c", "c"]), pipeline("c", &[]), pipeline("d", &["a"])] {
+            pipelines.register(config);
+        }
+        let check = |config: &PipelineConfig, pipelines: &PresetRegistry| {
+            check_pipeline_nodes(config, &[], pipelines).map_err(|e| e.to_string())
+        };
+
+        assert!(check(&pipeline("root", &["a", "c"]), &pipelines).is_ok());
+        assert!(check(&pipeline("root", &["missing"]), &pipelines).unwrap_err().contains("unknown pipeline 'missing'"));
+
+        pipelines.register(pipeline("c", &["a"]));
+        let err = check(&pipeline("root", &["a"]), &pipelines).unwrap_err();
+        assert!(err.contains("creates a cycle: a -> b -> c -> a"), "{}", err);
+
+        pipelines.register(pipeline("c", &["d"]));
+        pipelines.register(pipeline("d", &["e"]));
+        pipelines.register(pipeline("e", &[]));
+        let err = check(&pipeline("root", &["a"]), &pipelines).unwrap_err();
+        assert!(err.contains("more than 4 deep"), "{}", err);
+    }
+}
@@ -0,0 +1,269 @@
+//! Prompt templates: node prompts that reference run data.
+//!
+//! A prompt containing `{{ ... }}` placeholders is compiled before the run
+//! starts and rendered each time the node executes:
+//!
+//! | Placeholder | Value |
+//! |-------------|-------|
+//! | `{{input}}` | The original user input |
+//! | `{{nodes.<id>}}` | The latest output of node `<id>` (empty if it hasn't run) |
+//! | `{{nodes.<id>.<field>}}` | A field of node `<id>`'s JSON output, e.g. `{{nodes.triage.team}}` or `{{nodes.search.results.0.url}}` (empty if missing) |
+//! | `{{history}}` | The conversation so far as a `User:` / `Assistant:` transcript |
+//! | `{{vars.<name>}}` | A pipeline-level variable from `variables` |
+//!
+//! ```json
+//! {
+//!   "variables": { "tone": "friendly" },
+//!   "nodes": [{
+//!     "id": "writer",
+//!     "type": "llm",
+//!     "prompt": "Answer in a {{vars.tone}} tone.\n\nQuestion: {{input}}\n\nResearch:\n{{nodes.researcher}}"
+//!   }]
+//! }
+//! ```
+//!
+//! Unknown node IDs and variables, fields a node's `output_schema` rules out,
+//! unknown placeholder names, and unclosed braces are reported by
+//! [`compile_templates`] before any node runs.
+
+use std::collections::HashMap;
+
+use fissio_config::{ConfigError, NodeConfig, PipelineConfig};
+use fissio_core::Message;
+use serde_json::Value;
+
+use crate::history::format_transcript;
+use crate::schema::{field_text, has_field, lookup};
+use crate::subpipeline::{input_key, input_mapping};
+use crate::RunContext;
+
+/// A value a template can reference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Reference {
+    Input,
+    History,
+    Node(String),
+    /// A field path into a node's JSON output.
+    Field(String, Vec<String>),
+    Var(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    Ref(Reference),
+}
+
+/// A parsed prompt template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Template {
+    segments: Vec<Segment>,
+}
+
+/// Run data a template is rendered against.
+pub(crate) struct TemplateContext<'a> {
+    pub(crate) input: &'a str,
+    pub(crate) context: &'a RunContext,
+    pub(crate) history: &'a [Message],
+    pub(crate) variables: &'a HashMap<String, String>,
+}
+
+impl Template {
+    /// Parses a template, returning a description of the first syntax error.
+    fn parse(source: &str) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        let mut rest = source;
+
+        while let Some(open) = rest.find("{{") {
+            if open > 0 {
+                segments.push(Segment::Text(rest[..open].to_string()));
+            }
+            let after = &rest[open + 2..];
+            let close = after
+                .find("}}")
+                .ok_or_else(|| format!("unclosed '{{{{' at byte {}", source.len() - rest.len() + open))?;
+            segments.push(Segment::Ref(parse_reference(after[..close].trim())?));
+            rest = &after[close + 2..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Text(rest.to_string()));
+        }
+
+        Ok(Self { segments })
+    }
+
+    fn references(&self) -> impl Iterator<Item = &Reference> {
+        self.segments.iter().filter_map(|s| match s {
+            Segment::Ref(r) => Some(r),
+            Segment::Text(_) => None,
+        })
+    }
+
+    /// Renders the template; nodes that haven't produced output render as empty.
+    pub(crate) fn render(&self, ctx: &TemplateContext<'_>) -> String {
+        let mut out = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(text) => out.push_str(text),
+                Segment::Ref(Reference::Input) => out.push_str(ctx.input),
+                Segment::Ref(Reference::History) => out.push_str(&format_transcript(ctx.history)),
+                Segment::Ref(Reference::Node(id)) => out.push_str(&ctx.context.text(id).unwrap_or_default()),
+                Segment::Ref(Reference::Field(id, path)) => {
+                    // Outputs stored as text (e.g. an LLM that answered in JSON unprompted) are parsed
+                    let field = match ctx.context.output(id) {
+                        Some(Value::String(text)) => serde_json::from_str(text).ok().and_then(|v| lookup(&v, path).cloned()),
+                        Some(value) => lookup(value, path).cloned(),
+                        None => None,
+                    };
+                    if let Some(field) = field {
+                        out.push_str(&field_text(&field));
+                    }
+                }
+                Segment::Ref(Reference::Var(name)) => {
+                    out.push_str(ctx.variables.get(name).map(String::as_str).unwrap_or_default())
+                }
+            }
+        }
+        out
+    }
+}
+
+fn parse_reference(name: &str) -> Result<Reference, String> {
+    match name.split_once('.') {
+        None if name == "input" => Ok(Reference::Input),
+        None if name == "history" => Ok(Reference::History),
+        Some(("nodes", id)) if !id.is_empty() => match id.split_once('.') {
+            None => Ok(Reference::Node(id.to_string())),
+            Some((id, path)) if !id.is_empty() && path.split('.').all(|f| !f.is_empty()) => {
+                Ok(Reference::Field(id.to_string(), path.split('.').map(String::from).collect()))
+            }
+            Some(_) => Err(format!("malformed field path in '{{{{{}}}}}'", name)),
+        },
+        Some(("vars", var)) if !var.is_empty() => Ok(Reference::Var(var.to_string())),
+        _ => Err(format!("unknown placeholder '{{{{{}}}}}'", name)),
+    }
+}
+
+/// Compiles every templated node prompt in a pipeline, keyed by node ID, and
+/// the input mapping of every `pipeline` node, keyed by [`input_key`].
+///
+/// Prompts without placeholders are left out. Fails on the first syntax
+/// error or reference to a node or variable the pipeline doesn't define.
+pub(crate) fn compile_templates(config: &PipelineConfig) -> Result<HashMap<String, Template>, ConfigError> {
+    let mut templates = HashMap::new();
+
+    for node in &config.nodes {
+        if let Some(prompt) = node.prompt.as_deref().filter(|p| p.contains("{{")) {
+            templates.insert(node.id.clone(), compile(config, node, "prompt", prompt)?);
+        }
+        if let Some(input) = input_mapping(node) {
+            templates.insert(input_key(&node.id), compile(config, node, "input", input)?);
+        }
+    }
+
+    Ok(templates)
+}
+
+/// Compiles one template of `node`, checking its node and variable references.
+fn compile(config: &PipelineConfig, node: &NodeConfig, field: &str, source: &str) -> Result<Template, ConfigError> {
+    let invalid = |message: String| {
+        ConfigError::validation(&config.id, format!("{} of node '{}': {}", field, node.id, message))
+    };
+
+    let template = Template::parse(source).map_err(invalid)?;
+    for reference in template.references() {
+        match reference {
+            Reference::Node(id) | Reference::Field(id, _) => {
+                let Some(target) = config.nodes.iter().find(|n| &n.id == id) else {
+                    return Err(invalid(format!("unknown node '{}'", id)));
+                };
+                if let Reference::Field(_, path) = reference {
+                    if !has_field(target, &path[0]) {
+                        return Err(invalid(format!("node '{}' has no output field '{}'", id, path[0])));
+                    }
+                }
+            }
+            Reference::Var(name) if !config.variables.contains_key(name) => {
+                return Err(invalid(format!("unknown variable '{}'", name)));
+            }
+            _ => {}
+        }
+    }
+    Ok(template)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NodeOutput;
+    use fissio_config::NodeType;
+
+    fn pipeline(prompt: &str) -> PipelineConfig {
+        PipelineConfig::builder("test", "Test")
+            .variable("tone", "friendly")
+            .node("researcher", NodeType::Llm).done()
+            .node("writer", NodeType::Llm).prompt(prompt).done()
+            .build()
+    }
+
+    #[test]
+    fn test_render() {
+        let templates = compile_templates(&pipeline(
+            "Be {{ vars.tone }}.\nQ: {{input}}\nNotes: {{nodes.researcher}}\n{{history}}",
+        ))
+        .unwrap();
+        assert_eq!(templates.len(), 1);
+
+        let mut context = RunContext::default();
+        context.store("researcher", &NodeOutput::new("Rayleigh scattering"));
+        let history = [Message::user("Hi"), Message::assistant("Hello!")];
+        let variables = HashMap::from([("tone".to_string(), "friendly".to_string())]);
+        let ctx = TemplateContext { input: "Why is the sky blue?", context: &context, history: &history, variables: &variables };
+
+        assert_eq!(
+            templates["writer"].render(&ctx),
+            "Be friendly.\nQ: Why is the sky blue?\nNotes: Rayleigh scattering\nUser: Hi\n\nAssistant: Hello!"
+        );
+
+        let ctx = TemplateContext { input: "", context: &RunContext::default(), history: &[], variables: &variables };
+        assert_eq!(templates["writer"].render(&ctx), "Be friendly.\nQ: \nNotes: \n");
+    }
+
+    #[test]
+    fn test_render_fields() {
+        let schema = serde_json::json!({ "type": "object", "properties": { "team": {}, "tags": {} }, "additionalProperties": false });
+        let config = |prompt: &str| {
+            PipelineConfig::builder("test", "Test")
+                .node("triage", NodeType::Llm).output_schema(schema.clone()).done()
+                .node("writer", NodeType::Llm).prompt(prompt).done()
+                .build()
+        };
+        let templates = compile_templates(&config("{{nodes.triage.team}} / {{nodes.triage.tags.1}} / {{nodes.triage.tags}}")).unwrap();
+
+        let mut context = RunContext::default();
+        context.store("triage", &NodeOutput::json(serde_json::json!({ "team": "billing", "tags": ["refund", "late"] })));
+        let variables = HashMap::new();
+        let ctx = TemplateContext { input: "", context: &context, history: &[], variables: &variables };
+        assert_eq!(templates["writer"].render(&ctx), r#"billing / late / ["refund","late"]"#);
+
+        context.store("triage", &NodeOutput::new(r#"{"team":"tech","tags":[]}"#));
+        let ctx = TemplateContext { input: "", context: &context, history: &[], variables: &variables };
+        assert_eq!(templates["writer"].render(&ctx), "tech /  / []");
+
+        let err = compile_templates(&config("{{nodes.triage.urgent}}")).unwrap_err().to_string();
+        assert!(err.contains("node 'triage' has no output field 'urgent'"), "{}", err);
+    }
+
+    #[test]
+    fn test_compile_errors() {
+        let error = |prompt: &str| compile_templates(&pipeline(prompt)).unwrap_err().to_string();
+
+        assert!(error("{{nodes.editor}}").contains("unknown node 'editor'"));
+        assert!(error("{{vars.audience}}").contains("unknown variable 'audience'"));
+        assert!(error("{{question}}").contains("unknown placeholder '{{question}}'"));
+        assert!(error("{{nodes.researcher.}}").contains("malformed field path"));
+        assert!(error("{{nodes.editor.notes}}").contains("unknown node 'editor'"));
+        assert!(error("Q: {{input").contains("unclosed '{{' at byte 3"));
+        assert!(compile_templates(&pipeline("No placeholders")).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,236 @@
+//! Concurrency limits and token budgets.
+//!
+//! A pipeline's `max_concurrency` caps how many of its nodes execute at once
+//! within a run, so a wide parallel or dynamic fan-out queues its branches
+//! instead of starting them all together:
+//!
+//! ```json
+//! { "id": "research", "max_concurrency": 3, "nodes": [...], "edges": [...] }
+//! ```
+//!
+//! Limits per model and per provider ([`RateLimits`]) are enforced by a
+//! [`RateLimiter`]. Attach the same limiter to every engine with
+//! [`PipelineEngine::with_rate_limiter`](crate::PipelineEngine::with_rate_limiter)
+//! and the limits hold across all concurrent runs:
+//!
+//! ```rust,ignore
+//! let limiter = Arc::new(RateLimiter::new(&RateLimits::from_file("rate_limits.json")?));
+//! let engine = PipelineEngine::new(config, models, default_model, HashMap::new())
+//!     .with_rate_limiter(Arc::clone(&limiter));
+//! ```
+//!
+//! Each node attempt takes a slot from its model's and its provider's
+//! `max_concurrency` and keeps it until the attempt finishes (for a streamed
+//! output, until the stream ends). An attempt doesn't start while the tokens
+//! used over the trailing minute have reached a `tokens_per_minute` budget;
+//! its own usage counts against the budget once it finishes.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use fissio_config::{ConfigError, PipelineConfig, RateLimit, RateLimits};
+use fissio_core::ModelConfig;
+use fissio_llm::UnifiedLlmClient;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+use tracing::info;
+
+/// Window over which `tokens_per_minute` is measured.
+const BUDGET_WINDOW: Duration = Duration::from_secs(60);
+
+/// Enforces [`RateLimits`] on the node attempts of every engine it's attached to.
+///
+/// A `max_concurrency` of 0 is treated as 1.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    models: HashMap<String, Arc<Limiter>>,
+    providers: HashMap<String, Arc<Limiter>>,
+}
+
+impl RateLimiter {
+    /// Creates a limiter enforcing `limits`.
+    pub fn new(limits: &RateLimits) -> Self {
+        Self::with_window(limits, BUDGET_WINDOW)
+    }
+
+    fn with_window(limits: &RateLimits, window: Duration) -> Self {
+        let build = |limits: &HashMap<String, RateLimit>| {
+            limits
+                .iter()
+                .map(|(key, limit)| (key.clone(), Arc::new(Limiter::new(key, limit, window))))
+                .collect()
+        };
+        Self { models: build(&limits.models), providers: build(&limits.providers) }
+    }
+
+    /// Waits until an attempt on `model` fits within its provider's and its
+    /// own limits, and returns the slots it holds.
+    pub(crate) async fn acquire(&self, model: &ModelConfig) -> Permit {
+        let client = UnifiedLlmClient::new(&model.model, model.api_base.as_deref());
+        let limiters: Vec<Arc<Limiter>> = [self.providers.get(client.provider()), self.models.get(&model.id)]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect();
+
+        for limiter in &limiters {
+            limiter.wait_for_budget().await;
+        }
+        let mut slots = Vec::new();
+        for limiter in &limiters {
+            if let Some(slot) = limiter.take_slot().await {
+                slots.push(slot);
+            }
+        }
+        Permit { _slots: slots, limiters }
+    }
+}
+
+/// Concurrency slots and token budget for one model or provider.
+#[derive(Debug)]
+struct Limiter {
+    key: String,
+    slots: Option<Arc<Semaphore>>,
+    tokens_per_window: Option<u64>,
+    window: Duration,
+    /// Tokens used by finished attempts, oldest first.
+    usage: Mutex<VecDeque<(Instant, u64)>>,
+}
+
+impl Limiter {
+    fn new(key: &str, limit: &RateLimit, window: Duration) -> Self {
+        Self {
+            key: key.to_string(),
+            slots: limit.max_concurrency.map(|n| Arc::new(Semaphore::new(n.max(1)))),
+            tokens_per_window: limit.tokens_per_minute,
+            window,
+            usage: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Waits for a concurrency slot, if this limiter has any.
+    async fn take_slot(&self) -> Option<OwnedSemaphorePermit> {
+        let slots = self.slots.as_ref()?;
+        if let Ok(slot) = Arc::clone(slots).try_acquire_owned() {
+            return Some(slot);
+        }
+        info!("║     ⏳ Waiting for a free slot on '{}'", self.key);
+        // The semaphore is never closed
+        Arc::clone(slots).acquire_owned().await.ok()
+    }
+
+    /// Waits until the tokens used over the trailing window are below the budget.
+    async fn wait_for_budget(&self) {
+        let Some(budget) = self.tokens_per_window else {
+            return;
+        };
+        loop {
+            let retry_at = {
+                let mut usage = self.usage.lock().unwrap();
+                let now = Instant::now();
+                while usage.front().is_some_and(|(at, _)| now.duration_since(*at) >= self.window) {
+                    usage.pop_front();
+                }
+                let used: u64 = usage.iter().map(|(_, tokens)| tokens).sum();
+                match usage.front() {
+                    Some((oldest, _)) if used >= budget => *oldest + self.window,
+                    _ => return,
+                }
+            };
+            info!("║     ⏳ Token budget for '{}' spent, waiting {:?}", self.key, retry_at - Instant::now());
+            tokio::time::sleep_until(retry_at).await;
+        }
+    }
+
+    fn record(&self, tokens: u64) {
+        if self.tokens_per_window.is_some() && tokens > 0 {
+            self.usage.lock().unwrap().push_back((Instant::now(), tokens));
+        }
+    }
+}
+
+/// The slots held by one node attempt, released when dropped.
+#[derive(Debug, Default)]
+pub(crate) struct Permit {
+    _slots: Vec<OwnedSemaphorePermit>,
+    limiters: Vec<Arc<Limiter>>,
+}
+
+impl Permit {
+    /// Counts the tokens the attempt used against each budget it's under.
+    pub(crate) fn record(&self, tokens: u64) {
+        for limiter in &self.limiters {
+            limiter.record(tokens);
+        }
+    }
+}
+
+/// Checks that the pipeline's `max_concurrency`, if set, lets nodes run at all.
+pub(crate) fn check_concurrency(config: &PipelineConfig) -> Result<(), ConfigError> {
+    match config.max_concurrency {
+        Some(0) => Err(ConfigError::validation(&config.id, "max_concurrency must be at least 1")),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(id: &str, model: &str, api_base: Option<&str>) -> ModelConfig {
+        ModelConfig { id: id.into(), name: id.into(), model: model.into(), api_base: api_base.map(String::from) }
+    }
+
+    fn limits(models: &[(&str, RateLimit)], providers: &[(&str, RateLimit)]) -> RateLimits {
+        let map = |entries: &[(&str, RateLimit)]| entries.iter().map(|(k, v)| (k.to_string(), *v)).collect();
+        RateLimits { models: map(models), providers: map(providers) }
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_slots() {
+        let slots = |n| RateLimit { max_concurrency: Some(n), tokens_per_minute: None };
+        let limiter = RateLimiter::new(&limits(&[("gpt", slots(2))], &[("http://localhost:11434/v1", slots(1))]));
+        let gpt = model("gpt", "gpt-4o", None);
+        let llama = model("llama", "llama3", Some("http://localhost:11434/v1"));
+        let claude = model("claude", "claude-sonnet-4-5", None);
+
+        let first = limiter.acquire(&gpt).await;
+        let _second = limiter.acquire(&gpt).await;
+        let third = tokio::time::timeout(Duration::from_millis(20), limiter.acquire(&gpt)).await;
+        assert!(third.is_err(), "a third gpt attempt should wait for a slot");
+        drop(first);
+        let _third = limiter.acquire(&gpt).await;
+
+        let _local = limiter.acquire(&llama).await;
+        let other_local = model("mistral", "mistral", Some("http://localhost:11434/v1"));
+        assert!(tokio::time::timeout(Duration::from_millis(20), limiter.acquire(&other_local)).await.is_err());
+
+        // Unlimited models never wait
+        let _a = limiter.acquire(&claude).await;
+        let _b = limiter.acquire(&claude).await;
+    }
+
+    #[tokio::test]
+    async fn test_token_budget() {
+        let budget = RateLimit { max_concurrency: None, tokens_per_minute: Some(100) };
+        let limiter = RateLimiter::with_window(&limits(&[], &[("anthropic", budget)]), Duration::from_millis(50));
+        let claude = model("claude", "claude-sonnet-4-5", None);
+
+        limiter.acquire(&claude).await.record(60);
+        // Still under budget
+        limiter.acquire(&claude).await.record(60);
+
+        let start = Instant::now();
+        let _permit = limiter.acquire(&claude).await;
+        assert!(start.elapsed() >= Duration::from_millis(40), "should wait for usage to leave the window");
+    }
+
+    #[test]
+    fn test_check_concurrency() {
+        let mut config = PipelineConfig::builder("p", "P").max_concurrency(2).build();
+        assert!(check_concurrency(&config).is_ok());
+        config.max_concurrency = Some(0);
+        assert!(check_concurrency(&config).unwrap_err().to_string().contains("at least 1"));
+    }
+}
@@ -144,6 +144,21 @@ enum ToolResponseBlock {
     },
 }
 
+/// Builds the message list from conversation history followed by the user input.
+fn build_messages(history: &[Message], user_input: &str) -> Vec<AnthropicMessage> {
+    history
+        .iter()
+        .map(|msg| AnthropicMessage {
+            role: msg.role.as_str(),
+            content: msg.content.clone(),
+        })
+        .chain(std::iter::once(AnthropicMessage {
+            role: "user",
+            content: user_input.to_string(),
+        }))
+        .collect()
+}
+
 /// Client for Anthropic's Claude API.
 pub struct AnthropicClient {
     client: Client,
@@ -178,16 +193,23 @@ impl AnthropicClient {
 
     /// Sends a non-streaming chat request and returns the complete response.
     pub async fn chat(&self, system_prompt: &str, user_input: &str) -> Result<LlmResponse, AgentError> {
+        self.chat_with_history(system_prompt, &[], user_input).await
+    }
+
+    /// Sends a non-streaming chat request with history and returns the complete response.
+    pub async fn chat_with_history(
+        &self,
+        system_prompt: &str,
+        history: &[Message],
+        user_input: &str,
+    ) -> Result<LlmResponse, AgentError> {
         let start = std::time::Instant::now();
 
         let request = AnthropicRequest {
             model: self.model.clone(),
             max_tokens: 8192,
             system: system_prompt.to_string(),
-            messages: vec![AnthropicMessage {
-                role: "user",
-                content: user_input.to_string(),
-            }],
+            messages: build_messages(history, user_input),
             stream: false,
         };
 
@@ -226,24 +248,11 @@ impl AnthropicClient {
     ) -> Result<LlmStream, AgentError> {
         use futures::StreamExt;
 
-        let mut messages: Vec<AnthropicMessage> = history
-            .iter()
-            .map(|msg| AnthropicMessage {
-                role: msg.role.as_str(),
-                content: msg.content.clone(),
-            })
-            .collect();
-
-        messages.push(AnthropicMessage {
-            role: "user",
-            content: user_input.to_string(),
-        });
-
         let request = AnthropicRequest {
             model: self.model.clone(),
             max_tokens: 8192,
             system: system_prompt.to_string(),
-            messages,
+            messages: build_messages(history, user_input),
             stream: true,
         };
 
@@ -444,6 +453,16 @@ impl AnthropicMessageWithContent {
         }
     }
 
+    /// Creates an assistant message with text content.
+    pub fn assistant(text: &str) -> Self {
+        Self {
+            role: "assistant".to_string(),
+            content: vec![MessageContentBlock::Text {
+                text: text.to_string(),
+            }],
+        }
+    }
+
     /// Creates an assistant message with tool_use blocks.
     pub fn assistant_tool_use(tool_calls: &[ToolCall]) -> Self {
         Self {
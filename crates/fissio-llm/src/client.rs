@@ -14,12 +14,13 @@ use async_openai::{
         ChatCompletionRequestSystemMessageArgs, ChatCompletionRequestToolMessageArgs,
         ChatCompletionRequestUserMessageArgs, ChatCompletionStreamOptions,
         ChatCompletionTool, ChatCompletionToolType, CreateChatCompletionRequestArgs,
-        CreateChatCompletionResponse, FunctionObject, ResponseFormat,
+        CreateChatCompletionResponse, FunctionObject, ResponseFormat, ResponseFormatJsonSchema,
     },
     Client,
 };
 use futures::Stream;
 use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use tracing::{debug, info};
 
 /// A chunk from a streaming LLM response.
@@ -32,7 +33,7 @@ pub enum StreamChunk {
 pub type LlmStream = Pin<Box<dyn Stream<Item = Result<StreamChunk, AgentError>> + Send>>;
 
 /// Token usage and timing metrics from an LLM call.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LlmMetrics {
     pub input_tokens: u32,
     pub output_tokens: u32,
@@ -40,14 +41,15 @@ pub struct LlmMetrics {
 }
 
 /// Complete response from an LLM call.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LlmResponse {
     pub content: String,
     pub metrics: LlmMetrics,
 }
 
 /// Response from an LLM that may include tool calls.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum ChatResponse {
     Content(LlmResponse),
     ToolCalls { calls: Vec<ToolCall>, metrics: LlmMetrics },
@@ -79,6 +81,29 @@ fn build_messages(
     ])
 }
 
+/// Converts conversation history into request messages.
+fn history_messages(history: &[Message]) -> Result<Vec<ChatCompletionRequestMessage>, AgentError> {
+    history
+        .iter()
+        .map(|msg| {
+            Ok(match msg.role {
+                MessageRole::User => ChatCompletionRequestMessage::User(
+                    ChatCompletionRequestUserMessageArgs::default()
+                        .content(&*msg.content)
+                        .build()
+                        .map_err(llm_err)?,
+                ),
+                MessageRole::Assistant => ChatCompletionRequestMessage::Assistant(
+                    ChatCompletionRequestAssistantMessageArgs::default()
+                        .content(&*msg.content)
+                        .build()
+                        .map_err(llm_err)?,
+                ),
+            })
+        })
+        .collect()
+}
+
 /// Extracts content and metrics from a completion response.
 fn extract_response(response: CreateChatCompletionResponse, elapsed_ms: u64) -> Result<LlmResponse, AgentError> {
     let content = response
@@ -128,14 +153,58 @@ impl LlmClient {
 
     /// Sends a chat request and returns the complete response.
     pub async fn chat(&self, system_prompt: &str, user_input: &str) -> Result<LlmResponse, AgentError> {
-        let start = Instant::now();
-        let messages = build_messages(system_prompt, user_input)?;
+        self.chat_with_history(system_prompt, &[], user_input).await
+    }
 
-        let request = CreateChatCompletionRequestArgs::default()
-            .model(&self.default_model)
-            .messages(messages)
-            .build()
-            .map_err(llm_err)?;
+    /// Sends a chat request with history and returns the complete response.
+    pub async fn chat_with_history(
+        &self,
+        system_prompt: &str,
+        history: &[Message],
+        user_input: &str,
+    ) -> Result<LlmResponse, AgentError> {
+        self.chat_with_format(system_prompt, history, user_input, None).await
+    }
+
+    /// Sends a chat request with history whose response must be JSON
+    /// matching `schema`, using the API's `json_schema` response format.
+    pub async fn chat_with_schema(
+        &self,
+        system_prompt: &str,
+        history: &[Message],
+        user_input: &str,
+        schema: &serde_json::Value,
+    ) -> Result<LlmResponse, AgentError> {
+        let format = ResponseFormat::JsonSchema {
+            json_schema: ResponseFormatJsonSchema {
+                description: None,
+                name: "output".to_string(),
+                schema: Some(schema.clone()),
+                strict: None,
+            },
+        };
+        self.chat_with_format(system_prompt, history, user_input, Some(format)).await
+    }
+
+    async fn chat_with_format(
+        &self,
+        system_prompt: &str,
+        history: &[Message],
+        user_input: &str,
+        format: Option<ResponseFormat>,
+    ) -> Result<LlmResponse, AgentError> {
+        let start = Instant::now();
+        let mut messages = build_messages(system_prompt, user_input)?;
+        let user = messages.pop().expect("build_messages returns system and user messages");
+        messages.extend(history_messages(history)?);
+        messages.push(user);
+
+        let mut args = CreateChatCompletionRequestArgs::default();
+        args.model(&self.default_model).messages(messages);
+        if let Some(format) = format {
+            args.response_format(format);
+        }
+        let request = args.build().map_err(llm_err)?;
 
         let response = self.client.chat().create(request).await.map_err(llm_err)?;
         extract_response(response, start.elapsed().as_millis() as u64)
@@ -279,24 +348,7 @@ impl LlmClient {
                     .map_err(llm_err)?,
             ),
         ];
-
-        for msg in history {
-            let role_msg = match msg.role {
-                MessageRole::User => ChatCompletionRequestMessage::User(
-                    ChatCompletionRequestUserMessageArgs::default()
-                        .content(&*msg.content)
-                        .build()
-                        .map_err(llm_err)?,
-                ),
-                MessageRole::Assistant => ChatCompletionRequestMessage::Assistant(
-                    ChatCompletionRequestAssistantMessageArgs::default()
-                        .content(&*msg.content)
-                        .build()
-                        .map_err(llm_err)?,
-                ),
-            };
-            messages.push(role_msg);
-        }
+        messages.extend(history_messages(history)?);
 
         messages.push(ChatCompletionRequestMessage::User(
             ChatCompletionRequestUserMessageArgs::default()
@@ -41,6 +41,16 @@ impl UnifiedLlmClient {
         }
     }
 
+    /// Returns the provider requests go to: the `api_base` URL when set,
+    /// otherwise `"openai"` or `"anthropic"`.
+    pub fn provider(&self) -> &str {
+        match (&self.api_base, self.provider) {
+            (Some(api_base), _) => api_base,
+            (None, ProviderType::OpenAI) => "openai",
+            (None, ProviderType::Anthropic) => "anthropic",
+        }
+    }
+
     /// Sends a non-streaming chat request and returns the complete response.
     pub async fn chat(&self, system_prompt: &str, user_input: &str) -> Result<LlmResponse, AgentError> {
         match self.provider {
@@ -55,6 +65,50 @@ impl UnifiedLlmClient {
         }
     }
 
+    /// Sends a non-streaming chat request with history and returns the complete response.
+    pub async fn chat_with_history(
+        &self,
+        system_prompt: &str,
+        history: &[Message],
+        user_input: &str,
+    ) -> Result<LlmResponse, AgentError> {
+        match self.provider {
+            ProviderType::OpenAI => {
+                let client = LlmClient::new(&self.model, self.api_base.as_deref());
+                client.chat_with_history(system_prompt, history, user_input).await
+            }
+            ProviderType::Anthropic => {
+                let client = AnthropicClient::new(&self.model);
+                client.chat_with_history(system_prompt, history, user_input).await
+            }
+        }
+    }
+
+    /// Sends a non-streaming chat request with history whose response must be
+    /// JSON matching `schema`.
+    ///
+    /// OpenAI-compatible APIs are asked for the schema as their response
+    /// format. Anthropic has no equivalent, so the request is sent as is and
+    /// callers should describe the schema in the prompt.
+    pub async fn chat_with_schema(
+        &self,
+        system_prompt: &str,
+        history: &[Message],
+        user_input: &str,
+        schema: &serde_json::Value,
+    ) -> Result<LlmResponse, AgentError> {
+        match self.provider {
+            ProviderType::OpenAI => {
+                let client = LlmClient::new(&self.model, self.api_base.as_deref());
+                client.chat_with_schema(system_prompt, history, user_input, schema).await
+            }
+            ProviderType::Anthropic => {
+                let client = AnthropicClient::new(&self.model);
+                client.chat_with_history(system_prompt, history, user_input).await
+            }
+        }
+    }
+
     /// Sends a chat request with history and returns a stream of chunks.
     pub async fn chat_stream(
         &self,
@@ -149,6 +203,25 @@ impl UnifiedLlmClient {
                     };
                     tool_results.push((id, content));
                 }
+                ChatCompletionRequestMessage::Assistant(assistant_msg) => {
+                    // Text turns from conversation history; tool_use turns come from pending_tool_calls
+                    let text = match &assistant_msg.content {
+                        Some(async_openai::types::ChatCompletionRequestAssistantMessageContent::Text(t)) => t.clone(),
+                        Some(async_openai::types::ChatCompletionRequestAssistantMessageContent::Array(parts)) => {
+                            parts.iter().filter_map(|p| {
+                                if let async_openai::types::ChatCompletionRequestAssistantMessageContentPart::Text(t) = p {
+                                    Some(t.text.clone())
+                                } else {
+                                    None
+                                }
+                            }).collect::<Vec<_>>().join("\n")
+                        }
+                        None => String::new(),
+                    };
+                    if !text.is_empty() {
+                        result.push(AnthropicToolMessage::assistant(&text));
+                    }
+                }
                 _ => {} // Skip system and other message types
             }
         }
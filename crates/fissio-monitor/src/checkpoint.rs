@@ -0,0 +1,35 @@
+//! Checkpoint storage for resuming failed pipeline runs.
+
+use serde::{Deserialize, Serialize};
+
+use crate::store::StoreError;
+
+/// The latest saved state of a pipeline run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointRecord {
+    /// Run identifier (the run's trace ID when it is traced).
+    pub run_id: String,
+    /// Pipeline identifier.
+    pub pipeline_id: String,
+    /// Run this one resumed, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resumed_from: Option<String>,
+    /// Engine state needed to resume the run; opaque to the store.
+    pub state: serde_json::Value,
+    /// Unix timestamp (milliseconds) when the checkpoint was saved.
+    pub updated_at: i64,
+}
+
+/// Storage for run checkpoints.
+///
+/// Each run has at most one checkpoint; saving replaces the previous one.
+pub trait CheckpointStore: Send + Sync {
+    /// Saves a checkpoint, replacing any earlier one for the same run.
+    fn save_checkpoint(&self, checkpoint: &CheckpointRecord) -> Result<(), StoreError>;
+
+    /// Loads the latest checkpoint of a run.
+    fn load_checkpoint(&self, run_id: &str) -> Result<Option<CheckpointRecord>, StoreError>;
+
+    /// Deletes the checkpoint of a run, if any.
+    fn delete_checkpoint(&self, run_id: &str) -> Result<(), StoreError>;
+}
@@ -14,6 +14,9 @@ pub struct TracingCollector {
     pipeline_name: String,
     input: String,
     start_time: i64,
+    resumed_from: Option<String>,
+    /// The trace as it was when the run suspended, if this collector continues it.
+    earlier: Option<TraceRecord>,
     metrics: Mutex<Vec<NodeMetrics>>,
     spans: Mutex<Vec<SpanRecord>>,
 }
@@ -25,12 +28,53 @@ impl TracingCollector {
         pipeline_id: impl Into<String>,
         pipeline_name: impl Into<String>,
         input: impl Into<String>,
+    ) -> Self {
+        Self::start(store, pipeline_id.into(), pipeline_name.into(), input.into(), None)
+    }
+
+    /// Creates a collector for a run resuming `original`, with a new trace
+    /// for the same pipeline and input that links back to it.
+    pub fn resuming(store: Arc<TraceStore>, original: &TraceRecord) -> Self {
+        Self::start(
+            store,
+            original.pipeline_id.clone(),
+            original.pipeline_name.clone(),
+            original.input.clone(),
+            Some(original.trace_id.clone()),
+        )
+    }
+
+    /// Creates a collector for a run continuing after it suspended, adding to
+    /// the same trace. Totals carry over and the trace is marked running again.
+    pub fn continuing(store: Arc<TraceStore>, suspended: &TraceRecord) -> Self {
+        let trace = TraceRecord { status: TraceStatus::Running, ..suspended.clone() };
+        if let Err(e) = store.update_trace(&trace) {
+            tracing::warn!("Failed to update trace: {}", e);
+        }
+
+        Self {
+            store,
+            trace_id: trace.trace_id.clone(),
+            pipeline_id: trace.pipeline_id.clone(),
+            pipeline_name: trace.pipeline_name.clone(),
+            input: trace.input.clone(),
+            start_time: now_ms(),
+            resumed_from: trace.resumed_from.clone(),
+            earlier: Some(trace),
+            metrics: Mutex::new(Vec::new()),
+            spans: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn start(
+        store: Arc<TraceStore>,
+        pipeline_id: String,
+        pipeline_name: String,
+        input: String,
+        resumed_from: Option<String>,
     ) -> Self {
         let trace_id = uuid::Uuid::new_v4().to_string();
         let start_time = now_ms();
-        let pipeline_id = pipeline_id.into();
-        let pipeline_name = pipeline_name.into();
-        let input = input.into();
 
         // Insert initial trace record (status: running)
         let trace = TraceRecord {
@@ -45,6 +89,7 @@ impl TracingCollector {
             total_output_tokens: 0,
             total_tool_calls: 0,
             status: TraceStatus::Running,
+            resumed_from: resumed_from.clone(),
         };
 
         if let Err(e) = store.insert_trace(&trace) {
@@ -58,6 +103,8 @@ impl TracingCollector {
             pipeline_name,
             input,
             start_time,
+            resumed_from,
+            earlier: None,
             metrics: Mutex::new(Vec::new()),
             spans: Mutex::new(Vec::new()),
         }
@@ -73,7 +120,7 @@ impl TracingCollector {
         let elapsed_ms = (now_ms() - self.start_time) as u64;
         let metrics = self.flush();
 
-        let trace = TraceRecord {
+        let mut trace = TraceRecord {
             trace_id: self.trace_id.clone(),
             pipeline_id: self.pipeline_id.clone(),
             pipeline_name: self.pipeline_name.clone(),
@@ -85,7 +132,16 @@ impl TracingCollector {
             total_output_tokens: metrics.total_output_tokens,
             total_tool_calls: metrics.total_tool_calls,
             status,
+            resumed_from: self.resumed_from.clone(),
         };
+        // Time spent waiting for a decision isn't counted
+        if let Some(ref earlier) = self.earlier {
+            trace.timestamp = earlier.timestamp;
+            trace.total_elapsed_ms += earlier.total_elapsed_ms;
+            trace.total_input_tokens += earlier.total_input_tokens;
+            trace.total_output_tokens += earlier.total_output_tokens;
+            trace.total_tool_calls += earlier.total_tool_calls;
+        }
 
         if let Err(e) = self.store.update_trace(&trace) {
             tracing::warn!("Failed to update trace: {}", e);
@@ -142,6 +198,10 @@ impl MetricsCollector for TracingCollector {
             output_tokens: metrics.output_tokens,
             tool_call_count: metrics.tool_call_count,
             iteration_count: metrics.iteration_count,
+            score: metrics.score,
+            attempt: metrics.attempt,
+            model: metrics.model.clone(),
+            error: metrics.error.clone(),
         };
 
         if let Err(e) = self.store.insert_span(&span) {
@@ -211,6 +271,10 @@ mod tests {
             tool_call_count: 1,
             iteration_count: 1,
             estimated_cost_usd: None,
+            score: None,
+            attempt: 1,
+            model: None,
+            error: None,
         });
 
         collector.success("World");
@@ -221,4 +285,56 @@ mod tests {
         assert_eq!(trace.total_input_tokens, 10);
         assert_eq!(trace.total_output_tokens, 20);
     }
+
+    #[test]
+    fn test_finalize_interrupted_statuses() {
+        let store = Arc::new(TraceStore::in_memory().unwrap());
+
+        for status in [TraceStatus::Cancelled, TraceStatus::TimedOut] {
+            let collector = TracingCollector::new(store.clone(), "test-pipe", "Test Pipeline", "Hello");
+            collector.finalize("partial", status);
+
+            let trace = store.get_trace(collector.trace_id()).unwrap().unwrap();
+            assert_eq!(trace.status, status);
+            assert_eq!(trace.output, "partial");
+        }
+    }
+
+    #[test]
+    fn test_resuming_links_original() {
+        let store = Arc::new(TraceStore::in_memory().unwrap());
+        let original = TracingCollector::new(store.clone(), "test-pipe", "Test Pipeline", "Hello");
+        original.error("boom");
+
+        let original = store.get_trace(original.trace_id()).unwrap().unwrap();
+        let resumed = TracingCollector::resuming(store.clone(), &original);
+        resumed.success("World");
+
+        let trace = store.get_trace(resumed.trace_id()).unwrap().unwrap();
+        assert_eq!(trace.resumed_from.as_deref(), Some(original.trace_id.as_str()));
+        assert_eq!(trace.input, "Hello");
+        assert_eq!(trace.pipeline_id, "test-pipe");
+    }
+
+    #[test]
+    fn test_continuing_same_trace() {
+        let store = Arc::new(TraceStore::in_memory().unwrap());
+        let metrics = |tokens| NodeMetrics { input_tokens: tokens, ..NodeMetrics::new("node1") };
+        let first = TracingCollector::new(store.clone(), "test-pipe", "Test Pipeline", "Hello");
+        first.record(metrics(10));
+        first.finalize("", TraceStatus::AwaitingApproval);
+
+        let suspended = store.get_trace(first.trace_id()).unwrap().unwrap();
+        assert_eq!(suspended.status, TraceStatus::AwaitingApproval);
+        let second = TracingCollector::continuing(store.clone(), &suspended);
+        assert_eq!(store.get_trace(first.trace_id()).unwrap().unwrap().status, TraceStatus::Running);
+        second.record(metrics(5));
+        second.success("World");
+
+        let trace = store.get_trace(first.trace_id()).unwrap().unwrap();
+        assert_eq!(second.trace_id(), first.trace_id());
+        assert_eq!(trace.status, TraceStatus::Success);
+        assert_eq!(trace.total_input_tokens, 15);
+        assert_eq!(trace.timestamp, suspended.timestamp);
+    }
 }
@@ -1,10 +1,14 @@
 //! Observability and metrics collection for fissio pipelines.
 
+mod checkpoint;
 mod collector;
+mod recording;
 mod store;
 mod trace;
 
+pub use checkpoint::{CheckpointRecord, CheckpointStore};
 pub use collector::TracingCollector;
+pub use recording::{CallKind, CallRecord, CallStore};
 pub use store::{MetricsSummary, StoreError, TraceStore};
 pub use trace::{SpanRecord, ToolCallRecord, TraceQuery, TraceRecord, TraceStatus};
 
@@ -86,6 +90,18 @@ pub struct NodeMetrics {
     pub iteration_count: u32,
     /// Estimated cost in USD (if pricing configured).
     pub estimated_cost_usd: Option<f64>,
+    /// Quality score assigned by an evaluator node.
+    #[serde(default)]
+    pub score: Option<f64>,
+    /// Attempt number under the node's retry policy, starting at 1 (0 if not tracked).
+    #[serde(default)]
+    pub attempt: u32,
+    /// Model used for this execution.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Error message if this attempt failed.
+    #[serde(default)]
+    pub error: Option<String>,
 }
 
 impl NodeMetrics {
@@ -136,6 +152,7 @@ pub trait MetricsCollector: Send + Sync {
     /// Record metrics from a node execution.
     fn record(&self, metrics: NodeMetrics);
     /// Record a span with node I/O for detailed tracing.
+    #[allow(clippy::too_many_arguments)]
     fn record_span(
         &self,
         _node_id: &str,
@@ -267,6 +284,10 @@ mod tests {
             tool_call_count: 2,
             iteration_count: 1,
             estimated_cost_usd: None,
+            score: None,
+            attempt: 1,
+            model: None,
+            error: None,
         });
 
         collector.record(NodeMetrics {
@@ -277,6 +298,10 @@ mod tests {
             tool_call_count: 0,
             iteration_count: 1,
             estimated_cost_usd: None,
+            score: None,
+            attempt: 1,
+            model: None,
+            error: None,
         });
 
         let metrics = collector.flush();
@@ -0,0 +1,57 @@
+//! Recorded provider calls for replaying a run deterministically.
+
+use serde::{Deserialize, Serialize};
+
+use crate::store::StoreError;
+
+/// What a recorded call went to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CallKind {
+    /// A request to an LLM provider.
+    Llm,
+    /// A tool execution.
+    Tool,
+}
+
+impl CallKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CallKind::Llm => "llm",
+            CallKind::Tool => "tool",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "tool" => CallKind::Tool,
+            _ => CallKind::Llm,
+        }
+    }
+}
+
+/// A provider request or tool execution made during a run, with its result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallRecord {
+    /// Run identifier (the run's trace ID when it is traced).
+    pub run_id: String,
+    /// Node the call was made for.
+    pub node_id: String,
+    pub kind: CallKind,
+    /// The request as sent; opaque to the store.
+    pub request: serde_json::Value,
+    /// The successful response; opaque to the store.
+    pub response: serde_json::Value,
+    /// Unix timestamp (milliseconds) when the call completed.
+    pub timestamp: i64,
+}
+
+/// Storage for recorded calls.
+pub trait CallStore: Send + Sync {
+    /// Appends a call to its run's recording.
+    fn record_call(&self, call: &CallRecord) -> Result<(), StoreError>;
+
+    /// Loads the calls recorded for a run, in the order they completed.
+    fn load_calls(&self, run_id: &str) -> Result<Vec<CallRecord>, StoreError>;
+}
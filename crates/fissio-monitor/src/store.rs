@@ -1,5 +1,7 @@
 //! SQLite-backed trace storage.
 
+use crate::checkpoint::{CheckpointRecord, CheckpointStore};
+use crate::recording::{CallKind, CallRecord, CallStore};
 use crate::trace::{SpanRecord, ToolCallRecord, TraceQuery, TraceRecord, TraceStatus};
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
@@ -60,7 +62,8 @@ impl TraceStore {
                 total_input_tokens INTEGER NOT NULL,
                 total_output_tokens INTEGER NOT NULL,
                 total_tool_calls INTEGER NOT NULL,
-                status TEXT NOT NULL
+                status TEXT NOT NULL,
+                resumed_from TEXT
             );
 
             CREATE TABLE IF NOT EXISTS spans (
@@ -76,6 +79,10 @@ impl TraceStore {
                 output_tokens INTEGER NOT NULL,
                 tool_call_count INTEGER NOT NULL,
                 iteration_count INTEGER NOT NULL,
+                score REAL,
+                attempt INTEGER NOT NULL DEFAULT 1,
+                model TEXT,
+                error TEXT,
                 FOREIGN KEY (trace_id) REFERENCES traces(trace_id)
             );
 
@@ -89,13 +96,39 @@ impl TraceStore {
                 FOREIGN KEY (span_id) REFERENCES spans(span_id)
             );
 
+            CREATE TABLE IF NOT EXISTS checkpoints (
+                run_id TEXT PRIMARY KEY,
+                pipeline_id TEXT NOT NULL,
+                resumed_from TEXT,
+                state TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS calls (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                run_id TEXT NOT NULL,
+                node_id TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                request TEXT NOT NULL,
+                response TEXT NOT NULL,
+                timestamp INTEGER NOT NULL
+            );
+
             CREATE INDEX IF NOT EXISTS idx_traces_timestamp ON traces(timestamp DESC);
             CREATE INDEX IF NOT EXISTS idx_traces_pipeline ON traces(pipeline_id);
             CREATE INDEX IF NOT EXISTS idx_spans_trace ON spans(trace_id);
             CREATE INDEX IF NOT EXISTS idx_tool_calls_span ON tool_calls(span_id);
+            CREATE INDEX IF NOT EXISTS idx_calls_run ON calls(run_id);
             "#,
         )?;
 
+        // Columns added after the initial schema
+        add_column_if_missing(&conn, "spans", "score", "REAL")?;
+        add_column_if_missing(&conn, "spans", "attempt", "INTEGER NOT NULL DEFAULT 1")?;
+        add_column_if_missing(&conn, "spans", "model", "TEXT")?;
+        add_column_if_missing(&conn, "spans", "error", "TEXT")?;
+        add_column_if_missing(&conn, "traces", "resumed_from", "TEXT")?;
+
         Ok(())
     }
 
@@ -107,8 +140,8 @@ impl TraceStore {
             r#"INSERT INTO traces
                (trace_id, pipeline_id, pipeline_name, timestamp, input, output,
                 total_elapsed_ms, total_input_tokens, total_output_tokens,
-                total_tool_calls, status)
-               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)"#,
+                total_tool_calls, status, resumed_from)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)"#,
             params![
                 trace.trace_id,
                 trace.pipeline_id,
@@ -121,6 +154,7 @@ impl TraceStore {
                 trace.total_output_tokens,
                 trace.total_tool_calls,
                 trace.status.as_str(),
+                trace.resumed_from,
             ],
         )?;
 
@@ -150,6 +184,20 @@ impl TraceStore {
         Ok(())
     }
 
+    /// Marks a trace awaiting approval as running again, in one statement so
+    /// only one caller can continue it. Returns `false` if the trace wasn't
+    /// awaiting approval.
+    pub fn claim_suspended(&self, trace_id: &str) -> Result<bool, StoreError> {
+        let conn = self.conn.lock().map_err(|_| StoreError::Lock)?;
+
+        let changed = conn.execute(
+            "UPDATE traces SET status = ?1 WHERE trace_id = ?2 AND status = ?3",
+            params![TraceStatus::Running.as_str(), trace_id, TraceStatus::AwaitingApproval.as_str()],
+        )?;
+
+        Ok(changed > 0)
+    }
+
     /// Retrieves a trace by ID.
     pub fn get_trace(&self, trace_id: &str) -> Result<Option<TraceRecord>, StoreError> {
         let conn = self.conn.lock().map_err(|_| StoreError::Lock)?;
@@ -157,7 +205,7 @@ impl TraceStore {
         let mut stmt = conn.prepare(
             r#"SELECT trace_id, pipeline_id, pipeline_name, timestamp, input, output,
                total_elapsed_ms, total_input_tokens, total_output_tokens,
-               total_tool_calls, status
+               total_tool_calls, status, resumed_from
                FROM traces WHERE trace_id = ?1"#,
         )?;
 
@@ -174,6 +222,7 @@ impl TraceStore {
                 total_output_tokens: row.get(8)?,
                 total_tool_calls: row.get(9)?,
                 status: TraceStatus::from_str(&row.get::<_, String>(10)?),
+                resumed_from: row.get(11)?,
             })
         });
 
@@ -191,7 +240,7 @@ impl TraceStore {
         let mut sql = String::from(
             r#"SELECT trace_id, pipeline_id, pipeline_name, timestamp, input, output,
                total_elapsed_ms, total_input_tokens, total_output_tokens,
-               total_tool_calls, status
+               total_tool_calls, status, resumed_from
                FROM traces WHERE 1=1"#,
         );
 
@@ -234,6 +283,7 @@ impl TraceStore {
                 total_output_tokens: row.get(8)?,
                 total_tool_calls: row.get(9)?,
                 status: TraceStatus::from_str(&row.get::<_, String>(10)?),
+                resumed_from: row.get(11)?,
             })
         })?;
 
@@ -252,8 +302,9 @@ impl TraceStore {
         conn.execute(
             r#"INSERT INTO spans
                (span_id, trace_id, node_id, node_type, start_time, end_time,
-                input, output, input_tokens, output_tokens, tool_call_count, iteration_count)
-               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)"#,
+                input, output, input_tokens, output_tokens, tool_call_count, iteration_count, score,
+                attempt, model, error)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)"#,
             params![
                 span.span_id,
                 span.trace_id,
@@ -267,6 +318,10 @@ impl TraceStore {
                 span.output_tokens,
                 span.tool_call_count,
                 span.iteration_count,
+                span.score,
+                span.attempt,
+                span.model,
+                span.error,
             ],
         )?;
 
@@ -279,7 +334,8 @@ impl TraceStore {
 
         let mut stmt = conn.prepare(
             r#"SELECT span_id, trace_id, node_id, node_type, start_time, end_time,
-               input, output, input_tokens, output_tokens, tool_call_count, iteration_count
+               input, output, input_tokens, output_tokens, tool_call_count, iteration_count, score,
+               attempt, model, error
                FROM spans WHERE trace_id = ?1 ORDER BY start_time"#,
         )?;
 
@@ -297,6 +353,10 @@ impl TraceStore {
                 output_tokens: row.get(9)?,
                 tool_call_count: row.get(10)?,
                 iteration_count: row.get(11)?,
+                score: row.get(12)?,
+                attempt: row.get(13)?,
+                model: row.get(14)?,
+                error: row.get(15)?,
             })
         })?;
 
@@ -371,8 +431,10 @@ impl TraceStore {
         // Delete spans
         conn.execute("DELETE FROM spans WHERE trace_id = ?1", params![trace_id])?;
 
-        // Delete trace
+        // Delete trace, its checkpoint, and its recorded calls
         conn.execute("DELETE FROM traces WHERE trace_id = ?1", params![trace_id])?;
+        conn.execute("DELETE FROM checkpoints WHERE run_id = ?1", params![trace_id])?;
+        conn.execute("DELETE FROM calls WHERE run_id = ?1", params![trace_id])?;
 
         Ok(())
     }
@@ -405,6 +467,138 @@ impl TraceStore {
     }
 }
 
+impl CheckpointStore for TraceStore {
+    fn save_checkpoint(&self, checkpoint: &CheckpointRecord) -> Result<(), StoreError> {
+        let conn = self.conn.lock().map_err(|_| StoreError::Lock)?;
+
+        conn.execute(
+            r#"INSERT OR REPLACE INTO checkpoints (run_id, pipeline_id, resumed_from, state, updated_at)
+               VALUES (?1, ?2, ?3, ?4, ?5)"#,
+            params![
+                checkpoint.run_id,
+                checkpoint.pipeline_id,
+                checkpoint.resumed_from,
+                serde_json::to_string(&checkpoint.state)?,
+                checkpoint.updated_at,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    fn load_checkpoint(&self, run_id: &str) -> Result<Option<CheckpointRecord>, StoreError> {
+        let conn = self.conn.lock().map_err(|_| StoreError::Lock)?;
+
+        let mut stmt = conn.prepare(
+            r#"SELECT run_id, pipeline_id, resumed_from, state, updated_at
+               FROM checkpoints WHERE run_id = ?1"#,
+        )?;
+
+        let result = stmt.query_row(params![run_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, i64>(4)?,
+            ))
+        });
+
+        match result {
+            Ok((run_id, pipeline_id, resumed_from, state, updated_at)) => Ok(Some(CheckpointRecord {
+                run_id,
+                pipeline_id,
+                resumed_from,
+                state: serde_json::from_str(&state)?,
+                updated_at,
+            })),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn delete_checkpoint(&self, run_id: &str) -> Result<(), StoreError> {
+        let conn = self.conn.lock().map_err(|_| StoreError::Lock)?;
+        conn.execute("DELETE FROM checkpoints WHERE run_id = ?1", params![run_id])?;
+        Ok(())
+    }
+}
+
+impl CallStore for TraceStore {
+    fn record_call(&self, call: &CallRecord) -> Result<(), StoreError> {
+        let conn = self.conn.lock().map_err(|_| StoreError::Lock)?;
+
+        conn.execute(
+            r#"INSERT INTO calls (run_id, node_id, kind, request, response, timestamp)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+            params![
+                call.run_id,
+                call.node_id,
+                call.kind.as_str(),
+                serde_json::to_string(&call.request)?,
+                serde_json::to_string(&call.response)?,
+                call.timestamp,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    fn load_calls(&self, run_id: &str) -> Result<Vec<CallRecord>, StoreError> {
+        let conn = self.conn.lock().map_err(|_| StoreError::Lock)?;
+
+        let mut stmt = conn.prepare(
+            r#"SELECT run_id, node_id, kind, request, response, timestamp
+               FROM calls WHERE run_id = ?1 ORDER BY seq"#,
+        )?;
+
+        let rows = stmt.query_map(params![run_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, i64>(5)?,
+            ))
+        })?;
+
+        let mut calls = Vec::new();
+        for row in rows {
+            let (run_id, node_id, kind, request, response, timestamp) = row?;
+            calls.push(CallRecord {
+                run_id,
+                node_id,
+                kind: CallKind::from_str(&kind),
+                request: serde_json::from_str(&request)?,
+                response: serde_json::from_str(&response)?,
+                timestamp,
+            });
+        }
+
+        Ok(calls)
+    }
+}
+
+/// Adds a column to an existing table if an older database lacks it.
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    decl: &str,
+) -> Result<(), StoreError> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(Result::ok)
+        .any(|name| name == column);
+
+    if !exists {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl), [])?;
+    }
+    Ok(())
+}
+
 /// Aggregate metrics summary.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetricsSummary {
@@ -435,6 +629,7 @@ mod tests {
             total_output_tokens: 20,
             total_tool_calls: 2,
             status: TraceStatus::Success,
+            resumed_from: None,
         };
 
         store.insert_trace(&trace).unwrap();
@@ -445,6 +640,13 @@ mod tests {
 
         let traces = store.list_traces(&TraceQuery::default()).unwrap();
         assert_eq!(traces.len(), 1);
+
+        // A suspended trace can be claimed once
+        assert!(!store.claim_suspended("trace-1").unwrap());
+        store.update_trace(&TraceRecord { status: TraceStatus::AwaitingApproval, ..trace }).unwrap();
+        assert!(store.claim_suspended("trace-1").unwrap());
+        assert!(!store.claim_suspended("trace-1").unwrap());
+        assert_eq!(store.get_trace("trace-1").unwrap().unwrap().status, TraceStatus::Running);
     }
 
     #[test]
@@ -463,6 +665,7 @@ mod tests {
             total_output_tokens: 10,
             total_tool_calls: 1,
             status: TraceStatus::Success,
+            resumed_from: None,
         };
         store.insert_trace(&trace).unwrap();
 
@@ -479,6 +682,10 @@ mod tests {
             output_tokens: 10,
             tool_call_count: 1,
             iteration_count: 1,
+            score: Some(0.8),
+            attempt: 2,
+            model: Some("gpt-4o-mini".to_string()),
+            error: None,
         };
         store.insert_span(&span).unwrap();
 
@@ -494,9 +701,84 @@ mod tests {
 
         let spans = store.get_spans("trace-1").unwrap();
         assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].score, Some(0.8));
+        assert_eq!(spans[0].attempt, 2);
+        assert_eq!(spans[0].model.as_deref(), Some("gpt-4o-mini"));
 
         let calls = store.get_tool_calls("span-1").unwrap();
         assert_eq!(calls.len(), 1);
         assert_eq!(calls[0].tool_name, "search");
     }
+
+    #[test]
+    fn test_migrates_spans_without_score() {
+        let path = std::env::temp_dir().join(format!("fissio-traces-{}.db", uuid::Uuid::new_v4()));
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                r#"CREATE TABLE spans (
+                    span_id TEXT PRIMARY KEY, trace_id TEXT NOT NULL, node_id TEXT NOT NULL,
+                    node_type TEXT NOT NULL, start_time INTEGER NOT NULL, end_time INTEGER NOT NULL,
+                    input TEXT NOT NULL, output TEXT NOT NULL, input_tokens INTEGER NOT NULL,
+                    output_tokens INTEGER NOT NULL, tool_call_count INTEGER NOT NULL,
+                    iteration_count INTEGER NOT NULL
+                );
+                INSERT INTO spans VALUES ('s1', 't1', 'n1', 'llm', 0, 1, 'in', 'out', 1, 2, 0, 1);"#,
+            )
+            .unwrap();
+        }
+
+        let store = TraceStore::new(&path).unwrap();
+        let spans = store.get_spans("t1").unwrap();
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].score, None);
+        assert_eq!(spans[0].attempt, 1);
+        assert_eq!(spans[0].error, None);
+
+        drop(store);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_checkpoints() {
+        let store = TraceStore::in_memory().unwrap();
+        assert!(store.load_checkpoint("run-1").unwrap().is_none());
+
+        let mut checkpoint = CheckpointRecord {
+            run_id: "run-1".to_string(),
+            pipeline_id: "pipe-1".to_string(),
+            resumed_from: Some("run-0".to_string()),
+            state: serde_json::json!({ "completed": ["a"] }),
+            updated_at: 1700000000000,
+        };
+        store.save_checkpoint(&checkpoint).unwrap();
+        checkpoint.state = serde_json::json!({ "completed": ["a", "b"] });
+        store.save_checkpoint(&checkpoint).unwrap();
+
+        let loaded = store.load_checkpoint("run-1").unwrap().unwrap();
+        assert_eq!(loaded.state, serde_json::json!({ "completed": ["a", "b"] }));
+        assert_eq!(loaded.resumed_from.as_deref(), Some("run-0"));
+
+        store.delete_checkpoint("run-1").unwrap();
+        assert!(store.load_checkpoint("run-1").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_recorded_calls() {
+        let store = TraceStore::in_memory().unwrap();
+        let call = |run_id: &str, kind, response: &str| CallRecord {
+            run_id: run_id.to_string(),
+            node_id: "writer".to_string(),
+            kind,
+            request: serde_json::json!({ "input": "hi" }),
+            response: serde_json::json!(response),
+            timestamp: 1700000000000,
+        };
+        store.record_call(&call("run-1", CallKind::Tool, "first")).unwrap();
+        store.record_call(&call("run-2", CallKind::Llm, "other")).unwrap();
+        store.record_call(&call("run-1", CallKind::Llm, "second")).unwrap();
+
+        let calls = store.load_calls("run-1").unwrap();
+        assert_eq!(calls, [call("run-1", CallKind::Tool, "first"), call("run-1", CallKind::Llm, "second")]);
+    }
 }
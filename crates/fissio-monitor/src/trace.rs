@@ -27,6 +27,9 @@ pub struct TraceRecord {
     pub total_tool_calls: u32,
     /// Execution status.
     pub status: TraceStatus,
+    /// Trace of the failed run this one resumed, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resumed_from: Option<String>,
 }
 
 /// Status of a trace execution.
@@ -39,6 +42,14 @@ pub enum TraceStatus {
     Error,
     /// Execution is still in progress.
     Running,
+    /// Execution was cancelled before it finished.
+    Cancelled,
+    /// Execution exceeded a node or pipeline timeout.
+    #[serde(rename = "timed_out")]
+    TimedOut,
+    /// Execution is suspended until a human decides on a pending approval.
+    #[serde(rename = "awaiting_approval")]
+    AwaitingApproval,
 }
 
 impl TraceStatus {
@@ -47,14 +58,21 @@ impl TraceStatus {
             TraceStatus::Success => "success",
             TraceStatus::Error => "error",
             TraceStatus::Running => "running",
+            TraceStatus::Cancelled => "cancelled",
+            TraceStatus::TimedOut => "timed_out",
+            TraceStatus::AwaitingApproval => "awaiting_approval",
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Self {
         match s {
             "success" => TraceStatus::Success,
             "error" => TraceStatus::Error,
             "running" => TraceStatus::Running,
+            "cancelled" => TraceStatus::Cancelled,
+            "timed_out" => TraceStatus::TimedOut,
+            "awaiting_approval" => TraceStatus::AwaitingApproval,
             _ => TraceStatus::Error,
         }
     }
@@ -87,6 +105,18 @@ pub struct SpanRecord {
     pub tool_call_count: u32,
     /// Number of agentic loop iterations.
     pub iteration_count: u32,
+    /// Quality score (evaluator nodes only).
+    #[serde(default)]
+    pub score: Option<f64>,
+    /// Attempt number under the node's retry policy, starting at 1.
+    #[serde(default)]
+    pub attempt: u32,
+    /// Model used for this span.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Error message if this attempt failed.
+    #[serde(default)]
+    pub error: Option<String>,
 }
 
 /// A tool call record within a span.
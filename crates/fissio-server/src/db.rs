@@ -66,6 +66,9 @@ pub fn list_user_pipelines(conn: &Connection) -> Vec<PipelineInfo> {
             nodes: config.nodes,
             edges: config.edges,
             layout: config.layout,
+            variables: config.variables,
+            timeout_ms: config.timeout_ms,
+            max_concurrency: config.max_concurrency,
         })
     }).collect()
 }
@@ -76,6 +79,9 @@ pub fn save_pipeline(conn: &Connection, req: &SavePipelineRequest) -> Result<()>
         nodes: req.nodes.clone(),
         edges: req.edges.clone(),
         layout: req.layout.clone(),
+        variables: req.variables.clone(),
+        timeout_ms: req.timeout_ms,
+        max_concurrency: req.max_concurrency,
     };
     let config_json = serde_json::to_string(&config).context("failed to serialize config")?;
     conn.execute(
@@ -101,6 +107,12 @@ struct StoredConfig {
     edges: Vec<EdgeInfo>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     layout: Option<HashMap<String, Position>>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    variables: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timeout_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_concurrency: Option<usize>,
 }
 
 /// Example pipeline definition loaded from JSON.
@@ -133,7 +145,14 @@ pub fn seed_examples(conn: &Connection) -> Result<()> {
 
     let example_count = examples.len();
     for ex in examples {
-        let config = StoredConfig { nodes: ex.nodes, edges: ex.edges, layout: None };
+        let config = StoredConfig {
+            nodes: ex.nodes,
+            edges: ex.edges,
+            layout: None,
+            variables: HashMap::new(),
+            timeout_ms: None,
+            max_concurrency: None,
+        };
         let config_json = serde_json::to_string(&config)?;
 
         conn.execute(
@@ -3,6 +3,7 @@
 use std::collections::HashMap;
 use std::fmt;
 
+use fissio_config::{Condition, FailurePolicy, RetryPolicy};
 use fissio_core::ModelConfig;
 use serde::{Deserialize, Serialize};
 
@@ -35,6 +36,16 @@ pub struct RuntimeNodeConfig {
     pub prompt: Option<String>,
     #[serde(default)]
     pub tools: Option<Vec<String>>,
+    #[serde(default)]
+    pub config: Option<serde_json::Value>,
+    #[serde(default)]
+    pub retry: Option<RetryPolicy>,
+    #[serde(default)]
+    pub fallback_models: Option<Vec<String>>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub output_schema: Option<serde_json::Value>,
 }
 
 /// Runtime edge configuration from the frontend.
@@ -44,6 +55,14 @@ pub struct RuntimeEdgeConfig {
     pub to: serde_json::Value,
     #[serde(default)]
     pub edge_type: Option<String>,
+    #[serde(default)]
+    pub max_iterations: Option<u32>,
+    #[serde(default)]
+    pub on_failure: FailurePolicy,
+    #[serde(default)]
+    pub when: Option<Condition>,
+    #[serde(default)]
+    pub optional: bool,
 }
 
 /// Complete runtime pipeline configuration.
@@ -55,6 +74,12 @@ pub struct RuntimePipelineConfig {
     pub name: Option<String>,
     pub nodes: Vec<RuntimeNodeConfig>,
     pub edges: Vec<RuntimeEdgeConfig>,
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub max_concurrency: Option<usize>,
 }
 
 // === Pipeline Info Types ===
@@ -68,6 +93,16 @@ pub struct NodeInfo {
     pub prompt: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_models: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub x: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -81,6 +116,44 @@ pub struct EdgeInfo {
     pub to: serde_json::Value,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub edge_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_iterations: Option<u32>,
+    #[serde(default, skip_serializing_if = "FailurePolicy::is_fail_fast")]
+    pub on_failure: FailurePolicy,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<Condition>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
+}
+
+/// Converts a saved node for the runtime path. The destructuring is
+/// exhaustive so a field added to [`NodeInfo`] can't be left out.
+impl From<&NodeInfo> for RuntimeNodeConfig {
+    fn from(info: &NodeInfo) -> Self {
+        let NodeInfo {
+            id,
+            node_type,
+            model,
+            prompt,
+            tools,
+            config,
+            retry,
+            fallback_models,
+            timeout_ms,
+            output_schema,
+            x: _,
+            y: _,
+        } = info.clone();
+        Self { id, node_type, model, prompt, tools, config, retry, fallback_models, timeout_ms, output_schema }
+    }
+}
+
+/// Converts a saved edge for the runtime path.
+impl From<&EdgeInfo> for RuntimeEdgeConfig {
+    fn from(info: &EdgeInfo) -> Self {
+        let EdgeInfo { from, to, edge_type, max_iterations, on_failure, when, optional } = info.clone();
+        Self { from, to, edge_type, max_iterations, on_failure, when, optional }
+    }
 }
 
 /// Position for layout storage.
@@ -100,6 +173,12 @@ pub struct PipelineInfo {
     pub edges: Vec<EdgeInfo>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub layout: Option<HashMap<String, Position>>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub variables: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<usize>,
 }
 
 // === Pipeline CRUD Types ===
@@ -115,6 +194,12 @@ pub struct SavePipelineRequest {
     pub edges: Vec<EdgeInfo>,
     #[serde(default)]
     pub layout: Option<HashMap<String, Position>>,
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub max_concurrency: Option<usize>,
 }
 
 /// Response from saving a pipeline.
@@ -12,6 +12,8 @@ use serde::Serialize;
 pub enum AppError {
     Internal(String),
     NotFound(String),
+    BadRequest(String),
+    Conflict(String),
 }
 
 impl AppError {
@@ -49,6 +51,8 @@ impl IntoResponse for AppError {
         let (status, message) = match self {
             AppError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
             AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, msg),
         };
         (status, Json(ErrorResponse { error: message })).into_response()
     }
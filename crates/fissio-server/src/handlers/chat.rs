@@ -5,23 +5,28 @@ use std::sync::Arc;
 use std::time::Instant;
 
 use axum::{
-    extract::State,
+    extract::{Path, State},
     response::sse::{Event, KeepAlive, Sse},
     Json,
 };
-use fissio_core::Message as CoreMessage;
-use fissio_engine::EngineOutput;
-use fissio_monitor::{MetricsCollector, NodeMetrics, TracingCollector};
+use fissio_config::PipelineConfig;
+use fissio_core::{AgentError, Message as CoreMessage, ModelConfig};
+use fissio_engine::{
+    event_channel, CancellationToken, Checkpoint, Decision, EngineOutput, EventReceiver as EngineEventReceiver,
+    Replay,
+};
+use fissio_monitor::{MetricsCollector, NodeMetrics, TraceRecord, TraceStatus, TracingCollector};
 use futures::stream::{Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
 use tracing::{error, info};
 
-use crate::dto::{RuntimePipelineConfig, WsMetadata};
+use crate::dto::{RuntimeNodeConfig, RuntimePipelineConfig, WsMetadata};
+use crate::error::AppError;
 use crate::services::chat::{
-    build_metadata, execute_direct_chat, execute_ollama_stream,
-    execute_pipeline, runtime_to_pipeline_config, PipelineResult, StreamResult,
+    build_metadata, continue_pipeline, execute_direct_chat, execute_ollama_stream, execute_pipeline, replay_pipeline,
+    resume_pipeline, runtime_to_node_config, runtime_to_pipeline_config, trace_status, PipelineResult, StreamResult,
 };
 use crate::ServerState;
 
@@ -43,7 +48,102 @@ pub struct ChatRequest {
     pub system_prompt: Option<String>,
 }
 
+/// Request body for resuming a pipeline run from its checkpoint.
+#[derive(Debug, Deserialize)]
+pub struct ResumeRequest {
+    #[serde(default)]
+    pub model_id: Option<String>,
+    #[serde(default)]
+    pub node_models: HashMap<String, String>,
+    /// Edited node configs, each replacing the pipeline's node with the same ID.
+    #[serde(default)]
+    pub nodes: Vec<RuntimeNodeConfig>,
+}
+
+/// Request body for replaying a recorded pipeline run.
+#[derive(Debug, Deserialize)]
+pub struct ReplayRequest {
+    #[serde(default)]
+    pub model_id: Option<String>,
+    #[serde(default)]
+    pub node_models: HashMap<String, String>,
+    /// Edited node configs, each replacing the pipeline's node with the same ID
+    /// and running live.
+    #[serde(default)]
+    pub nodes: Vec<RuntimeNodeConfig>,
+    /// Further nodes that call providers and tools instead of replaying.
+    #[serde(default)]
+    pub live: Vec<String>,
+    /// Send calls without a recording to providers and tools instead of
+    /// failing the run.
+    #[serde(default)]
+    pub live_on_miss: bool,
+}
+
+/// Request body for deciding on a pending approval.
+#[derive(Debug, Deserialize)]
+pub struct DecisionRequest {
+    /// `{"action": "approve"}`, `{"action": "edit", "content": ...}`, or
+    /// `{"action": "reject", "reason": ...}`.
+    #[serde(flatten)]
+    pub decision: Decision,
+    #[serde(default)]
+    pub model_id: Option<String>,
+    #[serde(default)]
+    pub node_models: HashMap<String, String>,
+}
+
+/// A resumed run, validated before streaming starts.
+struct ResumeJob {
+    original: TraceRecord,
+    config: PipelineConfig,
+    model: ModelConfig,
+    node_models: HashMap<String, String>,
+}
+
+/// A suspended run continuing with a decision, validated before streaming starts.
+struct DecideJob {
+    suspended: TraceRecord,
+    node_id: String,
+    decision: Decision,
+    config: PipelineConfig,
+    model: ModelConfig,
+    node_models: HashMap<String, String>,
+}
+
+/// A replay of a recorded run, validated before streaming starts.
+struct ReplayJob {
+    input: String,
+    history: Vec<CoreMessage>,
+    replay: Replay,
+    config: PipelineConfig,
+    model: ModelConfig,
+    node_models: HashMap<String, String>,
+}
+
+/// Work whose output is streamed to the client.
+enum Job {
+    Chat(ChatRequest),
+    Resume(ResumeJob),
+    Decide(DecideJob),
+    Replay(ReplayJob),
+}
+
+impl Job {
+    async fn execute(self, tx: &EventSender, state: &ServerState, cancel: &CancellationToken) -> StreamResult {
+        match self {
+            Job::Chat(req) => execute_chat(tx, &req, state, cancel).await,
+            Job::Resume(job) => execute_resume(tx, &job, state, cancel).await,
+            Job::Decide(job) => execute_decide(tx, &job, state, cancel).await,
+            Job::Replay(job) => execute_replay(tx, job, state, cancel).await,
+        }
+    }
+}
+
 /// SSE event data types.
+///
+/// Pipeline runs additionally forward each [`fissio_engine::EngineEvent`] as its
+/// own SSE event (`node_started`, `node_finished`, `tool_call_started`, ...).
 #[derive(Debug, Serialize)]
 #[serde(tag = "type")]
 enum SseData {
@@ -71,11 +171,148 @@ pub async fn chat(
         req.message.get(..50).unwrap_or(&req.message)
     );
 
+    stream_job(state, Job::Chat(req))
+}
+
+/// POST /api/traces/:id/resume - Resume a pipeline run from its checkpoint,
+/// streaming the response like `/chat`.
+pub async fn resume(
+    State(state): State<Arc<ServerState>>,
+    Path(trace_id): Path<String>,
+    Json(req): Json<ResumeRequest>,
+) -> Result<Sse<impl Stream<Item = Result<Event, std::convert::Infallible>>>, AppError> {
+    let original = get_trace(&state, &trace_id)?;
+    let checkpoint = Checkpoint::load(state.trace_store.as_ref(), &trace_id)?
+        .ok_or_else(|| AppError::NotFound("trace has no checkpoint".into()))?;
+
+    let mut config = checkpoint.config;
+    apply_edits(&mut config, &req.nodes)?;
+
+    info!("Resuming trace {} ({} edited nodes)", trace_id, req.nodes.len());
+    let model = state.get_model(req.model_id.as_deref().unwrap_or(""));
+    let job = ResumeJob { original, config, model, node_models: req.node_models };
+    Ok(stream_job(state, Job::Resume(job)))
+}
+
+/// POST /api/traces/:id/replay - Re-run a recorded pipeline run, serving its
+/// recorded LLM and tool responses to every node except the edited and live
+/// ones, and stream the response like `/chat`.
+pub async fn replay(
+    State(state): State<Arc<ServerState>>,
+    Path(trace_id): Path<String>,
+    Json(req): Json<ReplayRequest>,
+) -> Result<Sse<impl Stream<Item = Result<Event, std::convert::Infallible>>>, AppError> {
+    get_trace(&state, &trace_id)?;
+    let checkpoint = Checkpoint::load(state.trace_store.as_ref(), &trace_id)?
+        .ok_or_else(|| AppError::NotFound("trace has no checkpoint".into()))?;
+
+    let mut config = checkpoint.config;
+    apply_edits(&mut config, &req.nodes)?;
+    let mut replay = Replay::load(state.trace_store.as_ref(), &trace_id)?;
+    for node_id in req.nodes.iter().map(|n| &n.id).chain(&req.live) {
+        replay = replay.live(node_id.clone());
+    }
+    if req.live_on_miss {
+        replay = replay.live_on_miss();
+    }
+
+    info!("Replaying trace {} ({} edited nodes, {} live)", trace_id, req.nodes.len(), req.live.len());
+    let model = state.get_model(req.model_id.as_deref().unwrap_or(""));
+    let job = ReplayJob {
+        input: checkpoint.input,
+        history: checkpoint.history,
+        replay,
+        config,
+        model,
+        node_models: req.node_models,
+    };
+    Ok(stream_job(state, Job::Replay(job)))
+}
+
+/// POST /api/traces/:id/approvals/:node_id - Decide on a pending approval,
+/// continuing the suspended run in the same trace and streaming it like `/chat`.
+pub async fn decide(
+    State(state): State<Arc<ServerState>>,
+    Path((trace_id, node_id)): Path<(String, String)>,
+    Json(req): Json<DecisionRequest>,
+) -> Result<Sse<impl Stream<Item = Result<Event, std::convert::Infallible>>>, AppError> {
+    let suspended = get_trace(&state, &trace_id)?;
+    if suspended.status != TraceStatus::AwaitingApproval {
+        return Err(AppError::BadRequest(format!("trace is {}, not awaiting approval", suspended.status.as_str())));
+    }
+    let checkpoint = Checkpoint::load(state.trace_store.as_ref(), &trace_id)?
+        .ok_or_else(|| AppError::NotFound("trace has no checkpoint".into()))?;
+    if !checkpoint.pending.iter().any(|a| a.node_id == node_id) {
+        return Err(AppError::NotFound(format!("no pending approval at node '{}'", node_id)));
+    }
+    // Concurrent decisions all pass the checks above; only one may continue the run
+    let claimed = state.trace_store.claim_suspended(&trace_id).map_err(|e| {
+        tracing::error!("Failed to claim trace: {}", e);
+        AppError::Internal("failed to claim trace".into())
+    })?;
+    if !claimed {
+        return Err(AppError::Conflict("trace is no longer awaiting approval".into()));
+    }
+
+    info!("Decision on trace {} at [{}]: {:?}", trace_id, node_id, req.decision);
+    let model = state.get_model(req.model_id.as_deref().unwrap_or(""));
+    let job = DecideJob {
+        suspended,
+        node_id,
+        decision: req.decision,
+        config: checkpoint.config,
+        model,
+        node_models: req.node_models,
+    };
+    Ok(stream_job(state, Job::Decide(job)))
+}
+
+/// Replaces the pipeline's nodes with their edited versions.
+fn apply_edits(config: &mut PipelineConfig, edits: &[RuntimeNodeConfig]) -> Result<(), AppError> {
+    for edited in edits {
+        let node = config.nodes.iter_mut()
+            .find(|n| n.id == edited.id)
+            .ok_or_else(|| AppError::BadRequest(format!("node '{}' not found in pipeline", edited.id)))?;
+        *node = runtime_to_node_config(edited);
+    }
+    Ok(())
+}
+
+fn get_trace(state: &ServerState, trace_id: &str) -> Result<TraceRecord, AppError> {
+    state
+        .trace_store
+        .get_trace(trace_id)
+        .map_err(|e| {
+            tracing::error!("Failed to get trace: {}", e);
+            AppError::Internal("failed to get trace".into())
+        })?
+        .ok_or_else(|| AppError::NotFound("trace not found".into()))
+}
+
+/// Runs `job` in the background, streaming its output as SSE and cancelling
+/// it if the client disconnects.
+fn stream_job(
+    state: Arc<ServerState>,
+    job: Job,
+) -> Sse<impl Stream<Item = Result<Event, std::convert::Infallible>>> {
     let (tx, rx) = mpsc::channel::<Result<Event, std::convert::Infallible>>(100);
 
     tokio::spawn(async move {
         let start = Instant::now();
-        let result = execute_chat(&tx, &req, &state).await;
+        let cancel = CancellationToken::new();
+
+        // The receiver is dropped when the client disconnects; cancel so the
+        // run stops instead of generating a response nobody will read
+        let disconnected = async {
+            tx.closed().await;
+            info!("Client disconnected, cancelling chat");
+            cancel.cancel();
+            std::future::pending().await
+        };
+        let result = tokio::select! {
+            result = job.execute(&tx, &state, &cancel) => result,
+            never = disconnected => never,
+        };
         let metadata = build_metadata(&result, start.elapsed().as_millis() as u64);
 
         let end_data = SseData::End { metadata };
@@ -94,58 +331,100 @@ async fn send_chunk(tx: &EventSender, content: &str) {
     }
 }
 
-/// Consumes a stream and sends chunks to the SSE channel.
-/// Returns (full_response, input_tokens, output_tokens).
-async fn stream_to_sse_with_response(tx: &EventSender, stream: fissio_llm::LlmStream) -> (String, u32, u32) {
-    let mut full_response = String::new();
-    let mut input_tokens = 0u32;
-    let mut output_tokens = 0u32;
-    let mut stream = stream;
+/// Forwards engine execution events to the SSE channel until every engine sender is dropped.
+async fn forward_engine_events(tx: EventSender, mut rx: EngineEventReceiver) {
+    while let Some(event) = rx.recv().await {
+        if let Ok(sse_event) = Event::default().event(event.name()).json_data(&event) {
+            let _ = tx.send(Ok(sse_event)).await;
+        }
+    }
+}
+
+/// A response streamed to the client.
+#[derive(Default)]
+struct StreamedResponse {
+    content: String,
+    input_tokens: u32,
+    output_tokens: u32,
+    /// The last error the stream yielded, or `Cancelled` if it was cut short.
+    error: Option<AgentError>,
+}
+
+impl StreamedResponse {
+    /// Finalizes the trace with the response, marking how the stream ended.
+    fn finalize(&self, collector: &TracingCollector) {
+        match &self.error {
+            Some(e) => collector.finalize(&self.content, trace_status(e)),
+            None => collector.success(&self.content),
+        }
+    }
+}
 
-    while let Some(chunk_result) = stream.next().await {
+/// Consumes a stream and sends chunks to the SSE channel until it ends or
+/// `cancel` is cancelled.
+async fn stream_to_sse_with_response(
+    tx: &EventSender,
+    mut stream: fissio_llm::LlmStream,
+    cancel: &CancellationToken,
+) -> StreamedResponse {
+    let mut response = StreamedResponse::default();
+
+    loop {
+        let chunk_result = tokio::select! {
+            biased;
+            _ = cancel.cancelled() => {
+                response.error = Some(AgentError::Cancelled);
+                break;
+            }
+            chunk_result = stream.next() => match chunk_result {
+                Some(chunk_result) => chunk_result,
+                None => break,
+            },
+        };
         match chunk_result {
             Ok(fissio_llm::StreamChunk::Content(chunk)) => {
-                full_response.push_str(&chunk);
+                response.content.push_str(&chunk);
                 send_chunk(tx, &chunk).await;
             }
             Ok(fissio_llm::StreamChunk::Usage { input_tokens: i, output_tokens: o }) => {
-                input_tokens = i;
-                output_tokens = o;
+                response.input_tokens = i;
+                response.output_tokens = o;
             }
             Err(e) => {
                 error!("Stream error: {}", e);
+                response.error = Some(e);
             }
         }
     }
 
-    (full_response, input_tokens, output_tokens)
+    response
 }
 
-async fn execute_chat(tx: &EventSender, req: &ChatRequest, state: &ServerState) -> StreamResult {
+async fn execute_chat(tx: &EventSender, req: &ChatRequest, state: &ServerState, cancel: &CancellationToken) -> StreamResult {
     let model_id = req.model_id.as_deref().unwrap_or("");
     let model = state.get_model(model_id);
     let system_prompt = req.system_prompt.as_deref().unwrap_or(DEFAULT_SYSTEM_PROMPT);
 
     // Use native Ollama API for local models (provides rich metrics)
     if model.api_base.is_some() {
-        return execute_ollama_chat(tx, &model, &req.history, &req.message, system_prompt, state).await;
+        return execute_ollama_chat(tx, &model, &req.history, &req.message, system_prompt, state, cancel).await;
     }
 
     // Runtime pipeline config from frontend
     if let Some(ref runtime_config) = req.pipeline_config {
         let config = runtime_to_pipeline_config(runtime_config);
         info!("Using runtime pipeline config ({} nodes)", config.nodes.len());
-        return execute_pipeline_chat(tx, &config, &req.message, &req.history, state, &model, req.node_models.clone()).await;
+        return execute_pipeline_chat(tx, &config, &req.message, &req.history, state, &model, req.node_models.clone(), cancel).await;
     }
 
     // Preset pipeline by ID
     if let Some(config) = req.pipeline_id.as_deref().and_then(|id| state.presets.get(id)) {
         info!("Using pipeline preset: {}", config.name);
-        return execute_pipeline_chat(tx, config, &req.message, &req.history, state, &model, req.node_models.clone()).await;
+        return execute_pipeline_chat(tx, config, &req.message, &req.history, state, &model, req.node_models.clone(), cancel).await;
     }
 
     // Direct chat
-    execute_direct(tx, &model, &req.history, &req.message, system_prompt, state).await
+    execute_direct(tx, &model, &req.history, &req.message, system_prompt, state, cancel).await
 }
 
 async fn execute_ollama_chat(
@@ -155,6 +434,7 @@ async fn execute_ollama_chat(
     message: &str,
     system_prompt: &str,
     state: &ServerState,
+    cancel: &CancellationToken,
 ) -> StreamResult {
     let collector = TracingCollector::new(
         state.trace_store.clone(),
@@ -169,7 +449,8 @@ async fn execute_ollama_chat(
 
     match execute_ollama_stream(model, history, message, system_prompt).await {
         Ok((stream, metrics)) => {
-            let (response, input_tokens, output_tokens) = stream_to_sse_with_response(tx, stream).await;
+            let response = stream_to_sse_with_response(tx, stream, cancel).await;
+            let (input_tokens, output_tokens) = (response.input_tokens, response.output_tokens);
             let end_time = std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .map(|d| d.as_millis() as i64)
@@ -183,10 +464,14 @@ async fn execute_ollama_chat(
                 tool_call_count: 0,
                 iteration_count: 1,
                 estimated_cost_usd: None,
+                score: None,
+                attempt: 1,
+                model: None,
+                error: None,
             };
             collector.record(node_metrics.clone());
-            collector.record_span("llm", "llm", start_time, end_time, message, &response, &node_metrics);
-            collector.success(&response);
+            collector.record_span("llm", "llm", start_time, end_time, message, &response.content, &node_metrics);
+            response.finalize(&collector);
 
             info!("Direct chat: {}ms, tokens: {}/{}", end_time - start_time, input_tokens, output_tokens);
             StreamResult { input_tokens, output_tokens, ollama_metrics: Some(metrics) }
@@ -207,6 +492,7 @@ async fn execute_direct(
     message: &str,
     system_prompt: &str,
     state: &ServerState,
+    cancel: &CancellationToken,
 ) -> StreamResult {
     let collector = TracingCollector::new(
         state.trace_store.clone(),
@@ -221,7 +507,8 @@ async fn execute_direct(
 
     match execute_direct_chat(model, history, message, system_prompt).await {
         Ok(stream) => {
-            let (response, input_tokens, output_tokens) = stream_to_sse_with_response(tx, stream).await;
+            let response = stream_to_sse_with_response(tx, stream, cancel).await;
+            let (input_tokens, output_tokens) = (response.input_tokens, response.output_tokens);
             let end_time = std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .map(|d| d.as_millis() as i64)
@@ -235,10 +522,14 @@ async fn execute_direct(
                 tool_call_count: 0,
                 iteration_count: 1,
                 estimated_cost_usd: None,
+                score: None,
+                attempt: 1,
+                model: None,
+                error: None,
             };
             collector.record(node_metrics.clone());
-            collector.record_span("llm", "llm", start_time, end_time, message, &response, &node_metrics);
-            collector.success(&response);
+            collector.record_span("llm", "llm", start_time, end_time, message, &response.content, &node_metrics);
+            response.finalize(&collector);
 
             info!("Direct chat: {}ms, tokens: {}/{}", end_time - start_time, input_tokens, output_tokens);
             StreamResult { input_tokens, output_tokens, ollama_metrics: None }
@@ -252,6 +543,7 @@ async fn execute_direct(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn execute_pipeline_chat(
     tx: &EventSender,
     config: &fissio_config::PipelineConfig,
@@ -260,22 +552,83 @@ async fn execute_pipeline_chat(
     state: &ServerState,
     default_model: &fissio_core::ModelConfig,
     node_overrides: HashMap<String, String>,
+    cancel: &CancellationToken,
 ) -> StreamResult {
-    let trace_store = Some(state.trace_store.clone());
+    let (events_tx, events_rx) = event_channel();
+    let forwarder = tokio::spawn(forward_engine_events(tx.clone(), events_rx));
+
+    let executed = execute_pipeline(config, message, history, state, default_model, node_overrides, events_tx, cancel.clone()).await;
+    let result = send_pipeline_result(tx, executed, cancel).await;
+
+    // The engine and its output stream are dropped by now, so the forwarder
+    // drains the remaining events and exits before the `end` event is sent
+    let _ = forwarder.await;
+    result
+}
+
+async fn execute_resume(tx: &EventSender, job: &ResumeJob, state: &ServerState, cancel: &CancellationToken) -> StreamResult {
+    let (events_tx, events_rx) = event_channel();
+    let forwarder = tokio::spawn(forward_engine_events(tx.clone(), events_rx));
+
+    let executed = resume_pipeline(
+        &job.config, &job.original, state, &job.model, job.node_models.clone(), events_tx, cancel.clone(),
+    ).await;
+    let result = send_pipeline_result(tx, executed, cancel).await;
+
+    let _ = forwarder.await;
+    result
+}
+
+async fn execute_decide(tx: &EventSender, job: &DecideJob, state: &ServerState, cancel: &CancellationToken) -> StreamResult {
+    let (events_tx, events_rx) = event_channel();
+    let forwarder = tokio::spawn(forward_engine_events(tx.clone(), events_rx));
+
+    let executed = continue_pipeline(
+        &job.config, &job.suspended, &job.node_id, job.decision.clone(), state, &job.model,
+        job.node_models.clone(), events_tx, cancel.clone(),
+    ).await;
+    let result = send_pipeline_result(tx, executed, cancel).await;
+
+    let _ = forwarder.await;
+    result
+}
 
-    match execute_pipeline(config, message, history, &state.models, default_model, node_overrides, trace_store).await {
+async fn execute_replay(tx: &EventSender, job: ReplayJob, state: &ServerState, cancel: &CancellationToken) -> StreamResult {
+    let (events_tx, events_rx) = event_channel();
+    let forwarder = tokio::spawn(forward_engine_events(tx.clone(), events_rx));
+
+    let ReplayJob { input, history, replay, config, model, node_models } = job;
+    let executed = replay_pipeline(
+        &config, &input, &history, replay, state, &model, node_models, events_tx, cancel.clone(),
+    ).await;
+    let result = send_pipeline_result(tx, executed, cancel).await;
+
+    let _ = forwarder.await;
+    result
+}
+
+/// Sends a pipeline's output to the client and finalizes its trace.
+async fn send_pipeline_result(
+    tx: &EventSender,
+    executed: Result<PipelineResult, String>,
+    cancel: &CancellationToken,
+) -> StreamResult {
+    match executed {
         Ok(PipelineResult { output: EngineOutput::Stream(stream), collector }) => {
-            let (response, input_tokens, output_tokens) = stream_to_sse_with_response(tx, stream).await;
-            if let Some(coll) = collector {
-                coll.success(&response);
-            }
-            StreamResult { input_tokens, output_tokens, ollama_metrics: None }
+            let response = stream_to_sse_with_response(tx, stream, cancel).await;
+            response.finalize(&collector);
+            StreamResult { input_tokens: response.input_tokens, output_tokens: response.output_tokens, ollama_metrics: None }
         }
         Ok(PipelineResult { output: EngineOutput::Complete(response), collector }) => {
             send_chunk(tx, &response).await;
-            if let Some(coll) = collector {
-                coll.success(&response);
-            }
+            collector.success(&response);
+            StreamResult { input_tokens: 0, output_tokens: 0, ollama_metrics: None }
+        }
+        Ok(PipelineResult { output: EngineOutput::Suspended(approvals), collector }) => {
+            let nodes: Vec<&str> = approvals.iter().map(|a| a.node_id.as_str()).collect();
+            let message = format!("Awaiting approval at {}.", nodes.join(", "));
+            send_chunk(tx, &message).await;
+            collector.finalize(&message, TraceStatus::AwaitingApproval);
             StreamResult { input_tokens: 0, output_tokens: 0, ollama_metrics: None }
         }
         Err(e) => {
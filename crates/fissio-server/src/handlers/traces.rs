@@ -4,6 +4,7 @@ use std::sync::Arc;
 
 use axum::extract::{Path, Query, State};
 use axum::Json;
+use fissio_engine::{Checkpoint, PendingApproval};
 use fissio_monitor::{SpanRecord, TraceQuery, TraceRecord, TraceStatus};
 use serde::{Deserialize, Serialize};
 
@@ -23,6 +24,19 @@ pub struct TraceDetailResponse {
     pub spans: Vec<SpanRecord>,
 }
 
+/// A suspended run with the approvals it's waiting on.
+#[derive(Serialize)]
+pub struct SuspendedRun {
+    pub trace: TraceRecord,
+    pub approvals: Vec<PendingApproval>,
+}
+
+/// Response for listing pending approvals.
+#[derive(Serialize)]
+pub struct ApprovalsListResponse {
+    pub runs: Vec<SuspendedRun>,
+}
+
 /// Query parameters for listing traces.
 #[derive(Debug, Deserialize, Default)]
 pub struct ListTracesQuery {
@@ -52,6 +66,26 @@ pub async fn list(
     Ok(Json(TracesListResponse { traces }))
 }
 
+/// GET /api/approvals - List runs awaiting approval, newest first.
+pub async fn approvals(
+    State(state): State<Arc<ServerState>>,
+) -> Result<Json<ApprovalsListResponse>, AppError> {
+    let query = TraceQuery { status: Some(TraceStatus::AwaitingApproval), ..Default::default() };
+    let traces = state.trace_store.list_traces(&query).map_err(|e| {
+        tracing::error!("Failed to list traces: {}", e);
+        AppError::Internal("failed to list traces".into())
+    })?;
+
+    let mut runs = Vec::new();
+    for trace in traces {
+        if let Some(checkpoint) = Checkpoint::load(state.trace_store.as_ref(), &trace.trace_id)? {
+            runs.push(SuspendedRun { trace, approvals: checkpoint.pending });
+        }
+    }
+
+    Ok(Json(ApprovalsListResponse { runs }))
+}
+
 /// GET /api/traces/:id - Get a single trace with its spans.
 pub async fn get(
     State(state): State<Arc<ServerState>>,
@@ -15,8 +15,9 @@ use std::time::Duration;
 
 use tokio::sync::RwLock;
 
-use fissio_config::PresetRegistry;
+use fissio_config::{PresetRegistry, RateLimits};
 use fissio_core::ModelConfig;
+use fissio_engine::RateLimiter;
 use fissio_llm::discover_models;
 use fissio_monitor::TraceStore;
 use fissio_tools::ToolRegistry;
@@ -78,6 +79,12 @@ pub struct ServerState {
     pub db: Mutex<rusqlite::Connection>,
     pub tool_registry: ToolRegistry,
     pub trace_store: Arc<TraceStore>,
+    /// Concurrency and token budgets shared by every pipeline run.
+    pub rate_limiter: Arc<RateLimiter>,
+    /// Whether every pipeline run is checkpointed and has its calls recorded,
+    /// so it can be resumed or replayed. Runs with approval nodes are always
+    /// checkpointed.
+    pub record_runs: bool,
 }
 
 impl ServerState {
@@ -149,6 +156,10 @@ async fn main() -> Result<()> {
         .route("/api/traces", get(handlers::traces::list))
         .route("/api/traces/{id}", get(handlers::traces::get))
         .route("/api/traces/{id}", axum::routing::delete(handlers::traces::delete))
+        .route("/api/traces/{id}/resume", post(handlers::chat::resume))
+        .route("/api/traces/{id}/replay", post(handlers::chat::replay))
+        .route("/api/traces/{id}/approvals/{node_id}", post(handlers::chat::decide))
+        .route("/api/approvals", get(handlers::traces::approvals))
         .route("/api/metrics/summary", get(handlers::traces::metrics_summary))
         .layer(trace_layer);
 
@@ -205,6 +216,11 @@ async fn init_server_state() -> ServerState {
                 model: n.model.clone(),
                 prompt: n.prompt.clone(),
                 tools: if n.tools.is_empty() { None } else { Some(n.tools.clone()) },
+                config: if n.config.is_null() { None } else { Some(n.config.clone()) },
+                retry: n.retry.clone(),
+                fallback_models: if n.fallback_models.is_empty() { None } else { Some(n.fallback_models.clone()) },
+                timeout_ms: n.timeout_ms,
+                output_schema: n.output_schema.clone(),
                 x: None,
                 y: None,
             }).collect(),
@@ -216,8 +232,15 @@ async fn init_server_state() -> ServerState {
                 } else {
                     Some(e.edge_type.to_string())
                 },
+                max_iterations: e.max_iterations,
+                on_failure: e.on_failure,
+                when: e.when.clone(),
+                optional: e.optional,
             }).collect(),
             layout: None,
+            variables: p.variables.clone(),
+            timeout_ms: p.timeout_ms,
+            max_concurrency: p.max_concurrency,
         })
         .collect();
 
@@ -239,6 +262,24 @@ async fn init_server_state() -> ServerState {
     let trace_store = Arc::new(TraceStore::new(&trace_db_path).expect("failed to initialize trace store"));
     info!("Trace store initialized at {}", trace_db_path);
 
+    let rate_limits_path = std::env::var("RATE_LIMITS").unwrap_or_else(|_| "rate_limits.json".into());
+    let rate_limits = if Path::new(&rate_limits_path).exists() {
+        RateLimits::from_file(&rate_limits_path).unwrap_or_else(|e| {
+            warn!("Failed to load rate limits: {}", e);
+            RateLimits::default()
+        })
+    } else {
+        RateLimits::default()
+    };
+    info!(
+        "Rate limits: {} models, {} providers",
+        rate_limits.models.len(),
+        rate_limits.providers.len()
+    );
+
+    let record_runs = std::env::var("RECORD_RUNS").is_ok_and(|v| matches!(v.as_str(), "1" | "true"));
+    info!("Run checkpoints and call recording: {}", if record_runs { "on" } else { "off" });
+
     ServerState {
         models,
         presets,
@@ -247,5 +288,7 @@ async fn init_server_state() -> ServerState {
         db: Mutex::new(conn),
         tool_registry,
         trace_store,
+        rate_limiter: Arc::new(RateLimiter::new(&rate_limits)),
+        record_runs,
     }
 }
@@ -3,15 +3,15 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use fissio_config::{EdgeConfig, EdgeEndpoint, EdgeType, NodeConfig, NodeType, PipelineConfig};
-use fissio_core::{Message as CoreMessage, ModelConfig};
-use fissio_engine::{EngineOutput, PipelineEngine};
-use fissio_llm::{LlmStream, OllamaClient, OllamaMetrics, StreamChunk, UnifiedLlmClient};
-use fissio_monitor::{ObserveConfig, TraceStore, TracingCollector};
-use futures::StreamExt;
-use tracing::{error, info};
+use fissio_config::{EdgeConfig, EdgeEndpoint, EdgeType, NodeConfig, NodeType, PipelineConfig, PresetRegistry};
+use fissio_core::{AgentError, Message as CoreMessage, ModelConfig};
+use fissio_engine::{CancellationToken, Decision, EngineOutput, EventSender as EngineEventSender, PipelineEngine, Replay};
+use fissio_llm::{LlmStream, OllamaClient, OllamaMetrics, UnifiedLlmClient};
+use fissio_monitor::{ObserveConfig, TraceRecord, TraceStatus, TracingCollector};
+use tracing::info;
 
-use crate::dto::{RuntimePipelineConfig, WsMetadata};
+use crate::dto::{PipelineInfo, RuntimeEdgeConfig, RuntimeNodeConfig, RuntimePipelineConfig, WsMetadata};
+use crate::ServerState;
 
 /// Result of a streaming chat operation.
 pub struct StreamResult {
@@ -20,17 +20,26 @@ pub struct StreamResult {
     pub ollama_metrics: Option<OllamaMetrics>,
 }
 
-/// Converts a runtime config from the frontend to a PipelineConfig.
-pub fn runtime_to_pipeline_config(runtime: &RuntimePipelineConfig) -> PipelineConfig {
-    let nodes = runtime.nodes.iter().map(|n| NodeConfig {
+/// Converts a runtime node config from the frontend to a NodeConfig.
+pub fn runtime_to_node_config(n: &RuntimeNodeConfig) -> NodeConfig {
+    NodeConfig {
         id: n.id.clone(),
         node_type: n.node_type.parse().unwrap_or(NodeType::Llm),
         model: n.model.clone(),
-        config: serde_json::Value::Null,
+        config: n.config.clone().unwrap_or_default(),
         prompt: n.prompt.clone(),
         tools: n.tools.clone().unwrap_or_default(),
         observe: Some(ObserveConfig::new()),
-    }).collect();
+        retry: n.retry.clone(),
+        fallback_models: n.fallback_models.clone().unwrap_or_default(),
+        timeout_ms: n.timeout_ms,
+        output_schema: n.output_schema.clone(),
+    }
+}
+
+/// Converts a runtime config from the frontend to a PipelineConfig.
+pub fn runtime_to_pipeline_config(runtime: &RuntimePipelineConfig) -> PipelineConfig {
+    let nodes = runtime.nodes.iter().map(runtime_to_node_config).collect();
 
     let edges = runtime.edges.iter().map(|e| EdgeConfig {
         from: EdgeEndpoint::from(&e.from),
@@ -38,6 +47,10 @@ pub fn runtime_to_pipeline_config(runtime: &RuntimePipelineConfig) -> PipelineCo
         edge_type: e.edge_type.as_deref()
             .and_then(|t| t.parse().ok())
             .unwrap_or(EdgeType::Direct),
+        max_iterations: e.max_iterations,
+        on_failure: e.on_failure,
+        when: e.when.clone(),
+        optional: e.optional,
     }).collect();
 
     PipelineConfig {
@@ -46,9 +59,36 @@ pub fn runtime_to_pipeline_config(runtime: &RuntimePipelineConfig) -> PipelineCo
         description: String::new(),
         nodes,
         edges,
+        variables: runtime.variables.clone(),
+        timeout_ms: runtime.timeout_ms,
+        max_concurrency: runtime.max_concurrency,
     }
 }
 
+/// Converts a saved pipeline to a PipelineConfig, the same way as a runtime config.
+pub fn saved_to_pipeline_config(info: &PipelineInfo) -> PipelineConfig {
+    let runtime = RuntimePipelineConfig {
+        id: Some(info.id.clone()),
+        name: Some(info.name.clone()),
+        nodes: info.nodes.iter().map(RuntimeNodeConfig::from).collect(),
+        edges: info.edges.iter().map(RuntimeEdgeConfig::from).collect(),
+        variables: info.variables.clone(),
+        timeout_ms: info.timeout_ms,
+        max_concurrency: info.max_concurrency,
+    };
+    PipelineConfig { description: info.description.clone(), ..runtime_to_pipeline_config(&runtime) }
+}
+
+/// Returns the pipelines `pipeline` nodes can run: presets, then saved
+/// configs, which take precedence on a shared ID.
+async fn pipeline_library(state: &ServerState) -> Arc<PresetRegistry> {
+    let mut pipelines = state.presets.clone();
+    for info in state.configs.read().await.iter() {
+        pipelines.register(saved_to_pipeline_config(info));
+    }
+    Arc::new(pipelines)
+}
+
 /// Executes a streaming chat with Ollama native API (for verbose metrics).
 pub async fn execute_ollama_stream(
     model: &ModelConfig,
@@ -82,74 +122,170 @@ pub async fn execute_direct_chat(
         .map_err(|e| e.to_string())
 }
 
-/// Result of pipeline execution with optional tracing collector.
+/// Result of pipeline execution with its tracing collector.
 pub struct PipelineResult {
     pub output: EngineOutput,
-    pub collector: Option<Arc<TracingCollector>>,
+    pub collector: Arc<TracingCollector>,
+}
+
+/// Maps an execution error to the status its trace is finalized with.
+pub fn trace_status(error: &AgentError) -> TraceStatus {
+    match error {
+        AgentError::Cancelled => TraceStatus::Cancelled,
+        AgentError::Timeout(_) => TraceStatus::TimedOut,
+        _ => TraceStatus::Error,
+    }
 }
 
 /// Executes a pipeline and returns the output stream.
+///
+/// Execution events are sent to `events` while the pipeline runs, and the run
+/// stops early once `cancel` is cancelled. If the pipeline fails before
+/// producing output, its trace is finalized here.
+#[allow(clippy::too_many_arguments)]
 pub async fn execute_pipeline(
     config: &PipelineConfig,
     message: &str,
     history: &[CoreMessage],
-    models: &[ModelConfig],
+    state: &ServerState,
     default_model: &ModelConfig,
     node_overrides: HashMap<String, String>,
-    trace_store: Option<Arc<TraceStore>>,
+    events: EngineEventSender,
+    cancel: CancellationToken,
 ) -> Result<PipelineResult, String> {
-    let collector = trace_store.map(|store| {
-        Arc::new(TracingCollector::new(
-            store,
-            &config.id,
-            &config.name,
-            message,
-        ))
-    });
-
-    let mut engine = PipelineEngine::new(
-        config.clone(),
-        models.to_vec(),
-        default_model.clone(),
-        node_overrides,
-    );
+    let collector = Arc::new(TracingCollector::new(
+        state.trace_store.clone(),
+        &config.id,
+        &config.name,
+        message,
+    ));
 
-    if let Some(ref coll) = collector {
-        engine = engine.with_collector(coll.clone());
-    }
+    let engine = pipeline_engine(config, state, default_model, node_overrides, events, &collector).await;
+    let output = engine.execute_stream_with_cancel(message, history, cancel).await;
+    finish_pipeline(output, collector)
+}
 
-    let output = engine
-        .execute_stream(message, history)
+/// Resumes the run traced as `original` from its checkpoint, executing `config`.
+///
+/// The resumed run gets its own trace, linked to the original, and is
+/// checkpointed under it in turn, so it can be resumed again.
+pub async fn resume_pipeline(
+    config: &PipelineConfig,
+    original: &TraceRecord,
+    state: &ServerState,
+    default_model: &ModelConfig,
+    node_overrides: HashMap<String, String>,
+    events: EngineEventSender,
+    cancel: CancellationToken,
+) -> Result<PipelineResult, String> {
+    let collector = Arc::new(TracingCollector::resuming(state.trace_store.clone(), original));
+
+    let engine = pipeline_engine(config, state, default_model, node_overrides, events, &collector)
         .await
-        .map_err(|e| e.to_string())?;
+        .with_checkpoints(state.trace_store.clone(), collector.trace_id());
+    let output = engine.resume(&original.trace_id, cancel).await;
+    finish_pipeline(output, collector)
+}
 
-    Ok(PipelineResult { output, collector })
+/// Continues the run traced as `suspended` with a decision on its approval
+/// node `node_id`, adding to the same trace.
+#[allow(clippy::too_many_arguments)]
+pub async fn continue_pipeline(
+    config: &PipelineConfig,
+    suspended: &TraceRecord,
+    node_id: &str,
+    decision: Decision,
+    state: &ServerState,
+    default_model: &ModelConfig,
+    node_overrides: HashMap<String, String>,
+    events: EngineEventSender,
+    cancel: CancellationToken,
+) -> Result<PipelineResult, String> {
+    let collector = Arc::new(TracingCollector::continuing(state.trace_store.clone(), suspended));
+
+    let engine = pipeline_engine(config, state, default_model, node_overrides, events, &collector).await;
+    let output = engine.decide(node_id, decision, cancel).await;
+    finish_pipeline(output, collector)
 }
 
-/// Consumes an LLM stream, calling the sender for each content chunk.
-/// Returns token counts.
-pub async fn consume_stream<F>(mut stream: LlmStream, on_chunk: F) -> (u32, u32)
-where
-    F: Fn(&str),
-{
-    let mut input_tokens = 0u32;
-    let mut output_tokens = 0u32;
+/// Re-runs `message` through `config` as a new traced run, serving calls
+/// from `replay` instead of providers and tools where it has them.
+#[allow(clippy::too_many_arguments)]
+pub async fn replay_pipeline(
+    config: &PipelineConfig,
+    message: &str,
+    history: &[CoreMessage],
+    replay: Replay,
+    state: &ServerState,
+    default_model: &ModelConfig,
+    node_overrides: HashMap<String, String>,
+    events: EngineEventSender,
+    cancel: CancellationToken,
+) -> Result<PipelineResult, String> {
+    let collector = Arc::new(TracingCollector::new(
+        state.trace_store.clone(),
+        &config.id,
+        &config.name,
+        message,
+    ));
 
-    while let Some(chunk_result) = stream.next().await {
-        match chunk_result {
-            Ok(StreamChunk::Content(chunk)) => on_chunk(&chunk),
-            Ok(StreamChunk::Usage { input_tokens: i, output_tokens: o }) => {
-                input_tokens = i;
-                output_tokens = o;
-            }
-            Err(e) => {
-                error!("Stream error: {}", e);
-                break;
-            }
-        }
+    let engine = pipeline_engine(config, state, default_model, node_overrides, events, &collector)
+        .await
+        .with_replay(replay);
+    let output = engine.execute_stream_with_cancel(message, history, cancel).await;
+    finish_pipeline(output, collector)
+}
+
+/// Builds an engine that traces its run under the collector's trace ID, and
+/// can run saved and preset pipelines as nested runs.
+///
+/// The run is checkpointed under the trace ID if the server records runs or
+/// the pipeline has approval nodes, which suspend it; its calls are recorded
+/// only if the server records runs.
+async fn pipeline_engine(
+    config: &PipelineConfig,
+    state: &ServerState,
+    default_model: &ModelConfig,
+    node_overrides: HashMap<String, String>,
+    events: EngineEventSender,
+    collector: &Arc<TracingCollector>,
+) -> PipelineEngine {
+    let engine = PipelineEngine::new(
+        config.clone(),
+        state.models.clone(),
+        default_model.clone(),
+        node_overrides,
+    )
+    .with_pipelines(pipeline_library(state).await)
+    .with_collector(collector.clone())
+    .with_events(events)
+    .with_rate_limiter(state.rate_limiter.clone());
+
+    let approvals = config.nodes.iter().any(|n| n.node_type == NodeType::Approval);
+    let engine = if state.record_runs || approvals {
+        engine.with_checkpoints(state.trace_store.clone(), collector.trace_id())
+    } else {
+        engine
+    };
+    if state.record_runs {
+        engine.with_recording(state.trace_store.clone(), collector.trace_id())
+    } else {
+        engine
     }
+}
 
-    (input_tokens, output_tokens)
+/// Pairs a run's output with its collector, finalizing the trace if the run failed.
+fn finish_pipeline(
+    output: Result<EngineOutput, AgentError>,
+    collector: Arc<TracingCollector>,
+) -> Result<PipelineResult, String> {
+    match output {
+        Ok(output) => Ok(PipelineResult { output, collector }),
+        Err(e) => {
+            collector.finalize(&e.to_string(), trace_status(&e));
+            Err(e.to_string())
+        }
+    }
 }
 
 /// Builds metadata from stream result.
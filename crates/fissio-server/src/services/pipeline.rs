@@ -22,6 +22,9 @@ pub async fn save_pipeline(state: &ServerState, req: &SavePipelineRequest) -> Re
         nodes: req.nodes.clone(),
         edges: req.edges.clone(),
         layout: req.layout.clone(),
+        variables: req.variables.clone(),
+        timeout_ms: req.timeout_ms,
+        max_concurrency: req.max_concurrency,
     };
 
     // Update in-memory cache
@@ -41,6 +41,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             // For streaming, you would consume the stream
             println!("(Streaming not shown in this example)");
         }
+        EngineOutput::Suspended(approvals) => {
+            // Only pipelines with approval nodes suspend
+            println!("Awaiting approval at {} node(s)", approvals.len());
+        }
     }
 
     Ok(())
@@ -56,6 +56,7 @@
 //! - `Aggregator` — Combines multiple inputs
 //! - `Orchestrator` — Dynamic task decomposition
 //! - `Evaluator` — Quality scoring
+//! - `Custom` — User-defined executor registered with the engine
 //!
 //! ## Edge Types
 //!
@@ -66,8 +67,8 @@
 
 // Re-export config types
 pub use fissio_config::{
-    ConfigError, EdgeConfig, EdgeEndpoint, EdgeType, NodeConfig, NodeType, PipelineConfig,
-    PresetRegistry,
+    Condition, ConfigError, EdgeConfig, EdgeEndpoint, EdgeType, FailurePolicy, NodeConfig, NodeType,
+    PipelineConfig, PresetRegistry, RateLimit, RateLimits,
 };
 
 // Re-export builders
@@ -77,7 +78,12 @@ pub use fissio_config::{NodeBuilder, PipelineBuilder};
 pub use fissio_core::{AgentError, Message, MessageRole, ModelConfig};
 
 // Re-export engine
-pub use fissio_engine::{EngineOutput, ModelResolver, NodeInput, NodeOutput, PipelineEngine};
+pub use fissio_engine::{
+    event_channel, CancellationToken, Checkpoint, Decision, EngineEvent, EngineOutput, EventReceiver, EventSender,
+    LlmProvider, LlmRequest, MockLlm, ModelResolver, NodeContext, NodeExecutor, NodeInput, NodeOutput, NodeRecord,
+    NodeRegistry, NodeStatus, NodeUsage, PendingApproval, PipelineEngine, RateLimiter, Replay, RunContext, RunResult,
+    UnifiedProvider,
+};
 
 // Re-export LLM clients
 pub use fissio_llm::{
@@ -115,7 +121,7 @@ pub mod prelude {
     pub use crate::{EdgeConfig, EdgeEndpoint, EdgeType, NodeConfig, NodeType, PipelineConfig};
 
     // Engine
-    pub use crate::{EngineOutput, PipelineEngine};
+    pub use crate::{EngineEvent, EngineOutput, NodeExecutor, NodeOutput, NodeRegistry, PipelineEngine};
 
     // LLM
     pub use crate::{ChatResponse, LlmResponse, LlmStream, StreamChunk, UnifiedLlmClient};